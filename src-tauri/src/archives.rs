@@ -0,0 +1,342 @@
+use crate::disk;
+use crate::error::CommandError;
+use crate::relocation::Copier;
+use crate::{GameEntry, InstallStatus, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::Deserialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum ArchiveAction {
+    /// Sends the archive to the recycle bin.
+    Delete,
+    /// Moves the archive into the folder `destination`.
+    Move { destination: String },
+}
+
+/// Deletes or moves `id`'s archive together with the other volumes of a
+/// split archive, such as `game.part2.rar` next to `game.part1.rar`.
+/// Deleting sends the files to the recycle bin, clears `archivePath`, measures
+/// the size from the install folder and leaves the game `Installed` when it
+/// has one. Moving renames the files when possible and otherwise copies
+/// them, checks the copies and removes the originals, reporting
+/// `move-progress`.
+#[tauri::command]
+pub async fn manage_archive(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+    action: ArchiveAction,
+) -> Result<GameEntry, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == id)
+        .ok_or_else(|| CommandError::game_not_found(&id))?;
+    if game.download_id.is_some() {
+        return Err(CommandError::conflict(format!(
+            "{} is still downloading",
+            game.title
+        )));
+    }
+    let archive = game
+        .archive_path
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|path| path.is_file())
+        .ok_or_else(|| CommandError::not_found(format!("{} has no archive", game.title)))?;
+    let parts = archive_parts(&archive);
+
+    let worker_app = app.clone();
+    let game_id = id.clone();
+    let change = tauri::async_runtime::spawn_blocking(move || match action {
+        ArchiveAction::Delete => delete_parts(&parts).map(|()| {
+            let install = game.install_path.as_deref().map(Path::new);
+            let size_bytes = install
+                .filter(|path| path.exists())
+                .and_then(|path| crate::compute_path_size(path).ok());
+            (None, size_bytes)
+        }),
+        ArchiveAction::Move { destination } => {
+            move_parts(&worker_app, &game_id, &parts, Path::new(destination.trim())).map(|dir| {
+                let moved = archive.file_name().map(|name| dir.join(name));
+                (moved, game.size_bytes)
+            })
+        }
+    })
+    .await??;
+
+    let (archive_path, size_bytes) = change;
+    let updated = library
+        .update(&app, |games| {
+            let game = games
+                .iter_mut()
+                .find(|game| game.id == id)
+                .ok_or_else(|| CommandError::game_not_found(&id))?;
+            if archive_path.is_none() {
+                let installed = game
+                    .install_path
+                    .as_deref()
+                    .is_some_and(|path| Path::new(path).exists());
+                game.status = if installed {
+                    InstallStatus::Installed
+                } else {
+                    InstallStatus::NotInstalled
+                };
+            }
+            game.archive_path = archive_path.map(|path| path.to_string_lossy().into_owned());
+            game.size_bytes = size_bytes;
+            game.updated_at = Utc::now();
+            Ok(game.clone())
+        })
+        .context("Failed to save library")?;
+    crate::emit_library_updated(&app, vec![id]);
+    Ok(updated)
+}
+
+/// The files making up the archive at `archive`: every volume beside it
+/// named like it when it is split, otherwise just the archive itself. Sorted
+/// by name.
+pub fn archive_parts(archive: &Path) -> Vec<PathBuf> {
+    let key = archive
+        .file_name()
+        .and_then(|name| set_key(&name.to_string_lossy()));
+    let (Some(key), Some(dir)) = (key, archive.parent()) else {
+        return vec![archive.to_path_buf()];
+    };
+    let mut parts: Vec<PathBuf> = fs::read_dir(dir)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| set_key(&name.to_string_lossy()))
+                .is_some_and(|other| other == key)
+        })
+        .collect();
+    if !parts.iter().any(|part| part == archive) {
+        parts.push(archive.to_path_buf());
+    }
+    parts.sort();
+    parts
+}
+
+/// What the volumes of a split archive have in common: the name with the
+/// volume number replaced. `None` for names that cannot be a volume.
+/// Covers `game.part1.rar`, `game.rar` with `game.r00`, `game.zip` with
+/// `game.z01`, and numbered volumes such as `game.7z.001`.
+fn set_key(name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    if let Some(stem) = name.strip_suffix(".rar") {
+        return Some(match strip_number(stem, ".part") {
+            Some(prefix) => format!("{prefix}.part#.rar"),
+            None => format!("{stem}.rar#"),
+        });
+    }
+    if let Some(prefix) = strip_number(&name, ".r") {
+        return Some(format!("{prefix}.rar#"));
+    }
+    if let Some(stem) = name.strip_suffix(".zip") {
+        return Some(format!("{stem}.zip#"));
+    }
+    if let Some(prefix) = strip_number(&name, ".z") {
+        return Some(format!("{prefix}.zip#"));
+    }
+    strip_number(&name, ".").map(|prefix| format!("{prefix}.#"))
+}
+
+/// `name` without a trailing `marker` followed by digits, if it has one.
+fn strip_number<'a>(name: &'a str, marker: &str) -> Option<&'a str> {
+    let digits = name.len() - name.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+    if digits == 0 {
+        return None;
+    }
+    name[..name.len() - digits].strip_suffix(marker)
+}
+
+/// The volumes of the RAR or numbered set `archive` belongs to, first
+/// volume first. Fails naming the first volume missing before the last one
+/// found; a set cut short at the end is left for the extractor to notice.
+pub fn volume_set(archive: &Path) -> Result<Vec<PathBuf>> {
+    let mut volumes: Vec<(Volume, PathBuf)> = archive_parts(archive)
+        .into_iter()
+        .filter_map(|part| {
+            let volume = Volume::parse(&part.file_name()?.to_string_lossy())?;
+            Some((volume, part))
+        })
+        .collect();
+    volumes.sort_by_key(|(volume, _)| volume.index);
+    let Some((first, _)) = volumes.first() else {
+        return Ok(vec![archive.to_path_buf()]);
+    };
+    let indices = first.first_index()..;
+    for (expected, (volume, _)) in indices.zip(&volumes) {
+        if volume.index != expected {
+            let missing = archive.with_file_name(volume.name(expected));
+            return Err(CommandError::not_found(format!(
+                "Volume {} of the archive is missing",
+                missing.display()
+            ))
+            .into());
+        }
+    }
+    Ok(volumes.into_iter().map(|(_, part)| part).collect())
+}
+
+#[derive(Debug, Clone, Copy)]
+enum VolumeStyle {
+    /// `game.part1.rar`, `game.part2.rar`, …
+    Part,
+    /// `game.rar`, then `game.r00`, `game.r01`, …
+    Rar,
+    /// `game.7z.001`, `game.7z.002`, …
+    Numbered,
+}
+
+/// A volume's place in its set, and how to name the others.
+#[derive(Debug)]
+struct Volume {
+    style: VolumeStyle,
+    stem: String,
+    index: u64,
+    width: usize,
+}
+
+impl Volume {
+    fn parse(name: &str) -> Option<Self> {
+        let lower = name.to_ascii_lowercase();
+        if lower.ends_with(".rar") {
+            let base = &name[..name.len() - 4];
+            return Some(match split_number(base, ".part") {
+                Some((stem, digits)) => Self::new(VolumeStyle::Part, stem, digits)?,
+                None => Self {
+                    style: VolumeStyle::Rar,
+                    stem: base.to_string(),
+                    index: 0,
+                    width: 2,
+                },
+            });
+        }
+        if let Some((stem, digits)) = split_number(name, ".r") {
+            let mut volume = Self::new(VolumeStyle::Rar, stem, digits)?;
+            // `game.r00` follows `game.rar`.
+            volume.index += 1;
+            return Some(volume);
+        }
+        let (stem, digits) = split_number(name, ".")?;
+        Self::new(VolumeStyle::Numbered, stem, digits)
+    }
+
+    fn new(style: VolumeStyle, stem: &str, digits: &str) -> Option<Self> {
+        Some(Self {
+            style,
+            stem: stem.to_string(),
+            index: digits.parse().ok()?,
+            width: digits.len(),
+        })
+    }
+
+    fn first_index(&self) -> u64 {
+        match self.style {
+            VolumeStyle::Rar => 0,
+            VolumeStyle::Part | VolumeStyle::Numbered => 1,
+        }
+    }
+
+    /// The file name of the volume at `index` in this volume's set.
+    fn name(&self, index: u64) -> String {
+        let (stem, width) = (&self.stem, self.width);
+        match self.style {
+            VolumeStyle::Part => format!("{stem}.part{index:0width$}.rar"),
+            VolumeStyle::Rar if index == 0 => format!("{stem}.rar"),
+            VolumeStyle::Rar => format!("{stem}.r{:0width$}", index - 1),
+            VolumeStyle::Numbered => format!("{stem}.{index:0width$}"),
+        }
+    }
+}
+
+/// `name` split before a trailing `marker` and the digits that follow it,
+/// ignoring case, as `("game", "01")` for `game.part01`.
+fn split_number<'a>(name: &'a str, marker: &str) -> Option<(&'a str, &'a str)> {
+    let rest = name.trim_end_matches(|c: char| c.is_ascii_digit());
+    if rest.len() == name.len() || !rest.to_ascii_lowercase().ends_with(marker) {
+        return None;
+    }
+    Some((&rest[..rest.len() - marker.len()], &name[rest.len()..]))
+}
+
+fn delete_parts(parts: &[PathBuf]) -> Result<()> {
+    if trash::delete_all(parts).is_ok() {
+        return Ok(());
+    }
+    for part in parts.iter().filter(|part| part.exists()) {
+        fs::remove_file(part).with_context(|| format!("Failed to delete {}", part.display()))?;
+    }
+    Ok(())
+}
+
+/// Moves `parts` into `dir`, returning it. Files are renamed while that
+/// works; the rest are copied, and the originals are removed only once
+/// every copy has the original's size.
+fn move_parts(app: &AppHandle, game_id: &str, parts: &[PathBuf], dir: &Path) -> Result<PathBuf> {
+    if !dir.is_absolute() {
+        return Err(CommandError::invalid_input("The destination must be an absolute path").into());
+    }
+    let moves: Vec<(&PathBuf, PathBuf)> = parts
+        .iter()
+        .filter_map(|part| Some((part, dir.join(part.file_name()?))))
+        .collect();
+    if let Some((_, target)) = moves
+        .iter()
+        .find(|(part, target)| target.exists() && target != *part)
+    {
+        return Err(
+            CommandError::already_exists(format!("{} already exists", target.display())).into(),
+        );
+    }
+    fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+
+    let renamed = moves
+        .iter()
+        .take_while(|(part, target)| part == &target || fs::rename(part, target).is_ok())
+        .count();
+    let remaining = &moves[renamed..];
+    if remaining.is_empty() {
+        return Ok(dir.to_path_buf());
+    }
+
+    let mut total_bytes = 0;
+    for (part, _) in remaining {
+        total_bytes += fs::metadata(part)?.len();
+    }
+    disk::ensure_free_space(dir, total_bytes)?;
+    let mut copier = Copier::new(app, game_id, total_bytes);
+    let mut copied = Vec::new();
+    let result = remaining.iter().try_for_each(|(part, target)| {
+        copied.push(target.clone());
+        let len = copier.copy(part, target)?;
+        if fs::metadata(target)?.len() != len || fs::metadata(part)?.len() != len {
+            return Err(anyhow!("{} did not copy intact", part.display()));
+        }
+        Ok(())
+    });
+    if let Err(error) = result {
+        for target in copied {
+            let _ = fs::remove_file(target);
+        }
+        return Err(error);
+    }
+    copier.finish();
+    // The archive already lives on, so leftovers are only a matter of space.
+    for (part, _) in remaining {
+        let _ = fs::remove_file(part);
+    }
+    Ok(dir.to_path_buf())
+}
@@ -0,0 +1,275 @@
+use crate::error::CommandError;
+use crate::{downloads, GameEntry, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use image::imageops::FilterType;
+use image::io::Reader as ImageReader;
+use image::{ImageFormat, ImageOutputFormat};
+use serde::Deserialize;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Folder under the data dir holding one subfolder of images per game.
+const ARTWORK_DIR: &str = "artwork";
+/// Largest image accepted from a file or URL, before any scaling.
+const MAX_SOURCE_BYTES: u64 = 25 * 1024 * 1024;
+/// Formats the webview can show; anything else is refused.
+const SUPPORTED_FORMATS: &[ImageFormat] = &[
+    ImageFormat::Png,
+    ImageFormat::Jpeg,
+    ImageFormat::Gif,
+    ImageFormat::WebP,
+    ImageFormat::Bmp,
+];
+
+/// Which of a game's images is meant.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ArtworkKind {
+    Cover,
+    Background,
+    /// Taken from the game's executable by `extract_icon`.
+    Icon,
+}
+
+impl ArtworkKind {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cover => "cover",
+            Self::Background => "background",
+            Self::Icon => "icon",
+        }
+    }
+
+    /// Bounding box larger images are scaled down to.
+    fn max_dimensions(self) -> (u32, u32) {
+        match self {
+            Self::Cover => (1000, 1500),
+            Self::Background => (3840, 2160),
+            Self::Icon => (256, 256),
+        }
+    }
+
+    fn path_mut(self, game: &mut GameEntry) -> &mut Option<String> {
+        match self {
+            Self::Cover => &mut game.cover_path,
+            Self::Background => &mut game.background_path,
+            Self::Icon => &mut game.icon_path,
+        }
+    }
+}
+
+/// Stores an image as a game's cover or background. `source` is a local file
+/// or an http(s) URL; either way the image is copied into
+/// `artwork/<game id>/`, scaled down if it is larger than needed, and the
+/// previous image of that kind is deleted. The stored path can be handed to
+/// `convertFileSrc` as is.
+#[tauri::command]
+pub async fn set_game_artwork(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+    kind: ArtworkKind,
+    source: String,
+) -> Result<GameEntry, CommandError> {
+    set_artwork(&app, &library, &id, kind, source.trim())
+        .await
+        .map_err(CommandError::from)
+}
+
+/// Absolute path of a game's cover, background or icon, if it has one.
+#[tauri::command]
+pub fn get_artwork_path(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+    kind: ArtworkKind,
+) -> Result<Option<String>, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == id)
+        .ok_or_else(|| CommandError::game_not_found(&id))?;
+    let path = match kind {
+        ArtworkKind::Cover => game.cover_path,
+        ArtworkKind::Background => game.background_path,
+        ArtworkKind::Icon => game.icon_path,
+    };
+    Ok(path.filter(|path| Path::new(path).is_file()))
+}
+
+/// `set_game_artwork` for callers inside the backend.
+pub async fn set_artwork(
+    app: &AppHandle,
+    library: &Library,
+    id: &str,
+    kind: ArtworkKind,
+    source: &str,
+) -> Result<GameEntry> {
+    if source.is_empty() {
+        return Err(CommandError::invalid_input("No image given").into());
+    }
+    if !crate::game_exists(app, id)? {
+        return Err(CommandError::game_not_found(id).into());
+    }
+
+    let bytes = if source.starts_with("http://") || source.starts_with("https://") {
+        downloads::fetch_bytes(app, source, MAX_SOURCE_BYTES)
+            .await
+            .with_context(|| format!("Failed to download {source}"))?
+    } else {
+        read_source(Path::new(source))?
+    };
+    store_artwork(app, library, id, kind, bytes).await
+}
+
+/// Stores image `bytes` as a game's artwork of `kind` and replaces the
+/// previous one, like `set_game_artwork` does once it has the image.
+pub async fn store_artwork(
+    app: &AppHandle,
+    library: &Library,
+    id: &str,
+    kind: ArtworkKind,
+    bytes: Vec<u8>,
+) -> Result<GameEntry> {
+    let folder = game_folder(app, id)?;
+    let stored = {
+        let folder = folder.clone();
+        tauri::async_runtime::spawn_blocking(move || store(&folder, kind, &bytes)).await??
+    };
+
+    let stored_path = stored.to_string_lossy().into_owned();
+    let updated = library.update(app, |games| {
+        let game = games
+            .iter_mut()
+            .find(|game| game.id == id)
+            .ok_or_else(|| CommandError::game_not_found(id))?;
+        let previous = kind.path_mut(game).replace(stored_path);
+        game.updated_at = Utc::now();
+        Ok((game.clone(), previous))
+    });
+    let (game, previous) = match updated {
+        Ok(updated) => updated,
+        Err(error) => {
+            let _ = fs::remove_file(&stored);
+            return Err(error);
+        }
+    };
+
+    // Only images this module stored are deleted, never a file the entry
+    // pointed at some other way.
+    if let Some(previous) = previous.map(PathBuf::from) {
+        if previous != stored && previous.starts_with(&folder) {
+            let _ = fs::remove_file(previous);
+        }
+    }
+    crate::emit_library_updated(app, vec![game.id.clone()]);
+    Ok(game)
+}
+
+/// Deletes the images stored for a game that is gone for good.
+pub fn remove_game_artwork(app: &AppHandle, id: &str) {
+    if let Ok(folder) = game_folder(app, id) {
+        if folder.is_dir() {
+            let _ = fs::remove_dir_all(folder);
+        }
+    }
+}
+
+/// Whether `game` shows an image stored for the game `id`, as a merged
+/// entry can after taking over the other game's artwork.
+pub fn uses_artwork_of(app: &AppHandle, game: &GameEntry, id: &str) -> bool {
+    let Ok(folder) = game_folder(app, id) else {
+        return false;
+    };
+    [&game.cover_path, &game.background_path, &game.icon_path]
+        .into_iter()
+        .flatten()
+        .any(|path| Path::new(path).starts_with(&folder))
+}
+
+fn game_folder(app: &AppHandle, id: &str) -> Result<PathBuf> {
+    // Ids are generated by the launcher, but imports may carry their own.
+    if id.is_empty() || id.contains(['/', '\\']) || id.starts_with('.') {
+        return Err(CommandError::invalid_input(format!("Invalid game id: {id}")).into());
+    }
+    Ok(crate::resolve_data_dir(app)?.join(ARTWORK_DIR).join(id))
+}
+
+fn read_source(path: &Path) -> Result<Vec<u8>> {
+    let metadata =
+        fs::metadata(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    if !metadata.is_file() {
+        return Err(
+            CommandError::invalid_input(format!("{} is not a file", path.display())).into(),
+        );
+    }
+    if metadata.len() > MAX_SOURCE_BYTES {
+        return Err(anyhow!(
+            "{} is larger than {}",
+            path.display(),
+            crate::disk::format_size(MAX_SOURCE_BYTES)
+        ));
+    }
+    fs::read(path).with_context(|| format!("Failed to read {}", path.display()))
+}
+
+/// Checks `bytes` is an image by its signature and writes it to `folder`,
+/// scaled to fit `kind`'s bounds. Returns the new file, which gets a fresh
+/// name every time so the webview never shows a cached older image.
+fn store(folder: &Path, kind: ArtworkKind, bytes: &[u8]) -> Result<PathBuf> {
+    let format = image::guess_format(bytes)
+        .ok()
+        .filter(|format| SUPPORTED_FORMATS.contains(format))
+        .ok_or_else(|| CommandError::invalid_input("Not a PNG, JPEG, GIF, WebP or BMP image"))?;
+    let (width, height) = ImageReader::with_format(Cursor::new(bytes), format)
+        .into_dimensions()
+        .context("The image could not be read")?;
+
+    let (max_width, max_height) = kind.max_dimensions();
+    let scaled = if width > max_width || height > max_height {
+        Some(scale(bytes, format, max_width, max_height)?)
+    } else {
+        None
+    };
+    let (format, bytes) = match &scaled {
+        Some((format, scaled)) => (*format, scaled.as_slice()),
+        None => (format, bytes),
+    };
+
+    fs::create_dir_all(folder).with_context(|| format!("Failed to create {}", folder.display()))?;
+    let path = folder.join(format!(
+        "{}-{}.{}",
+        kind.name(),
+        Utc::now().timestamp_millis(),
+        format.extensions_str()[0]
+    ));
+    crate::write_atomic(&path, bytes)?;
+    Ok(path)
+}
+
+/// Scales an image down to fit the bounds, keeping its aspect ratio. JPEGs
+/// stay JPEGs; everything else is written as PNG.
+fn scale(
+    bytes: &[u8],
+    format: ImageFormat,
+    max_width: u32,
+    max_height: u32,
+) -> Result<(ImageFormat, Vec<u8>)> {
+    let image = image::load_from_memory_with_format(bytes, format)
+        .context("The image could not be read")?
+        .resize(max_width, max_height, FilterType::Lanczos3);
+    let mut encoded = Cursor::new(Vec::new());
+    let format = if format == ImageFormat::Jpeg {
+        image::DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_to(&mut encoded, ImageOutputFormat::Jpeg(90))?;
+        ImageFormat::Jpeg
+    } else {
+        image.write_to(&mut encoded, ImageOutputFormat::Png)?;
+        ImageFormat::Png
+    };
+    Ok((format, encoded.into_inner()))
+}
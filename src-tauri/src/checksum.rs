@@ -0,0 +1,127 @@
+use anyhow::{anyhow, Result};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+const READ_BUFFER: usize = 1024 * 128;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChecksumAlgorithm {
+    Sha256,
+    Md5,
+}
+
+impl ChecksumAlgorithm {
+    fn prefix(self) -> &'static str {
+        match self {
+            Self::Sha256 => "sha256",
+            Self::Md5 => "md5",
+        }
+    }
+
+    fn hex_len(self) -> usize {
+        match self {
+            Self::Sha256 => 64,
+            Self::Md5 => 32,
+        }
+    }
+}
+
+/// A checksum in the `algorithm:hexdigest` form repackers publish, e.g.
+/// `sha256:9f86d0…`. A bare digest is accepted when its length identifies the
+/// algorithm.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checksum {
+    pub algorithm: ChecksumAlgorithm,
+    pub digest: String,
+}
+
+impl Checksum {
+    pub fn parse(value: &str) -> Result<Self> {
+        let value = value.trim();
+        let (algorithm, digest) = match value.split_once(':') {
+            Some((name, digest)) => {
+                let algorithm = match name.trim().to_ascii_lowercase().as_str() {
+                    "sha256" | "sha-256" => ChecksumAlgorithm::Sha256,
+                    "md5" => ChecksumAlgorithm::Md5,
+                    other => return Err(anyhow!("Unsupported checksum algorithm: {other}")),
+                };
+                (algorithm, digest.trim())
+            }
+            None => match value.len() {
+                64 => (ChecksumAlgorithm::Sha256, value),
+                32 => (ChecksumAlgorithm::Md5, value),
+                _ => return Err(anyhow!("Checksum must look like sha256:<hex> or md5:<hex>")),
+            },
+        };
+
+        if digest.len() != algorithm.hex_len() || !digest.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(anyhow!(
+                "Invalid {} digest: expected {} hex characters",
+                algorithm.prefix(),
+                algorithm.hex_len()
+            ));
+        }
+
+        Ok(Self {
+            algorithm,
+            digest: digest.to_ascii_lowercase(),
+        })
+    }
+}
+
+impl fmt::Display for Checksum {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.algorithm.prefix(), self.digest)
+    }
+}
+
+/// Incremental hasher fed with bytes as they stream through the download buffer.
+pub enum StreamHasher {
+    Sha256(Sha256),
+    Md5(Md5),
+}
+
+impl StreamHasher {
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Sha256 => Self::Sha256(Sha256::new()),
+            ChecksumAlgorithm::Md5 => Self::Md5(Md5::new()),
+        }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(bytes),
+            Self::Md5(hasher) => hasher.update(bytes),
+        }
+    }
+
+    /// Feeds the first `len` bytes of an existing file, used when a download
+    /// resumes from a partial file hashed by an earlier attempt.
+    pub fn update_from_file(&mut self, path: &Path, len: u64) -> Result<()> {
+        let mut reader = File::open(path)?.take(len);
+        let mut buffer = vec![0u8; READ_BUFFER];
+        loop {
+            let bytes_read = reader.read(&mut buffer)?;
+            if bytes_read == 0 {
+                return Ok(());
+            }
+            self.update(&buffer[..bytes_read]);
+        }
+    }
+
+    pub fn finalize(self) -> Checksum {
+        let (algorithm, digest) = match self {
+            Self::Sha256(hasher) => (
+                ChecksumAlgorithm::Sha256,
+                format!("{:x}", hasher.finalize()),
+            ),
+            Self::Md5(hasher) => (ChecksumAlgorithm::Md5, format!("{:x}", hasher.finalize())),
+        };
+        Checksum { algorithm, digest }
+    }
+}
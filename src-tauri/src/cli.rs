@@ -0,0 +1,79 @@
+use crate::deep_link::{self, SCHEME};
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "\
+Usage: roselauncher [options] [roselauncher://link]
+
+Options:
+  --launch <game id>               Start a game
+  --add-download <url> <folder>    Download a file into a folder
+  --minimized                      Start hidden in the tray, or minimized without one
+  --library <folder>               Keep the library and everything else in <folder>
+  --help                           Show this help";
+
+/// What the launcher was started with.
+#[derive(Debug, Default)]
+pub struct Args {
+    /// The action to perform, as a deep link so a running instance can be
+    /// handed it the same way as links from outside. `--add-download` is
+    /// not a link, see [`deep_link::add_download_message`].
+    pub link: Option<String>,
+    pub minimized: bool,
+    pub data_dir: Option<PathBuf>,
+    pub help: bool,
+}
+
+/// Reads the arguments after the program name. `--launch` becomes a
+/// `roselauncher://` link and `--add-download` a message for the running
+/// instance; a link given as is is taken over. Only one action may be given.
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.into_iter();
+    let prefix = format!("{SCHEME}://");
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .filter(|value| !value.starts_with("--"))
+                .ok_or_else(|| format!("{arg} needs {name}"))
+        };
+        let link = match arg.as_str() {
+            "--launch" => {
+                let id = value("a game id")?;
+                format!("{prefix}launch/{}", encode(&id))
+            }
+            "--add-download" => {
+                let url = value("a url and a folder")?;
+                let destination = value("a url and a folder")?;
+                deep_link::add_download_message(&url, &destination)
+            }
+            "--help" | "-h" => {
+                parsed.help = true;
+                continue;
+            }
+            "--minimized" => {
+                parsed.minimized = true;
+                continue;
+            }
+            "--library" => {
+                parsed.data_dir = Some(PathBuf::from(value("a folder")?));
+                continue;
+            }
+            // A line break would let a link pose as a second message.
+            link if link.starts_with(&prefix) && !link.contains(char::is_control) => {
+                link.to_string()
+            }
+            // macOS adds a process serial number when started from Finder.
+            other if other.starts_with("-psn_") => continue,
+            other => return Err(format!("Unknown argument: {other}")),
+        };
+        if parsed.link.replace(link).is_some() {
+            return Err("Only one action can be given".into());
+        }
+    }
+    Ok(parsed)
+}
+
+fn encode(value: &str) -> String {
+    utf8_percent_encode(value, NON_ALPHANUMERIC).to_string()
+}
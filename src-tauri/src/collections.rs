@@ -0,0 +1,277 @@
+use crate::error::CommandError;
+use crate::Library;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+
+const COLLECTIONS_FILE: &str = "collections.json";
+
+/// A named shelf of games, kept apart from the tags on the games themselves
+/// so renaming one does not touch any entry.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Collection {
+    pub id: String,
+    pub name: String,
+    /// Members in the order the collection shows them.
+    #[serde(default)]
+    pub game_ids: Vec<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Managed state guarding `collections.json`.
+#[derive(Debug, Default)]
+pub struct Collections {
+    lock: Mutex<()>,
+}
+
+impl Collections {
+    fn list(&self, app: &AppHandle) -> Result<Vec<Collection>> {
+        let _guard = self.lock.lock().expect("collections poisoned");
+        read_collections(app)
+    }
+
+    /// Applies `change` to the stored collections and writes them back.
+    fn update<T>(
+        &self,
+        app: &AppHandle,
+        change: impl FnOnce(&mut Vec<Collection>) -> Result<T>,
+    ) -> Result<T> {
+        let _guard = self.lock.lock().expect("collections poisoned");
+        let mut collections = read_collections(app)?;
+        let result = change(&mut collections)?;
+        write_collections(app, &collections)?;
+        Ok(result)
+    }
+
+    /// Game ids of the collection `id`, in its order.
+    pub fn game_ids(&self, app: &AppHandle, id: &str) -> Result<Vec<String>> {
+        self.list(app)?
+            .into_iter()
+            .find(|collection| collection.id == id)
+            .map(|collection| collection.game_ids)
+            .ok_or_else(|| CommandError::not_found(format!("Collection {id} not found")).into())
+    }
+}
+
+/// Every collection, oldest first. Games in the trash are left out of
+/// `game_ids` but keep their place for when they are restored.
+#[tauri::command]
+pub fn list_collections(
+    app: AppHandle,
+    collections: State<'_, Collections>,
+    library: State<'_, Library>,
+) -> Result<Vec<Collection>, CommandError> {
+    let live: HashSet<String> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .map(|game| game.id)
+        .collect();
+    let mut list = collections
+        .list(&app)
+        .context("Failed to load collections")?;
+    for collection in &mut list {
+        collection.game_ids.retain(|id| live.contains(id));
+    }
+    Ok(list)
+}
+
+#[tauri::command]
+pub fn create_collection(
+    app: AppHandle,
+    collections: State<'_, Collections>,
+    name: String,
+    color: Option<String>,
+    icon: Option<String>,
+) -> Result<Collection, CommandError> {
+    collections
+        .update(&app, |list| {
+            let name = checked_name(list, &name, None)?;
+            let collection = Collection {
+                id: Uuid::new_v4().to_string(),
+                name,
+                game_ids: Vec::new(),
+                color: color.and_then(crate::non_empty),
+                icon: icon.and_then(crate::non_empty),
+                created_at: Utc::now(),
+            };
+            list.push(collection.clone());
+            Ok(collection)
+        })
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn rename_collection(
+    app: AppHandle,
+    collections: State<'_, Collections>,
+    id: String,
+    name: String,
+) -> Result<Collection, CommandError> {
+    collections
+        .update(&app, |list| {
+            let name = checked_name(list, &name, Some(&id))?;
+            let collection = find_mut(list, &id)?;
+            collection.name = name;
+            Ok(collection.clone())
+        })
+        .map_err(CommandError::from)
+}
+
+/// Deletes a collection. Its games stay in the library.
+#[tauri::command]
+pub fn delete_collection(
+    app: AppHandle,
+    collections: State<'_, Collections>,
+    id: String,
+) -> Result<(), CommandError> {
+    collections
+        .update(&app, |list| {
+            let before = list.len();
+            list.retain(|collection| collection.id != id);
+            if list.len() == before {
+                return Err(CommandError::not_found(format!("Collection {id} not found")).into());
+            }
+            Ok(())
+        })
+        .map_err(CommandError::from)
+}
+
+/// Appends games to a collection. Games already in it keep their place and
+/// unknown ids are refused.
+#[tauri::command]
+pub fn add_to_collection(
+    app: AppHandle,
+    collections: State<'_, Collections>,
+    library: State<'_, Library>,
+    id: String,
+    game_ids: Vec<String>,
+) -> Result<Collection, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    if let Some(unknown) = game_ids
+        .iter()
+        .find(|game_id| !games.iter().any(|game| &game.id == *game_id))
+    {
+        return Err(CommandError::game_not_found(unknown));
+    }
+
+    collections
+        .update(&app, |list| {
+            let collection = find_mut(list, &id)?;
+            for game_id in game_ids {
+                if !collection.game_ids.contains(&game_id) {
+                    collection.game_ids.push(game_id);
+                }
+            }
+            Ok(collection.clone())
+        })
+        .map_err(CommandError::from)
+}
+
+#[tauri::command]
+pub fn remove_from_collection(
+    app: AppHandle,
+    collections: State<'_, Collections>,
+    id: String,
+    game_ids: Vec<String>,
+) -> Result<Collection, CommandError> {
+    collections
+        .update(&app, |list| {
+            let collection = find_mut(list, &id)?;
+            collection
+                .game_ids
+                .retain(|game_id| !game_ids.contains(game_id));
+            Ok(collection.clone())
+        })
+        .map_err(CommandError::from)
+}
+
+/// Takes games that are gone for good out of every collection.
+pub fn forget_games(app: &AppHandle, ids: &[String]) -> Result<()> {
+    app.state::<Collections>().update(app, |list| {
+        for collection in list {
+            collection.game_ids.retain(|game_id| !ids.contains(game_id));
+        }
+        Ok(())
+    })
+}
+
+/// Puts `replacement` wherever `id` was, for a game merged into another.
+/// Collections that already hold `replacement` just lose `id`.
+pub fn replace_game(app: &AppHandle, id: &str, replacement: &str) -> Result<()> {
+    app.state::<Collections>().update(app, |list| {
+        for collection in list {
+            let Some(position) = collection.game_ids.iter().position(|game_id| game_id == id)
+            else {
+                continue;
+            };
+            if collection
+                .game_ids
+                .iter()
+                .any(|game_id| game_id == replacement)
+            {
+                collection.game_ids.remove(position);
+            } else {
+                collection.game_ids[position] = replacement.to_string();
+            }
+        }
+        Ok(())
+    })
+}
+
+fn find_mut<'a>(list: &'a mut [Collection], id: &str) -> Result<&'a mut Collection> {
+    list.iter_mut()
+        .find(|collection| collection.id == id)
+        .ok_or_else(|| CommandError::not_found(format!("Collection {id} not found")).into())
+}
+
+/// Trims `name` and makes sure no other collection is called the same,
+/// ignoring case.
+fn checked_name(list: &[Collection], name: &str, own_id: Option<&str>) -> Result<String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(CommandError::invalid_input("Collection name cannot be empty").into());
+    }
+    let taken = list.iter().any(|collection| {
+        Some(collection.id.as_str()) != own_id && collection.name.eq_ignore_ascii_case(name)
+    });
+    if taken {
+        return Err(CommandError::already_exists(format!(
+            "A collection named {name} already exists"
+        ))
+        .into());
+    }
+    Ok(name.to_string())
+}
+
+fn collections_path(app: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::resolve_data_dir(app)?.join(COLLECTIONS_FILE))
+}
+
+fn read_collections(app: &AppHandle) -> Result<Vec<Collection>> {
+    let path = collections_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_collections(app: &AppHandle, collections: &[Collection]) -> Result<()> {
+    let payload = serde_json::to_string_pretty(collections)?;
+    crate::write_atomic(&collections_path(app)?, payload.as_bytes())
+}
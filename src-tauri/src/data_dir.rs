@@ -0,0 +1,297 @@
+use crate::checksum::{Checksum, ChecksumAlgorithm, StreamHasher};
+use crate::downloads::DownloadManager;
+use crate::error::CommandError;
+use crate::history::DownloadHistory;
+use crate::{GameEntry, Library};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tauri::{AppHandle, Manager};
+use walkdir::WalkDir;
+
+/// Beside the executable, turns on portable mode. It may hold the data
+/// folder's path, relative to the executable's folder; empty means
+/// `PORTABLE_DATA_DIR` there.
+const PORTABLE_FLAG: &str = "portable.flag";
+const PORTABLE_DATA_DIR: &str = "data";
+/// In the default data folder, points to the folder the data was moved to.
+const POINTER_FILE: &str = "data-location.txt";
+
+/// Data folder given with `--library`.
+static ARGUMENT: OnceLock<PathBuf> = OnceLock::new();
+/// The data folder in use, worked out on first use and changed by
+/// `move_data_directory`.
+static CURRENT: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+/// What decides where the data folder is, in order of precedence.
+enum Source {
+    /// `--library` on the command line.
+    Argument,
+    /// `portable.flag` beside the executable.
+    Portable {
+        flag: PathBuf,
+    },
+    /// `data-location.txt` in the default folder.
+    Pointer {
+        file: PathBuf,
+    },
+    Default {
+        dir: PathBuf,
+    },
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveProgress {
+    copied_bytes: u64,
+    total_bytes: u64,
+    file: String,
+}
+
+/// Keeps all data in `dir` for this run, ahead of a portable flag or a
+/// moved data folder. Call before the app is built.
+pub fn set_argument(dir: PathBuf) {
+    let _ = ARGUMENT.set(std::path::absolute(&dir).unwrap_or(dir));
+}
+
+/// Folder holding the library and every other file the launcher persists,
+/// created if missing.
+pub fn resolve(app: &AppHandle) -> Result<PathBuf> {
+    let mut current = CURRENT.lock().expect("data folder poisoned");
+    let dir = match &*current {
+        Some(dir) => dir.clone(),
+        None => locate(app)?.0,
+    };
+    fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create data folder {}", dir.display()))?;
+    *current = Some(dir.clone());
+    Ok(dir)
+}
+
+fn locate(app: &AppHandle) -> Result<(PathBuf, Source)> {
+    if let Some(dir) = ARGUMENT.get() {
+        return Ok((dir.clone(), Source::Argument));
+    }
+    if let Some(exe_dir) = exe_dir() {
+        let flag = exe_dir.join(PORTABLE_FLAG);
+        if flag.is_file() {
+            let target = fs::read_to_string(&flag)
+                .with_context(|| format!("Failed to read {}", flag.display()))?;
+            let target = target.trim();
+            let dir = if target.is_empty() {
+                exe_dir.join(PORTABLE_DATA_DIR)
+            } else {
+                exe_dir.join(target)
+            };
+            return Ok((dir, Source::Portable { flag }));
+        }
+    }
+    let resolver = app.path_resolver();
+    let default = resolver
+        .app_config_dir()
+        .or_else(|| resolver.app_data_dir())
+        .context("Unable to resolve application data folder")?;
+    let file = default.join(POINTER_FILE);
+    if let Some(target) = fs::read_to_string(&file)
+        .ok()
+        .and_then(|target| crate::non_empty(target.trim().to_string()))
+    {
+        return Ok((PathBuf::from(target), Source::Pointer { file }));
+    }
+    Ok((default.clone(), Source::Default { dir: default }))
+}
+
+fn exe_dir() -> Option<PathBuf> {
+    std::env::current_exe()
+        .ok()?
+        .parent()
+        .map(Path::to_path_buf)
+}
+
+/// Moves the library, settings, artwork and every other data file to
+/// `new_path`, which must be an absolute path to an empty or missing folder.
+/// The copies are checked against the originals before the launcher
+/// switches to them, and the originals are removed last, so a failure at
+/// any earlier point leaves the data where it was. Reports
+/// `data-move-progress` as files are copied. Returns the new folder.
+#[tauri::command]
+pub async fn move_data_directory(app: AppHandle, new_path: String) -> Result<String, CommandError> {
+    tauri::async_runtime::spawn_blocking(move || move_data(&app, PathBuf::from(new_path.trim())))
+        .await?
+        .map(|dir| dir.to_string_lossy().into_owned())
+        .map_err(CommandError::from)
+}
+
+fn move_data(app: &AppHandle, target: PathBuf) -> Result<PathBuf> {
+    if !target.is_absolute() {
+        return Err(
+            CommandError::invalid_input("The new data folder must be an absolute path").into(),
+        );
+    }
+    let (_, source) = locate(app)?;
+    if matches!(source, Source::Argument) {
+        return Err(anyhow!(
+            "The data folder was chosen with --library and cannot be moved"
+        ));
+    }
+    let old = crate::resolve_data_dir(app)?;
+    let target = std::path::absolute(&target).unwrap_or(target);
+    if target.starts_with(&old) || old.starts_with(&target) {
+        return Err(anyhow!(
+            "The new data folder cannot contain or lie inside the current one"
+        ));
+    }
+    // Moving back to the default folder finds only the pointer there.
+    if target.is_dir()
+        && target
+            .read_dir()?
+            .any(|entry| entry.map_or(true, |entry| entry.file_name() != POINTER_FILE))
+    {
+        return Err(CommandError::conflict(format!("{} is not empty", target.display())).into());
+    }
+
+    // Nothing touches the library while its files move, and its database
+    // is closed so the original can be removed. The download queue and
+    // history wait too, then write to the new folder.
+    let library = app.state::<Library>();
+    let mut loaded = library.loaded.lock().expect("library poisoned");
+    *loaded = None;
+    let downloads = app.state::<DownloadManager>();
+    let _snapshots = downloads.hold_snapshots();
+    let history = app.state::<DownloadHistory>();
+    let _history = history.hold();
+
+    let files = data_files(&old)?;
+    if let Err(error) = copy_files(app, &old, &target, &files) {
+        remove_files(&target, &files);
+        return Err(error);
+    }
+    point_to(&source, &target)?;
+    *CURRENT.lock().expect("data folder poisoned") = Some(target.clone());
+
+    // Artwork paths are stored in full, so they follow the folder.
+    let reopened = loaded.insert(crate::open_library(app)?);
+    let previous: Vec<GameEntry> = [reopened.games.as_slice(), reopened.trash.as_slice()].concat();
+    for game in reopened.games.iter_mut().chain(reopened.trash.iter_mut()) {
+        for path in [
+            &mut game.cover_path,
+            &mut game.background_path,
+            &mut game.icon_path,
+        ] {
+            if let Some(moved) = path
+                .as_deref()
+                .and_then(|path| Path::new(path).strip_prefix(&old).ok())
+            {
+                *path = Some(target.join(moved).to_string_lossy().into_owned());
+            }
+        }
+    }
+    let current = [reopened.games.as_slice(), reopened.trash.as_slice()].concat();
+    reopened.store.save(&previous, &current)?;
+    let game_ids = reopened.games.iter().map(|game| game.id.clone()).collect();
+    drop(loaded);
+
+    let _ = app
+        .asset_protocol_scope()
+        .allow_directory(target.join("artwork"), true);
+    crate::emit_library_updated(app, game_ids);
+    // The data already lives on, so leftovers are only a matter of space.
+    remove_files(&old, &files);
+    Ok(target)
+}
+
+/// Files under `dir`, relative to it. The pointer to a moved folder stays.
+fn data_files(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(dir) {
+        let entry = entry?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let relative = entry.path().strip_prefix(dir)?.to_path_buf();
+        if relative != Path::new(POINTER_FILE) {
+            files.push(relative);
+        }
+    }
+    Ok(files)
+}
+
+fn copy_files(app: &AppHandle, from: &Path, to: &Path, files: &[PathBuf]) -> Result<()> {
+    let mut total_bytes = 0;
+    for file in files {
+        total_bytes += fs::metadata(from.join(file))?.len();
+    }
+    let mut copied_bytes = 0;
+    for file in files {
+        let (source, copy) = (from.join(file), to.join(file));
+        if let Some(parent) = copy.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        copied_bytes += fs::copy(&source, &copy)
+            .with_context(|| format!("Failed to copy {}", source.display()))?;
+        if sha256(&source)? != sha256(&copy)? {
+            return Err(anyhow!("{} did not copy intact", source.display()));
+        }
+        let _ = app.emit_all(
+            "data-move-progress",
+            MoveProgress {
+                copied_bytes,
+                total_bytes,
+                file: file.to_string_lossy().into_owned(),
+            },
+        );
+    }
+    Ok(())
+}
+
+fn sha256(path: &Path) -> Result<Checksum> {
+    let len = fs::metadata(path)?.len();
+    let mut hasher = StreamHasher::new(ChecksumAlgorithm::Sha256);
+    hasher.update_from_file(path, len)?;
+    Ok(hasher.finalize())
+}
+
+/// Records `target` where the next start looks for the data folder.
+fn point_to(source: &Source, target: &Path) -> Result<()> {
+    match source {
+        Source::Argument => unreachable!("checked before moving"),
+        Source::Portable { flag } => {
+            // Kept relative when possible, so the drive letter may change.
+            let exe_dir = flag.parent().unwrap_or(Path::new(""));
+            let value = target.strip_prefix(exe_dir).unwrap_or(target);
+            crate::write_atomic(flag, value.to_string_lossy().as_bytes())
+        }
+        // Back in the default folder, the pointer is no longer needed.
+        Source::Pointer { file } if file.parent() == Some(target) => {
+            fs::remove_file(file).with_context(|| format!("Failed to remove {}", file.display()))
+        }
+        Source::Pointer { file } => crate::write_atomic(file, target.to_string_lossy().as_bytes()),
+        Source::Default { dir } => {
+            crate::write_atomic(&dir.join(POINTER_FILE), target.to_string_lossy().as_bytes())
+        }
+    }
+}
+
+/// Deletes `files` from `dir` and the folders they leave empty.
+fn remove_files(dir: &Path, files: &[PathBuf]) {
+    for file in files {
+        let _ = fs::remove_file(dir.join(file));
+    }
+    let mut folders: Vec<PathBuf> = WalkDir::new(dir)
+        .min_depth(1)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_dir())
+        .map(|entry| entry.into_path())
+        .collect();
+    // Deepest first, so parents are empty by the time they come up.
+    folders.sort_by_key(|folder| std::cmp::Reverse(folder.components().count()));
+    for folder in folders {
+        let _ = fs::remove_dir(folder);
+    }
+    // Fails, as it should, when the pointer file is still in it.
+    let _ = fs::remove_dir(dir);
+}
@@ -0,0 +1,236 @@
+use crate::downloads::{self, DownloadManager};
+use crate::launcher::{self, Launcher};
+use crate::{tray, Library};
+use percent_encoding::percent_decode_str;
+use serde::Serialize;
+use tauri::{AppHandle, Manager};
+use url::Url;
+
+/// Scheme of links such as `roselauncher://launch/<game id>`.
+pub const SCHEME: &str = "roselauncher";
+/// Must match `tauri.bundle.identifier`; a second instance finds the first by it.
+const IDENTIFIER: &str = "com.roselauncher.app";
+/// Starts the message `--add-download` is handed on as. It is not a link, so
+/// a web page cannot send it: downloading into a folder of the sender's
+/// choice is only for the command line.
+const ADD_DOWNLOAD: &str = "--add-download ";
+
+/// Sent for `roselauncher://add?url=…&title=…` so the add-game flow can open
+/// pre-filled.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct AddRequest {
+    url: String,
+    title: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UnknownLink {
+    url: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LinkError {
+    url: String,
+    message: String,
+}
+
+/// The message for `--add-download`, downloading `url` into `destination`.
+pub fn add_download_message(url: &str, destination: &str) -> String {
+    format!("{ADD_DOWNLOAD}{}", serde_json::json!([url, destination]))
+}
+
+/// Hands `link`, a link or a message from [`add_download_message`], to an
+/// instance that is already running and exits if there is one; started
+/// again without a link, the running instance just comes to the front. Call
+/// before the app is built.
+pub fn prepare(link: Option<&str>) {
+    if forward(link.unwrap_or_default()) {
+        std::process::exit(0);
+    }
+    let _ = tauri_plugin_deep_link::set_identifier(IDENTIFIER);
+}
+
+/// Registers the scheme with the system and handles links from then on,
+/// starting with `initial`, the one this instance was started with.
+pub fn register(app: &AppHandle, initial: Option<String>) {
+    let handle = app.clone();
+    // Without the registration links still work once the scheme is set up
+    // by the installer, so a failure is not fatal.
+    let _ = tauri_plugin_deep_link::register(SCHEME, move |link| handle_message(&handle, &link));
+    if let Some(link) = initial {
+        handle_message(app, &link);
+    }
+}
+
+fn handle_message(app: &AppHandle, message: &str) {
+    match message.strip_prefix(ADD_DOWNLOAD) {
+        Some(arguments) => add_download(app, message, arguments),
+        None => handle_link(app, message),
+    }
+}
+
+/// Queues the download `--add-download` asked for, its url and folder given
+/// as a JSON array.
+fn add_download(app: &AppHandle, message: &str, arguments: &str) {
+    tray::show_window(app);
+    let Ok([download, destination]) = serde_json::from_str::<[String; 2]>(arguments) else {
+        let _ = app.emit_all(
+            "deep-link-error",
+            LinkError {
+                url: message.to_string(),
+                message: "Malformed --add-download arguments".into(),
+            },
+        );
+        return;
+    };
+    let app = app.clone();
+    let url = download.clone();
+    tauri::async_runtime::spawn(async move {
+        let queued = downloads::queue_download(
+            app.clone(),
+            app.state::<DownloadManager>(),
+            Some(download),
+            None,
+            Some(destination),
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+            None,
+        )
+        .await;
+        if let Err(error) = queued {
+            let message = error.message;
+            let _ = app.emit_all("deep-link-error", LinkError { url, message });
+        }
+    });
+}
+
+/// Acts on one link. Nothing here may panic: links come from anywhere, so
+/// problems are reported through `deep-link-error` instead.
+fn handle_link(app: &AppHandle, link: &str) {
+    tray::show_window(app);
+    if link.trim().is_empty() {
+        return;
+    }
+    let error = |message: String| {
+        let _ = app.emit_all(
+            "deep-link-error",
+            LinkError {
+                url: link.to_string(),
+                message,
+            },
+        );
+    };
+    let url = match Url::parse(link.trim()) {
+        Ok(url) if url.scheme() == SCHEME => url,
+        _ => return error(format!("Not a {SCHEME}:// link")),
+    };
+
+    match url.host_str() {
+        Some("launch") => {
+            let id = url
+                .path_segments()
+                .and_then(|mut segments| segments.next())
+                .and_then(|id| percent_decode_str(id).decode_utf8().ok())
+                .unwrap_or_default();
+            if id.is_empty()
+                || !id
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+            {
+                return error(format!("Malformed game id: {id}"));
+            }
+            let launched = launcher::launch_game(
+                app.clone(),
+                app.state::<Library>(),
+                app.state::<Launcher>(),
+                id.to_string(),
+                None,
+                None,
+            );
+            if let Err(launch_error) = launched {
+                error(launch_error.message);
+            }
+        }
+        Some("add") => {
+            let query = |key: &str| {
+                url.query_pairs()
+                    .find(|(name, _)| name == key)
+                    .and_then(|(_, value)| crate::non_empty(value.into_owned()))
+            };
+            let Some(download) = query("url") else {
+                return error("The link has no download url".into());
+            };
+            if !(download.starts_with("http://") || download.starts_with("https://")) {
+                return error(format!("Not an http(s) url: {download}"));
+            }
+            let _ = app.emit_all(
+                "deep-link-add",
+                AddRequest {
+                    url: download,
+                    title: query("title"),
+                },
+            );
+        }
+        _ => {
+            let _ = app.emit_all(
+                "deep-link-unknown",
+                UnknownLink {
+                    url: link.to_string(),
+                },
+            );
+        }
+    }
+}
+
+/// Sends `link` to the socket the deep-link plugin listens on in the running
+/// instance. Returns whether there was one.
+#[cfg(target_os = "linux")]
+fn forward(link: &str) -> bool {
+    use std::io::{ErrorKind, Write};
+    use std::os::unix::net::UnixStream;
+
+    let socket = format!("/tmp/{IDENTIFIER}-deep-link.sock");
+    match UnixStream::connect(&socket) {
+        Ok(mut stream) => {
+            let _ = stream.write_all(link.as_bytes());
+            true
+        }
+        Err(error) => {
+            // Left behind by an instance that crashed.
+            if error.kind() == ErrorKind::ConnectionRefused {
+                let _ = std::fs::remove_file(&socket);
+            }
+            false
+        }
+    }
+}
+
+#[cfg(windows)]
+fn forward(link: &str) -> bool {
+    use interprocess::local_socket::LocalSocketStream;
+    use std::io::Write;
+    use windows_sys::Win32::UI::WindowsAndMessaging::{AllowSetForegroundWindow, ASFW_ANY};
+
+    let Ok(mut stream) = LocalSocketStream::connect(IDENTIFIER) else {
+        return false;
+    };
+    // Lets the running instance bring its window to the front.
+    unsafe { AllowSetForegroundWindow(ASFW_ANY) };
+    let _ = stream.write_all(format!("{link}\n").as_bytes());
+    true
+}
+
+/// macOS hands links to the running app itself.
+#[cfg(not(any(target_os = "linux", windows)))]
+fn forward(_link: &str) -> bool {
+    false
+}
@@ -0,0 +1,122 @@
+use crate::error::CommandError;
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use sysinfo::Disks;
+
+/// File systems that hold no user files, left out of `list_volumes` on top
+/// of those `sysinfo` already skips.
+#[cfg(target_os = "linux")]
+const PSEUDO_FILE_SYSTEMS: &[&str] = &[
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "configfs",
+    "debugfs",
+    "efivarfs",
+    "fusectl",
+    "overlay",
+    "ramfs",
+    "securityfs",
+    "tracefs",
+];
+
+/// A mounted volume as shown in destination pickers.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Volume {
+    pub mount_point: String,
+    /// The volume label on Windows and macOS, the device on Linux; `None`
+    /// when the system reports none.
+    pub label: Option<String>,
+    pub file_system: String,
+    pub total_bytes: u64,
+    pub available_bytes: u64,
+    pub removable: bool,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("Not enough disk space: {} needed but only {} available", format_size(*.needed), format_size(*.available))]
+pub struct InsufficientSpaceError {
+    pub needed: u64,
+    pub available: u64,
+}
+
+/// Bytes available to the current user on the volume holding `path`. Missing
+/// trailing components are skipped so folders that do not exist yet still
+/// resolve to their volume.
+pub fn free_space(path: &Path) -> Result<u64> {
+    let existing = path
+        .ancestors()
+        .find(|candidate| candidate.exists())
+        .ok_or_else(|| anyhow!("No existing folder found for {}", path.display()))?;
+    Ok(fs2::available_space(existing)?)
+}
+
+/// Fails with [`InsufficientSpaceError`] when fewer than `needed` bytes are free at `path`.
+pub fn ensure_free_space(path: &Path, needed: u64) -> Result<()> {
+    let available = free_space(path)?;
+    if available < needed {
+        return Err(InsufficientSpaceError { needed, available }.into());
+    }
+    Ok(())
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{value:.1} {}", UNITS[unit])
+    }
+}
+
+/// Lists the mounted volumes, read afresh on every call so drives plugged in
+/// or removed since show up right away. Pseudo file systems are left out.
+#[tauri::command]
+pub fn list_volumes() -> Vec<Volume> {
+    let disks = Disks::new_with_refreshed_list();
+    let mut volumes: Vec<Volume> = disks
+        .list()
+        .iter()
+        .filter(|disk| disk.total_space() > 0 && !is_pseudo(&disk.file_system().to_string_lossy()))
+        .map(|disk| Volume {
+            mount_point: disk.mount_point().to_string_lossy().into_owned(),
+            label: crate::non_empty(disk.name().to_string_lossy().into_owned()),
+            file_system: disk.file_system().to_string_lossy().into_owned(),
+            total_bytes: disk.total_space(),
+            available_bytes: disk.available_space(),
+            removable: disk.is_removable(),
+        })
+        .collect();
+    volumes.sort_by(|a, b| a.mount_point.cmp(&b.mount_point));
+    volumes.dedup_by(|a, b| a.mount_point == b.mount_point);
+    volumes
+}
+
+#[cfg(target_os = "linux")]
+fn is_pseudo(file_system: &str) -> bool {
+    PSEUDO_FILE_SYSTEMS.contains(&file_system)
+}
+
+#[cfg(not(target_os = "linux"))]
+fn is_pseudo(_file_system: &str) -> bool {
+    false
+}
+
+/// Bytes available on the volume holding `path`, which need not exist yet.
+#[tauri::command]
+pub fn get_free_space(path: String) -> Result<u64, CommandError> {
+    if path.trim().is_empty() {
+        return Err(CommandError::invalid_input("Path cannot be empty"));
+    }
+    free_space(&PathBuf::from(&path))
+        .context("Failed to read free space")
+        .map_err(CommandError::from)
+}
@@ -0,0 +1,203 @@
+use crate::error::CommandError;
+use crate::sizes::{self, ScanReporter, SizeJobs, SizeWalk, Visit};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use std::ffi::OsString;
+use std::path::{Components, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use tauri::{AppHandle, Manager, State};
+
+/// Files `analyze_path` reports when the caller does not say.
+const DEFAULT_LARGEST_FILES: usize = 20;
+
+/// A folder's share of an analyzed path.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FolderUsage {
+    path: String,
+    size_bytes: u64,
+    file_count: u64,
+    /// Subfolders down to the requested depth, largest first.
+    children: Vec<FolderUsage>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LargeFile {
+    path: String,
+    size_bytes: u64,
+}
+
+/// Sent once `analyze_path` is done, also when it was cancelled.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathAnalysis {
+    job_id: String,
+    root: FolderUsage,
+    /// Largest first.
+    largest_files: Vec<LargeFile>,
+    /// Links found inside, which are not followed unless asked to.
+    symlinks: Vec<String>,
+    /// Entries skipped because reading them was not permitted.
+    denied: u64,
+    /// Entries skipped for any other error.
+    unreadable: u64,
+    cancelled: bool,
+}
+
+/// Totals for a folder while the walk runs.
+#[derive(Debug, Default)]
+struct Node {
+    size_bytes: u64,
+    file_count: u64,
+    children: HashMap<OsString, Node>,
+}
+
+impl Node {
+    /// Creates the folders in `folders` below this node, down to `depth`
+    /// levels, adding a file of `len` bytes to each when given one.
+    fn record(&mut self, mut folders: Components, depth: usize, len: Option<u64>) {
+        if let Some(len) = len {
+            self.size_bytes += len;
+            self.file_count += 1;
+        }
+        if depth == 0 {
+            return;
+        }
+        if let Some(folder) = folders.next() {
+            let child = self
+                .children
+                .entry(folder.as_os_str().to_os_string())
+                .or_default();
+            child.record(folders, depth - 1, len);
+        }
+    }
+
+    fn into_usage(self, path: PathBuf) -> FolderUsage {
+        let mut children: Vec<FolderUsage> = self
+            .children
+            .into_iter()
+            .map(|(name, child)| {
+                let child_path = path.join(name);
+                child.into_usage(child_path)
+            })
+            .collect();
+        children.sort_by(|a, b| b.size_bytes.cmp(&a.size_bytes).then(a.path.cmp(&b.path)));
+        FolderUsage {
+            path: path.to_string_lossy().into_owned(),
+            size_bytes: self.size_bytes,
+            file_count: self.file_count,
+            children,
+        }
+    }
+}
+
+/// Starts breaking the folder `path` down by subfolder in the background
+/// and returns the job's id at once. Each folder down to `depth` levels
+/// gets its size and number of files, largest first, and the
+/// `largest_files` biggest files are listed. Sends `scan-progress` while it
+/// runs and `path-analysis` with the result; `cancel_scan` stops it. Links
+/// are listed but only followed with `follow_symlinks`.
+#[tauri::command]
+pub fn analyze_path(
+    app: AppHandle,
+    jobs: State<'_, SizeJobs>,
+    path: String,
+    depth: u8,
+    largest_files: Option<usize>,
+    follow_symlinks: Option<bool>,
+) -> Result<String, CommandError> {
+    let root = PathBuf::from(path.trim());
+    if !root.is_dir() {
+        return Err(CommandError::not_found(format!(
+            "{} is not a folder",
+            root.display()
+        )));
+    }
+    let (job_id, cancelled) = jobs.start_scan();
+
+    let id = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let analysis = analyze(
+            &app,
+            &id,
+            &root,
+            usize::from(depth),
+            largest_files.unwrap_or(DEFAULT_LARGEST_FILES),
+            follow_symlinks.unwrap_or(false),
+            &cancelled,
+        );
+        app.state::<SizeJobs>().finish_scan(&id);
+        let _ = app.emit_all("path-analysis", analysis);
+    });
+    Ok(job_id)
+}
+
+fn analyze(
+    app: &AppHandle,
+    job_id: &str,
+    root: &Path,
+    depth: usize,
+    largest: usize,
+    follow_symlinks: bool,
+    cancelled: &AtomicBool,
+) -> PathAnalysis {
+    let mut tree = Node::default();
+    let mut heap: BinaryHeap<Reverse<(u64, PathBuf)>> = BinaryHeap::new();
+    let mut symlinks = Vec::new();
+    let (mut denied, mut unreadable, mut was_cancelled) = (0, 0, false);
+    let mut reporter = ScanReporter::new(app, job_id);
+    let mut current_dir = root.to_path_buf();
+
+    for visit in SizeWalk::new(root, follow_symlinks) {
+        if cancelled.load(Ordering::Relaxed) {
+            was_cancelled = true;
+            break;
+        }
+        reporter.entry(tree.file_count, tree.size_bytes, &current_dir);
+
+        match visit {
+            Ok(Visit::File { path, len }) => {
+                let folder = path
+                    .parent()
+                    .and_then(|parent| parent.strip_prefix(root).ok());
+                let folders = folder.unwrap_or(Path::new("")).components();
+                tree.record(folders, depth, Some(len));
+                heap.push(Reverse((len, path)));
+                if heap.len() > largest {
+                    heap.pop();
+                }
+            }
+            Ok(Visit::Dir(dir)) => {
+                // Empty folders are listed too.
+                if let Ok(relative) = dir.strip_prefix(root) {
+                    tree.record(relative.components(), depth, None);
+                }
+                current_dir = dir;
+            }
+            Ok(Visit::Link(link)) => symlinks.push(link.to_string_lossy().into_owned()),
+            Ok(Visit::Other) => {}
+            Err(error) if sizes::is_denied(&error) => denied += 1,
+            Err(_) => unreadable += 1,
+        }
+    }
+
+    let largest_files = heap
+        .into_sorted_vec()
+        .into_iter()
+        .map(|Reverse((size_bytes, path))| LargeFile {
+            path: path.to_string_lossy().into_owned(),
+            size_bytes,
+        })
+        .collect();
+    PathAnalysis {
+        job_id: job_id.to_string(),
+        root: tree.into_usage(root.to_path_buf()),
+        largest_files,
+        symlinks,
+        denied,
+        unreadable,
+        cancelled: was_cancelled,
+    }
+}
@@ -0,0 +1,230 @@
+use crate::launcher::GameProcess;
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::path::Path;
+
+/// The user dismissed the prompt asking for administrator rights.
+#[cfg(windows)]
+#[derive(Debug, thiserror::Error)]
+#[error("Administrator rights were declined, so the game was not started")]
+pub struct ElevationDeclined;
+
+/// Variables a graphical program needs that pkexec would otherwise drop.
+#[cfg(all(unix, not(target_os = "macos")))]
+const SESSION_VARIABLES: &[&str] = &[
+    "DISPLAY",
+    "WAYLAND_DISPLAY",
+    "XAUTHORITY",
+    "XDG_RUNTIME_DIR",
+];
+
+/// Starts `argv` with administrator rights. Windows shows the UAC prompt;
+/// Linux goes through pkexec.
+#[cfg(windows)]
+pub fn spawn(
+    argv: &[OsString],
+    working_dir: Option<&Path>,
+    env: &HashMap<String, String>,
+) -> Result<GameProcess> {
+    if !env.is_empty() {
+        return Err(anyhow!(
+            "Environment variables cannot be passed to a game run as administrator"
+        ));
+    }
+    Ok(GameProcess::Elevated(windows::run_as(argv, working_dir)?))
+}
+
+/// Starts `argv` with administrator rights. Windows shows the UAC prompt;
+/// Linux goes through pkexec.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn spawn(
+    argv: &[OsString],
+    working_dir: Option<&Path>,
+    env: &HashMap<String, String>,
+) -> Result<GameProcess> {
+    pkexec_command(argv, working_dir, env)
+        .spawn()
+        .map(GameProcess::Child)
+        .map_err(|error| match error.kind() {
+            std::io::ErrorKind::NotFound => {
+                anyhow!("Running a game as root needs pkexec, which is not installed")
+            }
+            _ => anyhow!("Failed to start pkexec: {error}"),
+        })
+}
+
+/// Starts `argv` with administrator rights. Windows shows the UAC prompt;
+/// Linux goes through pkexec.
+#[cfg(target_os = "macos")]
+pub fn spawn(
+    _argv: &[OsString],
+    _working_dir: Option<&Path>,
+    _env: &HashMap<String, String>,
+) -> Result<GameProcess> {
+    Err(anyhow!("Running a game elevated is not supported on macOS"))
+}
+
+/// `pkexec env ... <argv>`. pkexec starts the program with a clean
+/// environment in root's home, so the working folder, the game's variables
+/// and the display session are handed over through `env`.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn pkexec_command(
+    argv: &[OsString],
+    working_dir: Option<&Path>,
+    env: &HashMap<String, String>,
+) -> std::process::Command {
+    let mut command = std::process::Command::new("pkexec");
+    command.arg("env");
+    if let Some(dir) = working_dir {
+        let mut chdir = OsString::from("--chdir=");
+        chdir.push(dir);
+        command.arg(chdir);
+    }
+    // Options have to come before the assignments.
+    for (name, _) in env.iter().filter(|(_, value)| value.is_empty()) {
+        command.args(["-u", name]);
+    }
+    for name in SESSION_VARIABLES {
+        if let (false, Some(value)) = (env.contains_key(*name), std::env::var_os(name)) {
+            let mut assignment = OsString::from(format!("{name}="));
+            assignment.push(value);
+            command.arg(assignment);
+        }
+    }
+    for (name, value) in env.iter().filter(|(_, value)| !value.is_empty()) {
+        command.arg(format!("{name}={value}"));
+    }
+    command.args(argv);
+    command
+}
+
+#[cfg(windows)]
+pub mod windows {
+    use super::ElevationDeclined;
+    use anyhow::{anyhow, Result};
+    use std::ffi::{OsStr, OsString};
+    use std::iter;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows_sys::Win32::Foundation::{CloseHandle, ERROR_CANCELLED, HANDLE};
+    use windows_sys::Win32::System::Threading::{GetProcessId, WaitForSingleObject, INFINITE};
+    use windows_sys::Win32::UI::Shell::{
+        ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+    };
+    use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+    /// A process started through the `runas` verb. It is not our child, so
+    /// it is tracked through its handle instead of `std::process::Child`.
+    #[derive(Debug)]
+    pub struct ElevatedProcess {
+        handle: HANDLE,
+        pid: u32,
+    }
+
+    // The handle is only used to wait on and close the process, which any thread may do.
+    unsafe impl Send for ElevatedProcess {}
+
+    impl ElevatedProcess {
+        pub fn id(&self) -> u32 {
+            self.pid
+        }
+
+        pub fn wait(&mut self) {
+            unsafe {
+                WaitForSingleObject(self.handle, INFINITE);
+            }
+        }
+    }
+
+    impl Drop for ElevatedProcess {
+        fn drop(&mut self) {
+            unsafe {
+                CloseHandle(self.handle);
+            }
+        }
+    }
+
+    pub fn run_as(argv: &[OsString], working_dir: Option<&Path>) -> Result<ElevatedProcess> {
+        let (program, args) = argv
+            .split_first()
+            .ok_or_else(|| anyhow!("Nothing to start"))?;
+        let verb = wide(OsStr::new("runas"));
+        let file = wide(program);
+        let parameters = wide(&join_args(args));
+        let directory = working_dir.map(|dir| wide(dir.as_os_str()));
+
+        let mut info: SHELLEXECUTEINFOW = unsafe { std::mem::zeroed() };
+        info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+        info.fMask = SEE_MASK_NOCLOSEPROCESS;
+        info.lpVerb = verb.as_ptr();
+        info.lpFile = file.as_ptr();
+        info.lpParameters = parameters.as_ptr();
+        info.lpDirectory = directory
+            .as_ref()
+            .map_or(std::ptr::null(), |dir| dir.as_ptr());
+        info.nShow = SW_SHOWNORMAL;
+
+        if unsafe { ShellExecuteExW(&mut info) } == 0 {
+            let error = std::io::Error::last_os_error();
+            if error.raw_os_error() == Some(ERROR_CANCELLED as i32) {
+                return Err(ElevationDeclined.into());
+            }
+            return Err(anyhow!(
+                "Failed to start {} as administrator: {error}",
+                Path::new(program).display()
+            ));
+        }
+        if info.hProcess.is_null() {
+            return Err(anyhow!(
+                "{} was started but did not report its process",
+                Path::new(program).display()
+            ));
+        }
+        let pid = unsafe { GetProcessId(info.hProcess) };
+        Ok(ElevatedProcess {
+            handle: info.hProcess,
+            pid,
+        })
+    }
+
+    fn wide(value: &OsStr) -> Vec<u16> {
+        value.encode_wide().chain(iter::once(0)).collect()
+    }
+
+    fn join_args(args: &[OsString]) -> OsString {
+        let quoted: Vec<String> = args
+            .iter()
+            .map(|arg| quote_arg(&arg.to_string_lossy()))
+            .collect();
+        quoted.join(" ").into()
+    }
+
+    /// Quotes `arg` so `CommandLineToArgvW` reads it back unchanged:
+    /// backslashes only need doubling where they precede a quote.
+    fn quote_arg(arg: &str) -> String {
+        if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+            return arg.to_string();
+        }
+        let mut quoted = String::from('"');
+        let mut backslashes = 0;
+        for c in arg.chars() {
+            match c {
+                '\\' => backslashes += 1,
+                '"' => {
+                    quoted.extend(iter::repeat_n('\\', backslashes * 2 + 1));
+                    quoted.push('"');
+                    backslashes = 0;
+                }
+                c => {
+                    quoted.extend(iter::repeat_n('\\', backslashes));
+                    quoted.push(c);
+                    backslashes = 0;
+                }
+            }
+        }
+        quoted.extend(iter::repeat_n('\\', backslashes * 2));
+        quoted.push('"');
+        quoted
+    }
+}
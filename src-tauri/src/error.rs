@@ -0,0 +1,168 @@
+use crate::disk::InsufficientSpaceError;
+use crate::downloads;
+use crate::library_format::NewerFormatError;
+use crate::library_store::NewerSchemaError;
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::error::Error as StdError;
+use std::fmt::Display;
+use std::io;
+
+/// What kind of failure a command ran into. The frontend branches on this
+/// rather than on the message, which is meant for people and logs.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ErrorCode {
+    NotFound,
+    AlreadyExists,
+    InvalidInput,
+    /// The target is busy or in the wrong state, such as a running game or a
+    /// download that already finished.
+    Conflict,
+    PermissionDenied,
+    InsufficientSpace,
+    Io,
+    Network,
+    ChecksumMismatch,
+    /// Needs setting up first, such as metadata credentials.
+    NotConfigured,
+    RateLimited,
+    /// Data written by a newer version, or a platform without the feature.
+    Unsupported,
+    /// An archive that is encrypted and cannot be unpacked without its
+    /// password.
+    PasswordRequired,
+    /// A download restored without the sign-in headers it was queued with.
+    CredentialsRequired,
+    Internal,
+}
+
+/// The error every command returns.
+#[derive(Debug, Clone, Serialize, thiserror::Error)]
+#[serde(rename_all = "camelCase")]
+#[error("{message}")]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// Facts the frontend may show or act on, depending on the code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub details: Option<Value>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: None,
+        }
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn already_exists(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::AlreadyExists, message)
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn internal(message: impl Display) -> Self {
+        Self::new(ErrorCode::Internal, message.to_string())
+    }
+
+    /// Reports a rejected value, such as a failed payload check.
+    pub fn invalid(error: impl Display) -> Self {
+        Self::invalid_input(format!("{error:#}"))
+    }
+
+    pub fn game_not_found(id: &str) -> Self {
+        Self::not_found(format!("Game {id} not found"))
+    }
+
+    pub fn with_details(mut self, details: Value) -> Self {
+        self.details = Some(details);
+        self
+    }
+}
+
+/// Gives internal errors their code from the first cause in the chain that
+/// has one, so the same failure gets the same code in every command. The
+/// message keeps the whole chain.
+impl From<anyhow::Error> for CommandError {
+    fn from(error: anyhow::Error) -> Self {
+        let message = format!("{error:#}");
+        for cause in error.chain() {
+            if let Some(inner) = cause.downcast_ref::<CommandError>() {
+                return Self {
+                    message,
+                    ..inner.clone()
+                };
+            }
+            if let Some((code, details)) = classify(cause) {
+                return Self {
+                    code,
+                    message,
+                    details,
+                };
+            }
+        }
+        Self::new(ErrorCode::Internal, message)
+    }
+}
+
+/// A background task that panicked or was cancelled.
+impl From<tauri::Error> for CommandError {
+    fn from(error: tauri::Error) -> Self {
+        Self::internal(error)
+    }
+}
+
+/// Code for failures the frontend can tell the user something useful about.
+pub fn code_of(error: &anyhow::Error) -> ErrorCode {
+    error
+        .chain()
+        .find_map(|cause| match cause.downcast_ref::<CommandError>() {
+            Some(inner) => Some(inner.code),
+            None => classify(cause).map(|(code, _)| code),
+        })
+        .unwrap_or(ErrorCode::Internal)
+}
+
+fn classify(cause: &(dyn StdError + 'static)) -> Option<(ErrorCode, Option<Value>)> {
+    if let Some(error) = cause.downcast_ref::<InsufficientSpaceError>() {
+        let details = json!({ "needed": error.needed, "available": error.available });
+        return Some((ErrorCode::InsufficientSpace, Some(details)));
+    }
+    if let Some(error) = cause.downcast_ref::<downloads::ChecksumMismatchError>() {
+        let details = json!({ "expected": error.expected, "actual": error.actual });
+        return Some((ErrorCode::ChecksumMismatch, Some(details)));
+    }
+    if cause.is::<NewerFormatError>() || cause.is::<NewerSchemaError>() {
+        return Some((ErrorCode::Unsupported, None));
+    }
+    #[cfg(windows)]
+    if cause.is::<crate::elevation::ElevationDeclined>() {
+        return Some((ErrorCode::PermissionDenied, None));
+    }
+    if cause.is::<reqwest::Error>() || downloads::is_network_error(cause) {
+        return Some((ErrorCode::Network, None));
+    }
+    let error = cause.downcast_ref::<io::Error>()?;
+    let code = match error.kind() {
+        io::ErrorKind::NotFound => ErrorCode::NotFound,
+        io::ErrorKind::AlreadyExists => ErrorCode::AlreadyExists,
+        io::ErrorKind::PermissionDenied => ErrorCode::PermissionDenied,
+        io::ErrorKind::StorageFull => ErrorCode::InsufficientSpace,
+        io::ErrorKind::InvalidInput => ErrorCode::InvalidInput,
+        _ => ErrorCode::Io,
+    };
+    Some((code, None))
+}
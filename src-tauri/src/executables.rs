@@ -0,0 +1,244 @@
+use crate::error::CommandError;
+use crate::search;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Extensions executables are recognised by unless told otherwise.
+pub const DEFAULT_EXTENSIONS: &[&str] = &["exe"];
+/// How deep inside a game folder executables are looked for.
+const MAX_DEPTH: usize = 4;
+/// Most candidates `list_executables` returns.
+const MAX_RESULTS: usize = 50;
+/// Name starts of uninstallers and installers.
+const IGNORED_PREFIXES: &[&str] = &["unins", "setup"];
+/// Folders holding DirectX and Visual C++ installers rather than the game.
+const IGNORED_FOLDERS: &[&str] = &["directx"];
+const IGNORED_FRAGMENT: &str = "redist";
+
+/// A file that may be the game's executable.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutableCandidate {
+    pub path: String,
+    /// `path` relative to the folder that was searched.
+    pub relative_path: String,
+    pub size_bytes: u64,
+    /// Product name from the version resource; only read on Windows.
+    pub product_name: Option<String>,
+    #[serde(skip)]
+    depth: usize,
+}
+
+/// Executables inside `path`, most likely first: big files, files named like
+/// `title`, files carrying a product name and files near the top score
+/// higher. Installers, uninstallers and redistributables are skipped unless
+/// `include_ignored` is set. Walks at most a few folders deep and returns a
+/// limited number of candidates, off the main thread.
+#[tauri::command]
+pub async fn list_executables(
+    path: String,
+    title: Option<String>,
+    include_ignored: Option<bool>,
+) -> Result<Vec<ExecutableCandidate>, CommandError> {
+    let folder = PathBuf::from(path.trim());
+    if !folder.is_dir() {
+        return Err(CommandError::not_found(format!(
+            "{} is not a folder",
+            folder.display()
+        )));
+    }
+    let extensions: Vec<String> = DEFAULT_EXTENSIONS
+        .iter()
+        .map(|extension| extension.to_string())
+        .collect();
+    let include_ignored = include_ignored.unwrap_or(false);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let mut candidates = find(&folder, &extensions, include_ignored);
+        for candidate in &mut candidates {
+            candidate.product_name = product_name(Path::new(&candidate.path));
+        }
+        let title = title.as_deref().map(search::title_key);
+        let mut scored: Vec<(f64, ExecutableCandidate)> = candidates
+            .into_iter()
+            .map(|candidate| (score(&candidate, title.as_deref()), candidate))
+            .collect();
+        scored.sort_by(|a, b| b.0.total_cmp(&a.0));
+        scored
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_, candidate)| candidate)
+            .collect()
+    })
+    .await
+    .map_err(CommandError::from)
+}
+
+/// Files in `folder` with one of `extensions`, in walk order. Installers,
+/// uninstallers and redistributable folders are skipped unless
+/// `include_ignored` is set.
+pub fn find(
+    folder: &Path,
+    extensions: &[String],
+    include_ignored: bool,
+) -> Vec<ExecutableCandidate> {
+    WalkDir::new(folder)
+        .max_depth(MAX_DEPTH)
+        .into_iter()
+        .filter_entry(|entry| {
+            entry.depth() == 0
+                || include_ignored
+                || !is_ignored(&entry.file_name().to_string_lossy())
+        })
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            entry.path().extension().is_some_and(|extension| {
+                extensions.contains(&extension.to_string_lossy().to_lowercase())
+            })
+        })
+        .filter_map(|entry| {
+            let size_bytes = entry.metadata().ok()?.len();
+            Some(ExecutableCandidate {
+                path: entry.path().to_string_lossy().into_owned(),
+                relative_path: entry
+                    .path()
+                    .strip_prefix(folder)
+                    .unwrap_or(entry.path())
+                    .to_string_lossy()
+                    .into_owned(),
+                size_bytes,
+                product_name: None,
+                depth: entry.depth(),
+            })
+        })
+        .collect()
+}
+
+fn is_ignored(name: &str) -> bool {
+    let name = name.to_lowercase();
+    IGNORED_PREFIXES
+        .iter()
+        .any(|prefix| name.starts_with(prefix))
+        || IGNORED_FOLDERS.contains(&name.as_str())
+        || name.contains(IGNORED_FRAGMENT)
+}
+
+/// Higher is more likely. Size counts logarithmically so a 2 GB file does
+/// not drown out a well-named 50 MB one.
+fn score(candidate: &ExecutableCandidate, title_key: Option<&str>) -> f64 {
+    let stem = Path::new(&candidate.relative_path)
+        .file_stem()
+        .map(|stem| search::title_key(&stem.to_string_lossy()))
+        .unwrap_or_default();
+    let mut score = (candidate.size_bytes as f64 + 1.0).log2();
+    score -= candidate.depth as f64;
+    if let Some(title_key) = title_key {
+        score += 20.0 * similarity(&stem, title_key);
+        if let Some(product_name) = &candidate.product_name {
+            score += 10.0 * similarity(&search::title_key(product_name), title_key);
+        }
+    }
+    if candidate.product_name.is_some() {
+        score += 2.0;
+    }
+    score
+}
+
+/// How alike two title keys are, from 0 to 1: 1 when they match once spaces
+/// are ignored ("witcher3" and "witcher 3"), otherwise the share of words
+/// they have in common.
+fn similarity(a: &str, b: &str) -> f64 {
+    if a.is_empty() || b.is_empty() {
+        return 0.0;
+    }
+    if a.replace(' ', "") == b.replace(' ', "") {
+        return 1.0;
+    }
+    let a: HashSet<&str> = a.split(' ').collect();
+    let b: HashSet<&str> = b.split(' ').collect();
+    a.intersection(&b).count() as f64 / a.union(&b).count() as f64
+}
+
+#[cfg(windows)]
+fn product_name(path: &Path) -> Option<String> {
+    windows::product_name(path)
+}
+
+#[cfg(not(windows))]
+fn product_name(_path: &Path) -> Option<String> {
+    None
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::ffi::c_void;
+    use std::iter;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileVersionInfoSizeW, GetFileVersionInfoW, VerQueryValueW,
+    };
+
+    /// The `ProductName` string of the file's version resource, in the first
+    /// language it lists.
+    pub fn product_name(path: &Path) -> Option<String> {
+        let file: Vec<u16> = path
+            .as_os_str()
+            .encode_wide()
+            .chain(iter::once(0))
+            .collect();
+        let size = unsafe { GetFileVersionInfoSizeW(file.as_ptr(), std::ptr::null_mut()) };
+        if size == 0 {
+            return None;
+        }
+        let mut info = vec![0u8; size as usize];
+        if unsafe { GetFileVersionInfoW(file.as_ptr(), 0, size, info.as_mut_ptr().cast()) } == 0 {
+            return None;
+        }
+
+        let translation = query(&info, "\\VarFileInfo\\Translation", 1)?;
+        if translation.len() < 4 {
+            return None;
+        }
+        let language = u16::from_le_bytes([translation[0], translation[1]]);
+        let code_page = u16::from_le_bytes([translation[2], translation[3]]);
+        let name = query(
+            &info,
+            &format!("\\StringFileInfo\\{language:04x}{code_page:04x}\\ProductName"),
+            2,
+        )?;
+        let units: Vec<u16> = name
+            .chunks_exact(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .take_while(|unit| *unit != 0)
+            .collect();
+        let name = String::from_utf16_lossy(&units);
+        let name = name.trim();
+        (!name.is_empty()).then(|| name.to_string())
+    }
+
+    /// The bytes of a value in a version resource, whose length Windows
+    /// reports in units of `unit_size` bytes: 2 for strings, 1 otherwise.
+    fn query<'a>(info: &'a [u8], sub_block: &str, unit_size: usize) -> Option<&'a [u8]> {
+        let sub_block: Vec<u16> = sub_block.encode_utf16().chain(iter::once(0)).collect();
+        let mut value: *mut c_void = std::ptr::null_mut();
+        let mut len = 0u32;
+        let found = unsafe {
+            VerQueryValueW(
+                info.as_ptr().cast(),
+                sub_block.as_ptr(),
+                &mut value,
+                &mut len,
+            )
+        };
+        if found == 0 || value.is_null() {
+            return None;
+        }
+        let bytes = len as usize * unit_size;
+        let offset = (value as usize).checked_sub(info.as_ptr() as usize)?;
+        info.get(offset..offset.checked_add(bytes)?)
+    }
+}
@@ -0,0 +1,736 @@
+use crate::error::{self, CommandError, ErrorCode};
+use crate::{archives, sizes, GameEntry, InstallStatus, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use sevenz_rust::{Password, SevenZReader};
+use std::collections::{HashMap, VecDeque};
+use std::env;
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+use zip::result::ZipError;
+use zip::ZipArchive;
+
+const PROGRESS_EVERY: Duration = Duration::from_millis(200);
+const BUFFER_SIZE: usize = 1024 * 1024;
+/// Lines of a program's output kept for explaining why it failed.
+const OUTPUT_LINES: usize = 20;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtractProgress {
+    id: String,
+    game_id: Option<String>,
+    processed_bytes: u64,
+    total_bytes: u64,
+    current_file: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtractComplete {
+    id: String,
+    game_id: Option<String>,
+    destination: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtractError {
+    id: String,
+    game_id: Option<String>,
+    message: String,
+    code: ErrorCode,
+    /// Stopped by `cancel_extraction` rather than by a failure.
+    cancelled: bool,
+}
+
+/// What can be unpacked on this machine, so the frontend can tell what is
+/// missing before offering to extract.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractionSupport {
+    /// Extensions `extract_archive` accepts right now.
+    formats: Vec<&'static str>,
+    /// The program RAR archives are unpacked with, when one was found.
+    rar_tool: Option<String>,
+    /// What to install for RAR archives when no program was found.
+    rar_hint: Option<String>,
+}
+
+/// Running extractions, each with its flag to stop it.
+#[derive(Debug, Default)]
+pub struct Extractions {
+    running: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Format {
+    Zip,
+    /// A single `.7z` or a set of `.7z.001` volumes.
+    SevenZip,
+    /// A single `.rar` or any volume of a RAR set.
+    Rar,
+}
+
+impl Format {
+    fn of(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_ascii_lowercase();
+        let (stem, extension) = name.rsplit_once('.')?;
+        let numbered =
+            |digits: &str| !digits.is_empty() && digits.bytes().all(|b| b.is_ascii_digit());
+        match extension {
+            "zip" => Some(Self::Zip),
+            "7z" => Some(Self::SevenZip),
+            "rar" => Some(Self::Rar),
+            _ if extension.strip_prefix('r').is_some_and(numbered) => Some(Self::Rar),
+            _ if stem.ends_with(".7z") && numbered(extension) => Some(Self::SevenZip),
+            _ => None,
+        }
+    }
+}
+
+/// Programs that can unpack RAR archives.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ToolKind {
+    UnRar,
+    SevenZip,
+}
+
+/// An installed program `extract_archive` hands archives to.
+#[derive(Debug, Clone)]
+struct Tool {
+    kind: ToolKind,
+    program: PathBuf,
+}
+
+impl Tool {
+    /// The program for RAR archives: UnRAR when installed, otherwise 7-Zip.
+    fn for_rar() -> Option<Self> {
+        Self::find(ToolKind::UnRar).or_else(|| Self::find(ToolKind::SevenZip))
+    }
+
+    fn find(kind: ToolKind) -> Option<Self> {
+        let (names, installed): (&[&str], &str) = match kind {
+            ToolKind::UnRar => (&["unrar", "UnRAR.exe"], r"WinRAR\UnRAR.exe"),
+            ToolKind::SevenZip => (&["7z", "7zz", "7z.exe"], r"7-Zip\7z.exe"),
+        };
+        let on_path = env::var_os("PATH").and_then(|paths| {
+            env::split_paths(&paths)
+                .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+                .find(|program| program.is_file())
+        });
+        let program = on_path.or_else(|| program_files(installed))?;
+        Some(Self { kind, program })
+    }
+
+    fn command(&self, archive: &Path, destination: &Path) -> Command {
+        let mut command = Command::new(&self.program);
+        match self.kind {
+            // `-p-` stops it asking for a password nobody could type in; a
+            // trailing separator makes the destination a folder.
+            ToolKind::UnRar => command
+                .args(["x", "-o+", "-p-", "-y"])
+                .arg(archive)
+                .arg(destination.join("")),
+            ToolKind::SevenZip => {
+                let mut output = OsString::from("-o");
+                output.push(destination);
+                command
+                    .args(["x", "-aoa", "-y", "-bsp1"])
+                    .arg(output)
+                    .arg(archive)
+            }
+        };
+        command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            command.creation_flags(CREATE_NO_WINDOW);
+        }
+        command
+    }
+
+    fn name(&self) -> &'static str {
+        match self.kind {
+            ToolKind::UnRar => "UnRAR",
+            ToolKind::SevenZip => "7-Zip",
+        }
+    }
+}
+
+/// `relative` below one of the Program Files folders, when installed there.
+#[cfg(windows)]
+fn program_files(relative: &str) -> Option<PathBuf> {
+    ["ProgramFiles", "ProgramFiles(x86)"]
+        .into_iter()
+        .filter_map(env::var_os)
+        .map(|dir| PathBuf::from(dir).join(relative))
+        .find(|program| program.is_file())
+}
+
+#[cfg(not(windows))]
+fn program_files(_relative: &str) -> Option<PathBuf> {
+    None
+}
+
+const RAR_HINT: &str = "Install UnRAR or 7-Zip to extract RAR archives";
+
+/// How one archive is unpacked.
+enum Unpacker {
+    Zip,
+    /// The volumes of a 7z archive, read back to back.
+    SevenZip(Vec<PathBuf>),
+    /// An installed program given the first of `volumes`.
+    Program(Tool, Vec<PathBuf>),
+}
+
+/// Raised when an extraction is cancelled, so the worker can tell it apart
+/// from a failure.
+#[derive(Debug, thiserror::Error)]
+#[error("Extraction cancelled")]
+struct Cancelled;
+
+/// Unpacks the zip, 7z or RAR archive `source` into the folder
+/// `destination` in the background and returns the extraction's id at once.
+/// `source` may be any volume of a split 7z or RAR archive; every volume
+/// up to the last one found must be there before anything is unpacked. RAR
+/// archives need UnRAR or 7-Zip installed, see `extraction_support`. Sends
+/// `extract-progress` while it runs, then `extract-complete` or
+/// `extract-error`, with the `password-required` code for encrypted
+/// archives. Members whose names would land outside `destination` fail the
+/// extraction. On failure or cancellation, a destination the extraction
+/// created is removed again. With `game_id`, the game is marked `Installed`
+/// in `destination` once done, and its size measured again.
+#[tauri::command]
+pub fn extract_archive(
+    app: AppHandle,
+    library: State<'_, Library>,
+    extractions: State<'_, Extractions>,
+    source: String,
+    destination: String,
+    game_id: Option<String>,
+) -> Result<String, CommandError> {
+    let source = PathBuf::from(source.trim());
+    if !source.is_file() {
+        return Err(CommandError::not_found(format!(
+            "{} does not exist",
+            source.display()
+        )));
+    }
+    let format = Format::of(&source).ok_or_else(|| {
+        CommandError::new(
+            ErrorCode::Unsupported,
+            format!("{} is not a zip, 7z or RAR archive", source.display()),
+        )
+    })?;
+    let unpacker = match format {
+        Format::Zip => Unpacker::Zip,
+        Format::SevenZip => Unpacker::SevenZip(archives::volume_set(&source)?),
+        Format::Rar => {
+            let volumes = archives::volume_set(&source)?;
+            let tool = Tool::for_rar()
+                .ok_or_else(|| CommandError::new(ErrorCode::NotConfigured, RAR_HINT))?;
+            Unpacker::Program(tool, volumes)
+        }
+    };
+    let destination = PathBuf::from(destination.trim());
+    if !destination.is_absolute() {
+        return Err(CommandError::invalid_input(
+            "The destination must be an absolute path",
+        ));
+    }
+    if let Some(game_id) = &game_id {
+        let game = library
+            .games(&app)
+            .context("Failed to load library")?
+            .into_iter()
+            .find(|game| &game.id == game_id)
+            .ok_or_else(|| CommandError::game_not_found(game_id))?;
+        if game.download_id.is_some() {
+            return Err(CommandError::conflict(format!(
+                "{} is still downloading",
+                game.title
+            )));
+        }
+    }
+
+    let id = Uuid::new_v4().to_string();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    extractions
+        .running
+        .lock()
+        .expect("extractions poisoned")
+        .insert(id.clone(), cancelled.clone());
+
+    let worker_id = id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let created = !destination.exists();
+        let mut extractor = Extractor {
+            app: &app,
+            id: &worker_id,
+            game_id: game_id.as_deref(),
+            destination: &destination,
+            cancelled: &cancelled,
+            processed_bytes: 0,
+            total_bytes: 0,
+            last_progress: Instant::now(),
+            buffer: vec![0; BUFFER_SIZE],
+        };
+        let extracted = fs::create_dir_all(&destination)
+            .with_context(|| format!("Failed to create {}", destination.display()))
+            .and_then(|()| match &unpacker {
+                Unpacker::Zip => extractor.zip(&source),
+                Unpacker::SevenZip(volumes) => extractor.seven_zip(volumes),
+                Unpacker::Program(tool, volumes) => extractor.program(tool, volumes),
+            });
+        if extracted.is_err() && created {
+            let _ = fs::remove_dir_all(&destination);
+        }
+        let result = extracted.and_then(|()| match &game_id {
+            Some(game_id) => mark_installed(&app, game_id, &destination),
+            None => Ok(()),
+        });
+        app.state::<Extractions>()
+            .running
+            .lock()
+            .expect("extractions poisoned")
+            .remove(&worker_id);
+
+        match result {
+            Ok(()) => {
+                let _ = app.emit_all(
+                    "extract-complete",
+                    ExtractComplete {
+                        id: worker_id,
+                        game_id,
+                        destination: destination.to_string_lossy().into_owned(),
+                    },
+                );
+            }
+            Err(failure) => {
+                let _ = app.emit_all(
+                    "extract-error",
+                    ExtractError {
+                        id: worker_id,
+                        game_id,
+                        message: format!("{failure:#}"),
+                        code: error::code_of(&failure),
+                        cancelled: failure.is::<Cancelled>(),
+                    },
+                );
+            }
+        }
+    });
+    Ok(id)
+}
+
+/// Stops the extraction `id`, which then sends `extract-error`.
+#[tauri::command]
+pub fn cancel_extraction(
+    extractions: State<'_, Extractions>,
+    id: String,
+) -> Result<(), CommandError> {
+    let running = extractions.running.lock().expect("extractions poisoned");
+    let cancelled = running
+        .get(&id)
+        .ok_or_else(|| CommandError::not_found(format!("Extraction {id} not found")))?;
+    cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+/// Reports which archives `extract_archive` can unpack here, and for RAR
+/// which program it would use or what to install.
+#[tauri::command]
+pub fn extraction_support() -> ExtractionSupport {
+    let rar_tool = Tool::for_rar();
+    let mut formats = vec!["zip", "7z", "7z.001"];
+    if rar_tool.is_some() {
+        formats.push("rar");
+    }
+    ExtractionSupport {
+        formats,
+        rar_hint: rar_tool.is_none().then(|| RAR_HINT.to_string()),
+        rar_tool: rar_tool.map(|tool| tool.program.to_string_lossy().into_owned()),
+    }
+}
+
+fn mark_installed(app: &AppHandle, game_id: &str, destination: &Path) -> Result<()> {
+    let game: GameEntry = app.state::<Library>().update(app, |games| {
+        let game = games
+            .iter_mut()
+            .find(|game| game.id == game_id)
+            .ok_or_else(|| CommandError::game_not_found(game_id))?;
+        game.install_path = Some(destination.to_string_lossy().into_owned());
+        game.status = InstallStatus::Installed;
+        game.updated_at = Utc::now();
+        Ok(game.clone())
+    })?;
+    crate::emit_library_updated(app, vec![game.id.clone()]);
+    sizes::measure(app, &game);
+    Ok(())
+}
+
+/// Writes the members of one archive below `destination`, reporting
+/// `extract-progress` against the unpacked size, or against the size of
+/// the volumes when a program does the unpacking.
+struct Extractor<'a> {
+    app: &'a AppHandle,
+    id: &'a str,
+    game_id: Option<&'a str>,
+    destination: &'a Path,
+    cancelled: &'a AtomicBool,
+    processed_bytes: u64,
+    total_bytes: u64,
+    last_progress: Instant,
+    buffer: Vec<u8>,
+}
+
+impl Extractor<'_> {
+    fn zip(&mut self, source: &Path) -> Result<()> {
+        let file =
+            File::open(source).with_context(|| format!("Failed to open {}", source.display()))?;
+        let mut archive = ZipArchive::new(file)
+            .with_context(|| format!("Failed to read {}", source.display()))?;
+        for index in 0..archive.len() {
+            self.total_bytes += archive.by_index_raw(index)?.size();
+        }
+        for index in 0..archive.len() {
+            let mut member = archive.by_index(index).map_err(|error| match error {
+                ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => {
+                    password_required(source)
+                }
+                error => error.into(),
+            })?;
+            let name = member.name().to_string();
+            let target = member_path(self.destination, &name)?;
+            if member.is_dir() {
+                fs::create_dir_all(&target)
+                    .with_context(|| format!("Failed to create {}", target.display()))?;
+            } else {
+                self.write(&mut member, &name, &target)?;
+            }
+        }
+        self.emit("");
+        Ok(())
+    }
+
+    fn seven_zip(&mut self, volumes: &[PathBuf]) -> Result<()> {
+        let source = &volumes[0];
+        let seven_zip_error = |error: sevenz_rust::Error| -> anyhow::Error {
+            match error {
+                sevenz_rust::Error::PasswordRequired | sevenz_rust::Error::MaybeBadPassword(_) => {
+                    password_required(source)
+                }
+                error => anyhow::Error::new(error)
+                    .context(format!("Failed to read {}", source.display())),
+            }
+        };
+        let joined = Volumes::open(volumes)?;
+        let len = joined.len;
+        let mut reader =
+            SevenZReader::new(joined, len, Password::empty()).map_err(seven_zip_error)?;
+        self.total_bytes = reader.archive().files.iter().map(|file| file.size()).sum();
+        // The reader wants its own error type back, so ours waits here.
+        let mut failure = None;
+        reader
+            .for_each_entries(|member, data| {
+                let name = member.name().to_string();
+                let written = member_path(self.destination, &name).and_then(|target| {
+                    if member.is_directory() {
+                        fs::create_dir_all(&target)
+                            .with_context(|| format!("Failed to create {}", target.display()))
+                    } else {
+                        self.write(data, &name, &target)
+                    }
+                });
+                match written {
+                    Ok(()) => Ok(true),
+                    Err(error) => {
+                        failure = Some(error);
+                        Ok(false)
+                    }
+                }
+            })
+            .map_err(seven_zip_error)?;
+        if let Some(failure) = failure {
+            return Err(failure);
+        }
+        self.emit("");
+        Ok(())
+    }
+
+    /// Runs `tool` on the first of `volumes`, turning the percentage it
+    /// prints into bytes of the whole set.
+    fn program(&mut self, tool: &Tool, volumes: &[PathBuf]) -> Result<()> {
+        let source = &volumes[0];
+        self.total_bytes = volumes
+            .iter()
+            .filter_map(|volume| fs::metadata(volume).ok())
+            .map(|metadata| metadata.len())
+            .sum();
+        let mut child = tool
+            .command(source, self.destination)
+            .spawn()
+            .with_context(|| format!("Failed to run {}", tool.program.display()))?;
+        let (sender, receiver) = mpsc::channel();
+        let stdout = child.stdout.take().expect("stdout is piped");
+        let stderr = child.stderr.take().expect("stderr is piped");
+        let stdout_sender = sender.clone();
+        let stdout = thread::spawn(move || follow_output(stdout, stdout_sender));
+        let stderr = thread::spawn(move || follow_output(stderr, sender));
+
+        let status = loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(Cancelled.into());
+            }
+            if let Some(status) = child
+                .try_wait()
+                .with_context(|| format!("Failed to wait for {}", tool.name()))?
+            {
+                break status;
+            }
+            if let Ok((percent, file)) = receiver.recv_timeout(PROGRESS_EVERY) {
+                self.processed_bytes = self.total_bytes * percent / 100;
+                if self.last_progress.elapsed() >= PROGRESS_EVERY {
+                    self.last_progress = Instant::now();
+                    self.emit(&file);
+                }
+            }
+        };
+        let mut output = stdout.join().unwrap_or_default();
+        output.extend(stderr.join().unwrap_or_default());
+
+        if status.success() {
+            self.processed_bytes = self.total_bytes;
+            self.emit("");
+            return Ok(());
+        }
+        // UnRAR exits with 11 for a wrong password.
+        let bad_password = tool.kind == ToolKind::UnRar && status.code() == Some(11);
+        if bad_password
+            || output
+                .iter()
+                .any(|line| line.to_ascii_lowercase().contains("password"))
+        {
+            return Err(password_required(source));
+        }
+        let reason = output.back().cloned().unwrap_or_else(|| status.to_string());
+        Err(anyhow!(
+            "{} failed to unpack {}: {reason}",
+            tool.name(),
+            source.display()
+        ))
+    }
+
+    fn write(&mut self, data: &mut dyn Read, name: &str, target: &Path) -> Result<()> {
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = File::create(target)
+            .with_context(|| format!("Failed to create {}", target.display()))?;
+        loop {
+            if self.cancelled.load(Ordering::Relaxed) {
+                return Err(Cancelled.into());
+            }
+            let read = data
+                .read(&mut self.buffer)
+                .with_context(|| format!("Failed to unpack {name}"))?;
+            if read == 0 {
+                break;
+            }
+            file.write_all(&self.buffer[..read])
+                .with_context(|| format!("Failed to write {}", target.display()))?;
+            self.processed_bytes += read as u64;
+            if self.last_progress.elapsed() >= PROGRESS_EVERY {
+                self.last_progress = Instant::now();
+                self.emit(name);
+            }
+        }
+        Ok(())
+    }
+
+    fn emit(&self, current_file: &str) {
+        let _ = self.app.emit_all(
+            "extract-progress",
+            ExtractProgress {
+                id: self.id.to_string(),
+                game_id: self.game_id.map(str::to_string),
+                processed_bytes: self.processed_bytes,
+                total_bytes: self.total_bytes,
+                current_file: current_file.to_string(),
+            },
+        );
+    }
+}
+
+/// Where the archive member `name` goes below `destination`. Names that
+/// could land anywhere else, being absolute or climbing out with `..`, are
+/// refused rather than cleaned up.
+fn member_path(destination: &Path, name: &str) -> Result<PathBuf> {
+    let outside = || -> anyhow::Error {
+        CommandError::invalid_input(format!(
+            "Archive member {name} points outside the destination"
+        ))
+        .into()
+    };
+    if name.starts_with(['/', '\\']) {
+        return Err(outside());
+    }
+    let mut path = destination.to_path_buf();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return Err(outside()),
+            // `:` would name a drive or an alternate data stream on Windows.
+            part if part.contains(':') => return Err(outside()),
+            part => path.push(part),
+        }
+    }
+    Ok(path)
+}
+
+fn password_required(archive: &Path) -> anyhow::Error {
+    CommandError::new(
+        ErrorCode::PasswordRequired,
+        format!("{} is protected by a password", archive.display()),
+    )
+    .into()
+}
+
+/// Reads what an unpacking program prints, sending each percentage with
+/// the file it last named, and returns the last lines that were not
+/// progress. Progress is redrawn with carriage returns and backspaces, so
+/// those end a line too.
+fn follow_output(output: impl Read, progress: mpsc::Sender<(u64, String)>) -> VecDeque<String> {
+    let mut lines = VecDeque::new();
+    let mut current_file = String::new();
+    let mut line = Vec::new();
+    for byte in io::BufReader::new(output).bytes() {
+        let Ok(byte) = byte else { break };
+        if !matches!(byte, b'\r' | b'\n' | 0x08) {
+            line.push(byte);
+            continue;
+        }
+        let text = String::from_utf8_lossy(&line).trim().to_string();
+        line.clear();
+        if text.is_empty() {
+            continue;
+        }
+        if let Some(file) = named_file(&text) {
+            current_file = file.to_string();
+        }
+        match percent_of(&text) {
+            Some(percent) => {
+                let _ = progress.send((percent, current_file.clone()));
+            }
+            None => {
+                if lines.len() == OUTPUT_LINES {
+                    lines.pop_front();
+                }
+                lines.push_back(text);
+            }
+        }
+    }
+    lines
+}
+
+/// The file a progress line is about: ` 42% 7 - dir/file` from 7-Zip, or
+/// `Extracting  dir/file  OK` from UnRAR.
+fn named_file(line: &str) -> Option<&str> {
+    if let Some((_, file)) = line.split_once(" - ") {
+        return Some(file.trim());
+    }
+    let file = line.strip_prefix("Extracting")?.trim_start();
+    if file.starts_with("from ") {
+        return None;
+    }
+    Some(file.strip_suffix("OK").unwrap_or(file).trim_end())
+}
+
+/// The number before the first `%` in `line`, if there is one.
+fn percent_of(line: &str) -> Option<u64> {
+    let (before, _) = line.split_once('%')?;
+    let before = before.trim_end();
+    let number = &before[before.trim_end_matches(|c: char| c.is_ascii_digit()).len()..];
+    number.parse::<u64>().ok().map(|percent| percent.min(100))
+}
+
+/// The volumes of a split archive read back to back as one file.
+struct Volumes {
+    files: Vec<(File, u64)>,
+    len: u64,
+    position: u64,
+}
+
+impl Volumes {
+    fn open(paths: &[PathBuf]) -> Result<Self> {
+        let files = paths
+            .iter()
+            .map(|path| {
+                let file = File::open(path)
+                    .with_context(|| format!("Failed to open {}", path.display()))?;
+                let len = file.metadata()?.len();
+                Ok((file, len))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            len: files.iter().map(|(_, len)| len).sum(),
+            files,
+            position: 0,
+        })
+    }
+}
+
+impl Read for Volumes {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let mut start = 0;
+        for (file, len) in &mut self.files {
+            if self.position < start + *len {
+                let offset = self.position - start;
+                file.seek(SeekFrom::Start(offset))?;
+                let wanted = buffer
+                    .len()
+                    .min((*len - offset).try_into().unwrap_or(usize::MAX));
+                let read = file.read(&mut buffer[..wanted])?;
+                self.position += read as u64;
+                return Ok(read);
+            }
+            start += *len;
+        }
+        Ok(0)
+    }
+}
+
+impl Seek for Volumes {
+    fn seek(&mut self, target: SeekFrom) -> io::Result<u64> {
+        let position = match target {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.len.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seek before the start of the archive",
+            )
+        })?;
+        Ok(self.position)
+    }
+}
@@ -0,0 +1,412 @@
+use crate::error::CommandError;
+use crate::settings::present;
+use crate::tags::{self, TagRules};
+use crate::{
+    check_custom_fields, check_unchanged, checked_rating, checked_release_year, launcher,
+    merge_custom_fields, modify_games, non_empty, sizes, Completion, GameEntry, GamePayload,
+    InstallStatus, Library,
+};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::{AppHandle, State};
+
+/// Changes to a game for `patch_game`. Fields left out keep their value;
+/// optional fields and lists set to `null` are cleared. `update_game` goes through
+/// here too, with every field set from its payload.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GamePatch {
+    /// A blank title becomes "Untitled".
+    pub title: Option<String>,
+    #[serde(default, deserialize_with = "present")]
+    pub sort_title: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub alternate_titles: Option<Option<Vec<String>>>,
+    #[serde(default, deserialize_with = "present")]
+    pub version: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub developer: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub publisher: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub release_year: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub description: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub archive_path: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub install_path: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub executable_path: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub launch_args: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub working_dir: Option<Option<String>>,
+    /// Replaces all variables at once.
+    #[serde(default, deserialize_with = "present")]
+    pub env: Option<Option<HashMap<String, String>>>,
+    #[serde(default, deserialize_with = "present")]
+    pub wrapper_command: Option<Option<String>>,
+    pub run_elevated: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub repacker: Option<Option<String>>,
+    /// Replaces all tags at once, before `add_tags` and `remove_tags` apply.
+    #[serde(default, deserialize_with = "present")]
+    pub tags: Option<Option<Vec<String>>>,
+    #[serde(default)]
+    pub add_tags: Vec<String>,
+    /// Matched regardless of case.
+    #[serde(default)]
+    pub remove_tags: Vec<String>,
+    pub status: Option<InstallStatus>,
+    pub favorite: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub rating: Option<Option<i64>>,
+    #[serde(default, deserialize_with = "present")]
+    pub completion: Option<Option<Completion>>,
+    #[serde(default, deserialize_with = "present")]
+    pub notes: Option<Option<String>>,
+    /// Merged into the existing fields; an empty value removes the field.
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, String>,
+    #[serde(default, deserialize_with = "present")]
+    pub checksum: Option<Option<String>>,
+    #[serde(default, deserialize_with = "present")]
+    pub color: Option<Option<String>>,
+    /// Size to record instead of measuring the archive or install folder,
+    /// which otherwise happens in the background whenever either path is set.
+    /// `null` marks the size unknown and skips measuring; a number, zero
+    /// included, is recorded as that many bytes. Clearing both paths without
+    /// an override clears the size.
+    #[serde(default, deserialize_with = "present")]
+    pub size_override: Option<Option<u64>>,
+}
+
+/// A full payload replaces every field, as `update_game` always has.
+impl From<GamePayload> for GamePatch {
+    fn from(payload: GamePayload) -> Self {
+        Self {
+            title: Some(payload.title),
+            sort_title: Some(payload.sort_title),
+            alternate_titles: Some(Some(payload.alternate_titles)),
+            version: Some(payload.version),
+            developer: Some(payload.developer),
+            publisher: Some(payload.publisher),
+            release_year: Some(payload.release_year),
+            description: Some(payload.description),
+            archive_path: Some(payload.archive_path),
+            install_path: Some(payload.install_path),
+            executable_path: Some(payload.executable_path),
+            launch_args: Some(payload.launch_args),
+            working_dir: Some(payload.working_dir),
+            env: Some(Some(payload.env)),
+            wrapper_command: Some(payload.wrapper_command),
+            run_elevated: Some(payload.run_elevated),
+            repacker: Some(payload.repacker),
+            tags: Some(Some(payload.tags)),
+            add_tags: Vec::new(),
+            remove_tags: Vec::new(),
+            status: Some(payload.status),
+            favorite: Some(payload.favorite),
+            rating: Some(payload.rating),
+            completion: Some(payload.completion),
+            notes: Some(payload.notes),
+            custom_fields: payload.custom_fields,
+            checksum: Some(payload.checksum),
+            color: Some(payload.color),
+            size_override: payload.size_override,
+        }
+    }
+}
+
+impl GamePatch {
+    /// Refuses values `apply` would otherwise have to drop.
+    pub fn check(&self) -> Result<()> {
+        if let Some(Some(env)) = &self.env {
+            launcher::validate_env(env)?;
+        }
+        checked_rating(self.rating.flatten())?;
+        checked_release_year(self.release_year.flatten())?;
+        check_custom_fields(&self.custom_fields)
+    }
+
+    /// Whether the game needs measuring once this patch is applied.
+    pub fn changes_size(&self) -> bool {
+        self.size_override.is_none() && (self.archive_path.is_some() || self.install_path.is_some())
+    }
+
+    /// Writes the fields this patch sets into `entry`. Text is trimmed and
+    /// blank text clears the field. Leaves `updated_at` to the caller.
+    pub fn apply(self, entry: &mut GameEntry, rules: &mut TagRules) {
+        let set = |field: &mut Option<String>, value: Option<Option<String>>| {
+            if let Some(value) = value {
+                *field = value.and_then(non_empty);
+            }
+        };
+
+        if let Some(title) = self.title {
+            entry.title = non_empty(title).unwrap_or_else(|| "Untitled".to_string());
+        }
+        set(&mut entry.sort_title, self.sort_title);
+        if let Some(titles) = self.alternate_titles {
+            entry.alternate_titles = titles
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(non_empty)
+                .collect();
+        }
+        set(&mut entry.version, self.version);
+        set(&mut entry.developer, self.developer);
+        set(&mut entry.publisher, self.publisher);
+        if let Some(year) = self.release_year {
+            entry.release_year = checked_release_year(year).unwrap_or(None);
+        }
+        set(&mut entry.description, self.description);
+        let paths_changed = self.archive_path.is_some() || self.install_path.is_some();
+        set(&mut entry.archive_path, self.archive_path);
+        set(&mut entry.install_path, self.install_path);
+        set(&mut entry.executable_path, self.executable_path);
+        set(&mut entry.launch_args, self.launch_args);
+        set(&mut entry.working_dir, self.working_dir);
+        if let Some(env) = self.env {
+            entry.env = env.unwrap_or_default();
+        }
+        set(&mut entry.wrapper_command, self.wrapper_command);
+        if let Some(run_elevated) = self.run_elevated {
+            entry.run_elevated = run_elevated;
+        }
+        set(&mut entry.repacker, self.repacker);
+
+        if self.tags.is_some() || !self.add_tags.is_empty() || !self.remove_tags.is_empty() {
+            let mut tags = match self.tags {
+                Some(tags) => tags.unwrap_or_default(),
+                None => entry.tags.clone(),
+            };
+            tags.extend(self.add_tags);
+            let removed: HashSet<String> = self
+                .remove_tags
+                .iter()
+                .map(|tag| tags::tag_key(tag.trim()))
+                .collect();
+            entry.tags = rules.normalize(tags);
+            entry
+                .tags
+                .retain(|tag| !removed.contains(&tags::tag_key(tag)));
+        }
+
+        if let Some(status) = self.status {
+            entry.status = status;
+        }
+        if let Some(favorite) = self.favorite {
+            entry.favorite = favorite;
+        }
+        if let Some(rating) = self.rating {
+            entry.rating = checked_rating(rating).unwrap_or(None);
+        }
+        if let Some(completion) = self.completion {
+            entry.completion = completion;
+        }
+        set(&mut entry.notes, self.notes);
+        merge_custom_fields(&mut entry.custom_fields, self.custom_fields);
+        set(&mut entry.checksum, self.checksum);
+        set(&mut entry.color, self.color);
+        if let Some(size) = self.size_override {
+            entry.size_bytes = size;
+        } else if paths_changed && entry.archive_path.is_none() && entry.install_path.is_none() {
+            // Nothing is left to measure, so the old size could only mislead.
+            entry.size_bytes = None;
+        }
+    }
+}
+
+/// Changes only the fields `patch` sets, so a form that is out of date cannot
+/// erase the rest. `expected_updated_at` works as for `update_game`.
+#[tauri::command]
+pub fn patch_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+    patch: GamePatch,
+    expected_updated_at: Option<DateTime<Utc>>,
+) -> Result<GameEntry, CommandError> {
+    patch.check().map_err(CommandError::invalid)?;
+    let changes_size = patch.changes_size();
+    let mut patch = Some(patch);
+    let mut rules = library.tag_rules(&app)?;
+    let updated = library.update(&app, |games| {
+        check_unchanged(games, &id, expected_updated_at)?;
+        let mut updated = None;
+        modify_games(games, std::slice::from_ref(&id), |game| {
+            if let Some(patch) = patch.take() {
+                patch.apply(game, &mut rules);
+                updated = Some(game.clone());
+            }
+        });
+        Ok(updated.ok_or_else(|| CommandError::game_not_found(&id))?)
+    })?;
+    if changes_size {
+        sizes::measure(&app, &updated);
+    }
+    Ok(updated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::TagPolicy;
+    use serde_json::json;
+
+    fn rules() -> TagRules {
+        TagRules::new(TagPolicy::default(), &[])
+    }
+
+    /// A game with both paths set and a measured size of 1000 bytes.
+    fn game() -> GameEntry {
+        let payload = GamePayload {
+            title: "Hollow Depths".into(),
+            archive_path: Some("/repacks/hollow.rar".into()),
+            install_path: Some("/games/hollow".into()),
+            ..GamePayload::default()
+        };
+        let mut game = crate::game_from_payload(payload, None, &mut rules());
+        game.size_bytes = Some(1000);
+        game
+    }
+
+    fn patch(patch: serde_json::Value) -> GamePatch {
+        serde_json::from_value(patch).unwrap()
+    }
+
+    /// `game()` with `patch` applied, and whether it needs measuring.
+    fn patched(patch_json: serde_json::Value) -> (GameEntry, bool) {
+        let patch = patch(patch_json);
+        let changes_size = patch.changes_size();
+        let mut game = game();
+        patch.apply(&mut game, &mut rules());
+        (game, changes_size)
+    }
+
+    #[test]
+    fn removing_both_paths_clears_the_size() {
+        let (game, _) = patched(json!({ "archivePath": null, "installPath": null }));
+        assert_eq!(game.archive_path, None);
+        assert_eq!(game.install_path, None);
+        assert_eq!(game.size_bytes, None);
+    }
+
+    #[test]
+    fn removing_one_path_measures_the_other() {
+        let (game, changes_size) = patched(json!({ "archivePath": null }));
+        assert_eq!(game.install_path.as_deref(), Some("/games/hollow"));
+        // Kept until the remaining folder is measured.
+        assert_eq!(game.size_bytes, Some(1000));
+        assert!(changes_size);
+    }
+
+    #[test]
+    fn swapping_a_path_measures_again() {
+        let (game, changes_size) = patched(json!({ "installPath": "/games/elsewhere" }));
+        assert_eq!(game.install_path.as_deref(), Some("/games/elsewhere"));
+        assert!(changes_size);
+    }
+
+    #[test]
+    fn null_override_marks_the_size_unknown() {
+        let (game, changes_size) =
+            patched(json!({ "installPath": "/games/elsewhere", "sizeOverride": null }));
+        assert_eq!(game.size_bytes, None);
+        assert!(!changes_size);
+    }
+
+    #[test]
+    fn zero_override_is_zero_bytes() {
+        let (game, changes_size) = patched(json!({ "sizeOverride": 0 }));
+        assert_eq!(game.size_bytes, Some(0));
+        assert!(!changes_size);
+    }
+
+    #[test]
+    fn other_changes_keep_the_size() {
+        let (game, changes_size) = patched(json!({ "notes": "Act two" }));
+        assert_eq!(game.size_bytes, Some(1000));
+        assert!(!changes_size);
+    }
+
+    /// A game with a value in every field the tri-state tests cover.
+    fn filled_game() -> GameEntry {
+        let mut game = game();
+        patch(json!({
+            "notes": "Act one",
+            "env": { "DXVK_HUD": "fps" },
+            "rating": 4,
+            "tags": ["rpg", "indie"],
+        }))
+        .apply(&mut game, &mut rules());
+        game
+    }
+
+    fn filled_patched(patch_json: serde_json::Value) -> GameEntry {
+        let mut game = filled_game();
+        patch(patch_json).apply(&mut game, &mut rules());
+        game
+    }
+
+    #[test]
+    fn absent_fields_are_left_alone() {
+        let before = filled_game();
+        let mut after = before.clone();
+        patch(json!({})).apply(&mut after, &mut rules());
+        assert_eq!(after, before);
+
+        let after = filled_patched(json!({ "title": "Renamed" }));
+        assert_eq!(after.notes, before.notes);
+        assert_eq!(after.env, before.env);
+        assert_eq!(after.rating, before.rating);
+        assert_eq!(after.tags, before.tags);
+    }
+
+    #[test]
+    fn null_clears_fields() {
+        let game = filled_patched(json!({
+            "notes": null,
+            "env": null,
+            "rating": null,
+            "tags": null,
+        }));
+        assert_eq!(game.notes, None);
+        assert!(game.env.is_empty());
+        assert_eq!(game.rating, None);
+        assert!(game.tags.is_empty());
+    }
+
+    #[test]
+    fn values_set_fields() {
+        let game = filled_patched(json!({
+            "notes": "  Act two  ",
+            "env": { "WINEDEBUG": "-all" },
+            "rating": 5,
+            "tags": ["strategy"],
+        }));
+        assert_eq!(game.notes.as_deref(), Some("Act two"));
+        assert_eq!(
+            game.env,
+            HashMap::from([("WINEDEBUG".into(), "-all".into())])
+        );
+        assert_eq!(game.rating, Some(5));
+        assert_eq!(game.tags, ["strategy"]);
+    }
+
+    #[test]
+    fn blank_text_clears_the_field() {
+        let game = filled_patched(json!({ "notes": "   " }));
+        assert_eq!(game.notes, None);
+    }
+
+    #[test]
+    fn tags_are_added_and_removed_without_the_full_list() {
+        let game = filled_patched(json!({ "addTags": ["co-op"], "removeTags": ["RPG"] }));
+        assert_eq!(game.tags, ["co-op", "indie"]);
+    }
+}
@@ -0,0 +1,121 @@
+use crate::error::CommandError;
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, State};
+
+const HISTORY_FILE: &str = "download-history.json";
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum HistoryOutcome {
+    Completed,
+    /// Transferred, but rejected by the post-download command.
+    Flagged,
+    Failed,
+    Cancelled,
+}
+
+/// A finished transfer as recorded in `download-history.json`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HistoryEntry {
+    pub id: String,
+    /// Mirror the download was last fetched from.
+    pub url: String,
+    pub file_name: String,
+    pub destination: String,
+    /// Bytes on disk when the download ended.
+    pub size_bytes: u64,
+    /// Time spent transferring, excluding pauses and time waiting in the queue.
+    pub duration_ms: u64,
+    pub average_bytes_per_second: Option<u64>,
+    pub finished_at: DateTime<Utc>,
+    pub outcome: HistoryOutcome,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+/// Managed state guarding `download-history.json`, which only ever grows until
+/// it is cleared.
+#[derive(Debug, Default)]
+pub struct DownloadHistory {
+    lock: Mutex<()>,
+}
+
+impl DownloadHistory {
+    /// Keeps the history from being read or written until the guard is
+    /// dropped, while the data folder moves.
+    pub fn hold(&self) -> MutexGuard<'_, ()> {
+        self.lock.lock().expect("download history poisoned")
+    }
+
+    fn append(&self, app: &AppHandle, entry: HistoryEntry) -> Result<()> {
+        let _guard = self.lock.lock().expect("download history poisoned");
+        let mut entries = read_history(app)?;
+        entries.push(entry);
+        write_history(app, &entries)
+    }
+}
+
+/// Appends `entry` on the blocking pool. Failures are ignored, a lost history
+/// line is not worth failing a download over.
+pub fn record(app: &AppHandle, entry: HistoryEntry) {
+    let app = app.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let _ = app.state::<DownloadHistory>().append(&app, entry);
+    });
+}
+
+/// Recorded downloads, newest first.
+#[tauri::command]
+pub fn get_download_history(
+    app: AppHandle,
+    history: State<'_, DownloadHistory>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+) -> Result<Vec<HistoryEntry>, CommandError> {
+    let _guard = history.lock.lock().expect("download history poisoned");
+    let entries = read_history(&app)?;
+    Ok(entries
+        .into_iter()
+        .rev()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect())
+}
+
+#[tauri::command]
+pub fn clear_download_history(
+    app: AppHandle,
+    history: State<'_, DownloadHistory>,
+) -> Result<(), CommandError> {
+    let _guard = history.lock.lock().expect("download history poisoned");
+    write_history(&app, &[]).map_err(CommandError::from)
+}
+
+fn history_path(app: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::resolve_data_dir(app)?.join(HISTORY_FILE))
+}
+
+fn read_history(app: &AppHandle) -> Result<Vec<HistoryEntry>> {
+    let path = history_path(app)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+
+    let content = fs::read_to_string(path)?;
+    if content.trim().is_empty() {
+        return Ok(Vec::new());
+    }
+
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_history(app: &AppHandle, entries: &[HistoryEntry]) -> Result<()> {
+    let payload = serde_json::to_string_pretty(entries)?;
+    crate::write_atomic(&history_path(app)?, payload.as_bytes())
+}
@@ -0,0 +1,125 @@
+use anyhow::{anyhow, Result};
+use std::path::Path;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+/// Placeholder replaced by the downloaded file's path.
+const FILE_PLACEHOLDER: &str = "{file}";
+/// Longest a post-download command may run before the file is flagged.
+const HOOK_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
+/// Result of running the post-download command on a finished file.
+#[derive(Debug)]
+pub struct HookReport {
+    pub passed: bool,
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr, or why the command could not run.
+    pub output: String,
+}
+
+/// Splits a command template such as `clamscan --no-summary {file}` into
+/// arguments. Single and double quotes group words; backslashes are kept as
+/// is so Windows paths need no escaping.
+pub fn parse_template(template: &str) -> Result<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+
+    for c in template.chars() {
+        match quote {
+            Some(open) if c == open => quote = None,
+            Some(_) => current.push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                in_word = true;
+            }
+            None if c.is_whitespace() => {
+                if in_word {
+                    args.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return Err(anyhow!("Unterminated quote in command: {template}"));
+    }
+    if in_word {
+        args.push(current);
+    }
+    if args.is_empty() {
+        return Err(anyhow!("Command cannot be empty"));
+    }
+    Ok(args)
+}
+
+/// Runs `template` against `file` without a shell, so the path is passed as a
+/// single argument whatever characters it contains. The path is appended when
+/// the template has no `{file}` placeholder. Anything other than a zero exit
+/// within `HOOK_TIMEOUT` fails the check.
+pub async fn run(template: &str, file: &Path) -> HookReport {
+    let args = match parse_template(template) {
+        Ok(args) => args,
+        Err(error) => return failed(error.to_string()),
+    };
+    let path = file.to_string_lossy();
+    let mut args: Vec<String> = if args.iter().any(|arg| arg.contains(FILE_PLACEHOLDER)) {
+        args.iter()
+            .map(|arg| arg.replace(FILE_PLACEHOLDER, &path))
+            .collect()
+    } else {
+        args.into_iter().chain([path.to_string()]).collect()
+    };
+    let program = args.remove(0);
+
+    let child = Command::new(&program)
+        .args(&args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn();
+    let child = match child {
+        Ok(child) => child,
+        Err(error) => return failed(format!("Failed to run {program}: {error}")),
+    };
+
+    let output = match tokio::time::timeout(HOOK_TIMEOUT, child.wait_with_output()).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(error)) => return failed(format!("Failed to run {program}: {error}")),
+        Err(_) => {
+            return failed(format!(
+                "{program} did not finish within {} seconds",
+                HOOK_TIMEOUT.as_secs()
+            ))
+        }
+    };
+
+    let mut text = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !stderr.trim().is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        text.push_str(stderr.trim());
+    }
+    HookReport {
+        passed: output.status.success(),
+        exit_code: output.status.code(),
+        output: text,
+    }
+}
+
+fn failed(output: String) -> HookReport {
+    HookReport {
+        passed: false,
+        exit_code: None,
+        output,
+    }
+}
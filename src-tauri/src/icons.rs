@@ -0,0 +1,245 @@
+use crate::artwork::{self, ArtworkKind};
+use crate::error::{CommandError, ErrorCode};
+use crate::{GameEntry, Library};
+use anyhow::Context;
+use image::codecs::ico::IcoDecoder;
+use image::{DynamicImage, ImageOutputFormat};
+use serde::Serialize;
+use std::fs;
+use std::io::Cursor;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+/// Image files looked for next to an executable that has no icon of its own,
+/// besides one named after the executable.
+const FALLBACK_ICON_NAMES: &[&str] = &["icon.png", "icon.ico"];
+
+/// Why no icon could be extracted, in a shape the frontend can branch on.
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum IconError {
+    #[error("The game has no executable set")]
+    NoExecutable,
+    #[error("The executable {path} does not exist")]
+    MissingExecutable { path: String },
+    #[error("{path} contains no icon")]
+    NoIcon { path: String },
+    #[error("{message}")]
+    Failed { message: String },
+}
+
+impl From<anyhow::Error> for IconError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Failed {
+            message: format!("{error:#}"),
+        }
+    }
+}
+
+/// Keeps the variant in `details`, as `extract_all_icons` reports it.
+impl From<IconError> for CommandError {
+    fn from(error: IconError) -> Self {
+        let code = match error {
+            IconError::NoExecutable
+            | IconError::MissingExecutable { .. }
+            | IconError::NoIcon { .. } => ErrorCode::NotFound,
+            IconError::Failed { .. } => ErrorCode::Internal,
+        };
+        let details = serde_json::to_value(&error).ok();
+        Self {
+            code,
+            message: error.to_string(),
+            details,
+        }
+    }
+}
+
+/// Sent after each game `extract_all_icons` has handled.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IconProgress {
+    game_id: String,
+    done: usize,
+    total: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IconFailure {
+    game_id: String,
+    title: String,
+    error: IconError,
+}
+
+/// Result of `extract_all_icons`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IconExtraction {
+    /// Games that got an icon.
+    extracted: Vec<String>,
+    failed: Vec<IconFailure>,
+}
+
+/// Takes the largest icon out of a game's executable and stores it as a PNG
+/// with the game's artwork, replacing any earlier icon. Windows executables
+/// carry their icons as resources; for anything else an image next to the
+/// executable is used, as named by a `.desktop` file in the same folder or
+/// called like the executable or `icon`. Returns the stored path.
+#[tauri::command]
+pub async fn extract_icon(
+    app: AppHandle,
+    library: State<'_, Library>,
+    game_id: String,
+) -> Result<String, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == game_id)
+        .ok_or_else(|| CommandError::game_not_found(&game_id))?;
+    Ok(extract(&app, &library, &game).await?)
+}
+
+/// `extract_icon` for every game with an executable but no icon yet, one
+/// after another. Sends `icon-extraction-progress` after each game. Failures
+/// are collected rather than stopping the run.
+#[tauri::command]
+pub async fn extract_all_icons(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<IconExtraction, CommandError> {
+    let games: Vec<GameEntry> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .filter(|game| game.executable_path.is_some() && game.icon_path.is_none())
+        .collect();
+
+    let total = games.len();
+    let mut extracted = Vec::new();
+    let mut failed = Vec::new();
+    for (index, game) in games.into_iter().enumerate() {
+        match extract(&app, &library, &game).await {
+            Ok(_) => extracted.push(game.id.clone()),
+            Err(error) => failed.push(IconFailure {
+                game_id: game.id.clone(),
+                title: game.title.clone(),
+                error,
+            }),
+        }
+        let _ = app.emit_all(
+            "icon-extraction-progress",
+            IconProgress {
+                game_id: game.id,
+                done: index + 1,
+                total,
+            },
+        );
+    }
+    Ok(IconExtraction { extracted, failed })
+}
+
+async fn extract(
+    app: &AppHandle,
+    library: &Library,
+    game: &GameEntry,
+) -> Result<String, IconError> {
+    let executable = PathBuf::from(
+        game.executable_path
+            .as_deref()
+            .ok_or(IconError::NoExecutable)?,
+    );
+    if !executable.is_file() {
+        return Err(IconError::MissingExecutable {
+            path: executable.to_string_lossy().into_owned(),
+        });
+    }
+
+    let png = tauri::async_runtime::spawn_blocking(move || read_icon(&executable))
+        .await
+        .map_err(|error| IconError::Failed {
+            message: error.to_string(),
+        })??;
+    let game = artwork::store_artwork(app, library, &game.id, ArtworkKind::Icon, png).await?;
+    Ok(game.icon_path.unwrap_or_default())
+}
+
+/// The executable's icon encoded as PNG.
+fn read_icon(executable: &Path) -> Result<Vec<u8>, IconError> {
+    let no_icon = || IconError::NoIcon {
+        path: executable.to_string_lossy().into_owned(),
+    };
+    let map = pelite::FileMap::open(executable)
+        .with_context(|| format!("Failed to read {}", executable.display()))?;
+    let image = match pelite::PeFile::from_bytes(&map) {
+        Ok(pe) => resource_icon(pe).ok_or_else(no_icon)?,
+        Err(_) => fallback_icon(executable).ok_or_else(no_icon)?,
+    };
+
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .context("Failed to encode the icon")?;
+    Ok(png.into_inner())
+}
+
+/// The largest image among the executable's icon groups. Groups that cannot
+/// be decoded are skipped.
+fn resource_icon(pe: pelite::PeFile<'_>) -> Option<DynamicImage> {
+    let resources = pe.resources().ok()?;
+    resources
+        .icons()
+        .filter_map(Result::ok)
+        .filter_map(|(_, group)| {
+            let mut ico = Vec::new();
+            group.write(&mut ico).ok()?;
+            // The decoder picks the largest image in the group.
+            let decoder = IcoDecoder::new(Cursor::new(ico)).ok()?;
+            DynamicImage::from_decoder(decoder).ok()
+        })
+        .reduce(|largest, image| {
+            let area = |image: &DynamicImage| image.width() * image.height();
+            if area(&image) > area(&largest) {
+                image
+            } else {
+                largest
+            }
+        })
+}
+
+/// An image file shipped next to an executable that is not a Windows one.
+fn fallback_icon(executable: &Path) -> Option<DynamicImage> {
+    let folder = executable.parent()?;
+    let stem = executable.file_stem()?.to_string_lossy().into_owned();
+    desktop_entry_icon(folder)
+        .into_iter()
+        .chain([
+            folder.join(format!("{stem}.png")),
+            folder.join(format!("{stem}.ico")),
+        ])
+        .chain(FALLBACK_ICON_NAMES.iter().map(|name| folder.join(name)))
+        .filter(|path| path.is_file())
+        .find_map(|path| image::open(path).ok())
+}
+
+/// The `Icon=` of the first `.desktop` file in `folder`, when it names an
+/// image file rather than an icon theme entry.
+fn desktop_entry_icon(folder: &Path) -> Option<PathBuf> {
+    fs::read_dir(folder)
+        .ok()?
+        .filter_map(Result::ok)
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.extension()
+                .is_some_and(|extension| extension == "desktop")
+        })
+        .filter_map(|path| fs::read_to_string(path).ok())
+        .find_map(|entry| {
+            let icon = entry
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("Icon="))?
+                .trim();
+            let icon = Path::new(icon);
+            (icon.extension().is_some() || icon.is_absolute()).then(|| folder.join(icon))
+        })
+}
@@ -0,0 +1,686 @@
+use crate::error::CommandError;
+use crate::settings::{SettingsPatch, SettingsStore};
+use crate::{InstallStatus, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessesToUpdate, System};
+use tauri::{AppHandle, Manager, State};
+
+/// How long to look for processes from the install folder once the launched
+/// executable exits, for launchers that start the game and quit.
+const FOLLOW_GRACE: Duration = Duration::from_secs(10);
+const FOLLOW_POLL_INTERVAL: Duration = Duration::from_secs(2);
+/// Stands for the game's own command line in a wrapper command.
+const COMMAND_PLACEHOLDER: &str = "%command%";
+
+/// Managed state for games started by the launcher.
+#[derive(Debug, Default)]
+pub struct Launcher {
+    /// Processes started for each game that have not exited yet.
+    running: Mutex<HashMap<String, Vec<RunningProcess>>>,
+}
+
+impl Launcher {
+    pub fn is_running(&self, game_id: &str) -> bool {
+        self.running
+            .lock()
+            .expect("launcher poisoned")
+            .contains_key(game_id)
+    }
+
+    /// Forgets `pid`. Returns whether it was the game's last process.
+    fn unregister(&self, game_id: &str, pid: u32) -> bool {
+        let mut running = self.running.lock().expect("launcher poisoned");
+        let Some(processes) = running.get_mut(game_id) else {
+            return false;
+        };
+        processes.retain(|process| process.pid != pid);
+        if processes.is_empty() {
+            running.remove(game_id);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// One run of a game, from launch until its last process exited.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlaySession {
+    pub game_id: String,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub duration_seconds: u64,
+}
+
+/// A started game. Games run as administrator on Windows are not children
+/// of the launcher, so they are tracked through a process handle instead.
+#[derive(Debug)]
+pub enum GameProcess {
+    Child(Child),
+    #[cfg(windows)]
+    Elevated(crate::elevation::windows::ElevatedProcess),
+}
+
+impl GameProcess {
+    fn id(&self) -> u32 {
+        match self {
+            Self::Child(child) => child.id(),
+            #[cfg(windows)]
+            Self::Elevated(process) => process.id(),
+        }
+    }
+
+    fn wait(&mut self) {
+        match self {
+            Self::Child(child) => {
+                let _ = child.wait();
+            }
+            #[cfg(windows)]
+            Self::Elevated(process) => process.wait(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct RunningProcess {
+    pid: u32,
+    /// Checked before signalling `pid`, which the OS may have handed to an
+    /// unrelated program once the game exited. Either the executable or,
+    /// for launches through a wrapper, the start time has to match.
+    executable: PathBuf,
+    start_time: Option<u64>,
+    /// When the launch happened, in seconds since the epoch like
+    /// `start_time`. Only processes started since are taken for the game.
+    launched_at: u64,
+    install_dir: Option<PathBuf>,
+    /// Whether another entry's install folder overlaps `install_dir`, so its
+    /// processes cannot be told apart from the game's.
+    shared_install: bool,
+    /// Set by `stop_game`, so the session ends without waiting for
+    /// processes from the install folder to show up.
+    stop_requested: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameLaunchedEvent {
+    game_id: String,
+    pid: u32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameStoppedEvent {
+    game_id: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameSessionEndedEvent {
+    #[serde(flatten)]
+    session: PlaySession,
+    total_playtime_seconds: u64,
+}
+
+/// Starts an installed game's executable with its launch arguments and
+/// returns the process id. It runs in the game's working folder, falling
+/// back to the executable's own folder and then the install folder. `force`
+/// launches games whose status is not installed, and `allow_running` starts
+/// another copy of a game that is already running. The launch is counted and
+/// the session recorded once the game exits. Games imported from Steam are
+/// started through Steam instead; their playtime is not tracked and 0 is
+/// returned for the process id.
+#[tauri::command]
+pub fn launch_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    launcher: State<'_, Launcher>,
+    id: String,
+    force: Option<bool>,
+    allow_running: Option<bool>,
+) -> Result<u32, CommandError> {
+    launch(
+        &app,
+        &library,
+        &launcher,
+        &id,
+        force.unwrap_or(false),
+        allow_running.unwrap_or(false),
+    )
+    .map_err(CommandError::from)
+}
+
+/// Asks a running game to close: SIGTERM, or WM_CLOSE on Windows. With
+/// `force` the game and every process it started are killed instead.
+/// Returns whether any process was signalled; `game-stopped` follows once
+/// the game has exited and its session is recorded.
+#[tauri::command]
+pub fn stop_game(launcher: State<'_, Launcher>, id: String, force: bool) -> bool {
+    let processes = launcher
+        .running
+        .lock()
+        .expect("launcher poisoned")
+        .get(&id)
+        .cloned()
+        .unwrap_or_default();
+    if processes.is_empty() {
+        return false;
+    }
+
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::All, true);
+    let mut stopped = false;
+    for process in &processes {
+        process.stop_requested.store(true, Ordering::SeqCst);
+        let mut targets = stop_targets(&system, process);
+        if force {
+            targets = with_descendants(&system, targets);
+        }
+        for pid in targets {
+            stopped |= terminate(&system, pid, force);
+        }
+    }
+    stopped
+}
+
+/// Whether a process started for the game is still running.
+#[tauri::command]
+pub fn is_game_running(launcher: State<'_, Launcher>, id: String) -> bool {
+    launcher.is_running(&id)
+}
+
+/// Play sessions of a game, oldest first.
+#[tauri::command]
+pub fn get_play_sessions(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+) -> Result<Vec<PlaySession>, CommandError> {
+    library
+        .sessions(&app, &id)
+        .context("Failed to load play sessions")
+        .map_err(CommandError::from)
+}
+
+/// Sets whether playtime keeps counting while processes from a game's
+/// install folder outlive the executable that was launched. On by default.
+#[tauri::command]
+pub fn set_follow_game_processes(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    let patch = SettingsPatch {
+        follow_game_processes: Some(enabled),
+        ..SettingsPatch::default()
+    };
+    settings.update(&app, patch).map(drop)
+}
+
+fn launch(
+    app: &AppHandle,
+    library: &Library,
+    launcher: &Launcher,
+    id: &str,
+    force: bool,
+    allow_running: bool,
+) -> Result<u32> {
+    let games = library.games(app)?;
+    let game = games
+        .iter()
+        .find(|game| game.id == id)
+        .cloned()
+        .ok_or_else(|| CommandError::game_not_found(id))?;
+    if game.status != InstallStatus::Installed && !force {
+        return Err(anyhow!("{} is not installed", game.title));
+    }
+    if let Some(app_id) = crate::steam::app_id(&game) {
+        crate::steam::run(app, app_id)?;
+        let _ = crate::stamp_launch(app, library, id);
+        return Ok(0);
+    }
+    let executable = game
+        .executable_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| CommandError::not_found(format!("{} has no executable set", game.title)))?;
+    check_executable(&executable)?;
+    let args = match game.launch_args.as_deref() {
+        Some(args) => crate::hook::parse_template(args)
+            .with_context(|| format!("Invalid launch arguments for {}", game.title))?,
+        None => Vec::new(),
+    };
+    let working_dir = game
+        .working_dir
+        .as_deref()
+        .map(PathBuf::from)
+        .or_else(|| executable.parent().map(Path::to_path_buf))
+        .or_else(|| game.install_path.as_deref().map(PathBuf::from));
+
+    if let Some(folder) = working_dir.as_deref().filter(|folder| !folder.is_dir()) {
+        return Err(CommandError::not_found(format!(
+            "Working folder not found: {}",
+            folder.display()
+        ))
+        .into());
+    }
+    let mut argv: Vec<OsString> = vec![executable.clone().into()];
+    argv.extend(args.into_iter().map(OsString::from));
+    if let Some(wrapper) = game.wrapper_command.as_deref() {
+        argv = wrap_command(wrapper, argv)
+            .with_context(|| format!("Invalid wrapper command for {}", game.title))?;
+    }
+
+    // Held until the process is registered so two launches cannot both pass the check.
+    let mut running = launcher.running.lock().expect("launcher poisoned");
+    if running.contains_key(id) && !allow_running {
+        return Err(CommandError::conflict(format!("{} is already running", game.title)).into());
+    }
+    let process = if game.run_elevated {
+        crate::elevation::spawn(&argv, working_dir.as_deref(), &game.env)?
+    } else {
+        spawn(&argv, working_dir.as_deref(), &game.env)?
+    };
+    let pid = process.id();
+    let started_at = Utc::now();
+    let stop_requested = Arc::new(AtomicBool::new(false));
+    let install_dir = game.install_path.map(PathBuf::from);
+    let shared_install = install_dir.as_deref().is_some_and(|dir| {
+        games
+            .iter()
+            .filter(|other| other.id != id)
+            .filter_map(|other| other.install_path.as_deref().map(Path::new))
+            .any(|other| other.starts_with(dir) || dir.starts_with(other))
+    });
+    running
+        .entry(id.to_string())
+        .or_default()
+        .push(RunningProcess {
+            pid,
+            executable: executable.clone(),
+            start_time: process_start_time(pid),
+            launched_at: started_at.timestamp().try_into().unwrap_or(0),
+            install_dir: install_dir.clone(),
+            shared_install,
+            stop_requested: stop_requested.clone(),
+        });
+    drop(running);
+
+    let event = GameLaunchedEvent {
+        game_id: id.to_string(),
+        pid,
+    };
+    let _ = app.emit_all("game-launched", event.clone());
+    let _ = app.emit_all("game-started", event);
+    // The game is running either way; a failed stamp only loses the counter.
+    let _ = crate::stamp_launch(app, library, id);
+
+    let app = app.clone();
+    let id = id.to_string();
+    tauri::async_runtime::spawn_blocking(move || {
+        supervise(
+            &app,
+            id,
+            process,
+            started_at,
+            install_dir.as_deref(),
+            &stop_requested,
+        )
+    });
+    Ok(pid)
+}
+
+fn spawn(
+    argv: &[OsString],
+    working_dir: Option<&Path>,
+    env: &HashMap<String, String>,
+) -> Result<GameProcess> {
+    let (program, args) = argv
+        .split_first()
+        .ok_or_else(|| anyhow!("Nothing to start"))?;
+    let mut command = Command::new(program);
+    command.args(args);
+    for (name, value) in env {
+        if value.is_empty() {
+            command.env_remove(name);
+        } else {
+            command.env(name, value);
+        }
+    }
+    if let Some(folder) = working_dir {
+        command.current_dir(folder);
+    }
+    let child = command
+        .spawn()
+        .with_context(|| format!("Failed to start {}", Path::new(program).display()))?;
+    Ok(GameProcess::Child(child))
+}
+
+/// Puts `command` where the wrapper says `%command%`, or after the
+/// wrapper's own words when it does not say.
+fn wrap_command(wrapper: &str, command: Vec<OsString>) -> Result<Vec<OsString>> {
+    let tokens = crate::hook::parse_template(wrapper)?;
+    if !tokens.iter().any(|token| token == COMMAND_PLACEHOLDER) {
+        return Ok(tokens
+            .into_iter()
+            .map(OsString::from)
+            .chain(command)
+            .collect());
+    }
+    let mut wrapped = Vec::new();
+    for token in tokens {
+        if token == COMMAND_PLACEHOLDER {
+            wrapped.extend(command.iter().cloned());
+        } else {
+            wrapped.push(token.into());
+        }
+    }
+    Ok(wrapped)
+}
+
+fn process_start_time(pid: u32) -> Option<u64> {
+    let pid = Pid::from_u32(pid);
+    let mut system = System::new();
+    system.refresh_processes(ProcessesToUpdate::Some(&[pid]), true);
+    system.process(pid).map(|process| process.start_time())
+}
+
+/// Checks that every variable name can be set: not empty, without `=` and
+/// without NUL. Values only need to be free of NUL.
+pub fn validate_env(env: &HashMap<String, String>) -> Result<()> {
+    for (name, value) in env {
+        if name.is_empty() {
+            return Err(
+                CommandError::invalid_input("Environment variable names cannot be empty").into(),
+            );
+        }
+        if name.contains(['=', '\0']) {
+            return Err(CommandError::invalid_input(format!(
+                "Invalid environment variable name: {name:?}"
+            ))
+            .into());
+        }
+        if value.contains('\0') {
+            return Err(anyhow!(
+                "Environment variable {name} contains a NUL character"
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Fails with a readable message when `path` is missing or is not a program.
+fn check_executable(path: &Path) -> Result<()> {
+    let metadata = fs::metadata(path).map_err(|_| {
+        CommandError::not_found(format!("Executable not found: {}", path.display()))
+    })?;
+    if !metadata.is_file() {
+        return Err(CommandError::invalid_input(format!(
+            "Executable is not a file: {}",
+            path.display()
+        ))
+        .into());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        if metadata.permissions().mode() & 0o111 == 0 {
+            return Err(anyhow!("{} is not executable", path.display()));
+        }
+    }
+    #[cfg(windows)]
+    {
+        let runnable = path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| {
+                ["exe", "bat", "cmd", "com"]
+                    .iter()
+                    .any(|known| extension.eq_ignore_ascii_case(known))
+            });
+        if !runnable {
+            return Err(CommandError::invalid_input(format!(
+                "{} is not a program",
+                path.display()
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Waits for a launched game to exit and records the session.
+fn supervise(
+    app: &AppHandle,
+    game_id: String,
+    mut process: GameProcess,
+    started_at: DateTime<Utc>,
+    install_dir: Option<&Path>,
+    stop_requested: &AtomicBool,
+) {
+    process.wait();
+    let mut ended_at = Utc::now();
+    let launcher = app.state::<Launcher>();
+    let follow = app.state::<SettingsStore>().get().follow_game_processes
+        && !stop_requested.load(Ordering::SeqCst);
+    if let (true, Some(dir)) = (follow, install_dir) {
+        if let Some(last_seen) = follow_install_processes(dir) {
+            ended_at = last_seen;
+        }
+    }
+    if launcher.unregister(&game_id, process.id()) {
+        let _ = app.emit_all(
+            "game-stopped",
+            GameStoppedEvent {
+                game_id: game_id.clone(),
+            },
+        );
+    }
+
+    let session = PlaySession {
+        game_id,
+        started_at,
+        ended_at,
+        duration_seconds: (ended_at - started_at).num_seconds().max(0) as u64,
+    };
+    let Ok(Some(game)) = app.state::<Library>().record_session(app, &session) else {
+        return;
+    };
+    let _ = app.emit_all(
+        "game-session-ended",
+        GameSessionEndedEvent {
+            session,
+            total_playtime_seconds: game.total_playtime_seconds,
+        },
+    );
+    crate::emit_library_updated(app, vec![game.id]);
+}
+
+/// The processes `stop_game` signals for `running`: the launched process if
+/// it is still the game, or once it has handed off, the processes that took
+/// over from it, see [`took_over`].
+fn stop_targets(system: &System, running: &RunningProcess) -> Vec<Pid> {
+    match system.process(Pid::from_u32(running.pid)) {
+        Some(process)
+            if process
+                .exe()
+                .is_some_and(|exe| is_same_file(exe, &running.executable))
+                || running.start_time == Some(process.start_time()) =>
+        {
+            vec![process.pid()]
+        }
+        Some(_) => Vec::new(),
+        None => system
+            .processes()
+            .values()
+            .filter(|process| {
+                took_over(
+                    running,
+                    process.exe(),
+                    process.parent(),
+                    process.start_time(),
+                )
+            })
+            .map(|process| process.pid())
+            .collect(),
+    }
+}
+
+/// Whether a process, with its executable, parent and start time, took over
+/// from the launched one once it exited: it runs from the install folder,
+/// and was started by the launched process or since the launch. Nothing is
+/// taken when another entry shares the install folder.
+fn took_over(
+    running: &RunningProcess,
+    exe: Option<&Path>,
+    parent: Option<Pid>,
+    start_time: u64,
+) -> bool {
+    let Some(dir) = running.install_dir.as_deref() else {
+        return false;
+    };
+    !running.shared_install
+        && exe.is_some_and(|exe| exe.starts_with(dir))
+        && (parent == Some(Pid::from_u32(running.pid)) || start_time >= running.launched_at)
+}
+
+/// `roots` followed by every process they started, directly or not.
+fn with_descendants(system: &System, mut roots: Vec<Pid>) -> Vec<Pid> {
+    loop {
+        let children: Vec<Pid> = system
+            .processes()
+            .values()
+            .filter(|process| !roots.contains(&process.pid()))
+            .filter(|process| {
+                process
+                    .parent()
+                    .is_some_and(|parent| roots.contains(&parent))
+            })
+            .map(|process| process.pid())
+            .collect();
+        if children.is_empty() {
+            return roots;
+        }
+        roots.extend(children);
+    }
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+#[cfg(unix)]
+fn terminate(system: &System, pid: Pid, force: bool) -> bool {
+    let signal = if force {
+        sysinfo::Signal::Kill
+    } else {
+        sysinfo::Signal::Term
+    };
+    system
+        .process(pid)
+        .and_then(|process| process.kill_with(signal))
+        .unwrap_or(false)
+}
+
+/// `taskkill` without `/F` posts WM_CLOSE to the process's windows, which
+/// is what closing them by hand does.
+#[cfg(windows)]
+fn terminate(_system: &System, pid: Pid, force: bool) -> bool {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+
+    let mut command = Command::new("taskkill");
+    command
+        .args(["/PID", &pid.to_string()])
+        .creation_flags(CREATE_NO_WINDOW);
+    if force {
+        command.arg("/F");
+    }
+    command.status().is_ok_and(|status| status.success())
+}
+
+/// Blocks while any process whose executable lies in `dir` is running,
+/// giving up if none shows up within `FOLLOW_GRACE`. Returns when the last
+/// one was seen, or `None` if there never was one.
+fn follow_install_processes(dir: &Path) -> Option<DateTime<Utc>> {
+    let deadline = Instant::now() + FOLLOW_GRACE;
+    let mut system = System::new();
+    let mut last_seen = None;
+    loop {
+        system.refresh_processes(ProcessesToUpdate::All, true);
+        let running = system
+            .processes()
+            .values()
+            .any(|process| process.exe().is_some_and(|exe| exe.starts_with(dir)));
+        if running {
+            last_seen = Some(Utc::now());
+        } else if last_seen.is_some() || Instant::now() >= deadline {
+            return last_seen;
+        }
+        thread::sleep(FOLLOW_POLL_INTERVAL);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn running(shared_install: bool) -> RunningProcess {
+        RunningProcess {
+            pid: 100,
+            executable: PathBuf::from("/games/starfall/launcher"),
+            start_time: Some(1_000),
+            launched_at: 1_000,
+            install_dir: Some(PathBuf::from("/games/starfall")),
+            shared_install,
+            stop_requested: Arc::default(),
+        }
+    }
+
+    const GAME: &str = "/games/starfall/bin/starfall";
+
+    #[test]
+    fn takes_processes_the_launch_started() {
+        let running = running(false);
+        let game = Some(Path::new(GAME));
+        assert!(took_over(&running, game, Some(Pid::from_u32(100)), 900));
+        assert!(took_over(&running, game, Some(Pid::from_u32(1)), 1_005));
+    }
+
+    #[test]
+    fn leaves_processes_from_before_the_launch() {
+        let running = running(false);
+        let game = Some(Path::new(GAME));
+        assert!(!took_over(&running, game, Some(Pid::from_u32(1)), 900));
+    }
+
+    #[test]
+    fn leaves_processes_from_elsewhere() {
+        let running = running(false);
+        let other = Some(Path::new("/usr/bin/editor"));
+        assert!(!took_over(&running, other, Some(Pid::from_u32(100)), 1_005));
+        assert!(!took_over(&running, None, Some(Pid::from_u32(100)), 1_005));
+    }
+
+    #[test]
+    fn takes_nothing_from_a_shared_install_folder() {
+        let running = running(true);
+        let game = Some(Path::new(GAME));
+        assert!(!took_over(&running, game, Some(Pid::from_u32(100)), 1_005));
+    }
+}
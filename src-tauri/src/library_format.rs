@@ -0,0 +1,307 @@
+use crate::GameEntry;
+use anyhow::{anyhow, Result};
+use serde::de::{DeserializeSeed, Deserializer, IgnoredAny, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use std::fmt;
+
+/// Version of the JSON library format written by this build.
+pub const LIBRARY_FORMAT_VERSION: u64 = 2;
+
+/// Steps upgrading a document by one version; the first turns version 1 into
+/// version 2. A format change adds a step here and bumps `LIBRARY_FORMAT_VERSION`.
+const UPGRADES: &[fn(Value) -> Result<Value>] = &[wrap_games_array];
+
+/// The library as stored in `library.json` and its backups.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LibraryDocument {
+    pub version: u64,
+    pub games: Vec<GameEntry>,
+    /// Top-level fields this build does not know, written by a newer one.
+    /// They are carried through unchanged so a downgrade does not lose them.
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl LibraryDocument {
+    pub fn new(games: Vec<GameEntry>) -> Self {
+        Self {
+            version: LIBRARY_FORMAT_VERSION,
+            games,
+            extra: Map::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("The library was written by a newer version of the launcher (format {0})")]
+pub struct NewerFormatError(pub u64);
+
+/// Reads a library document of any known version, upgrading it to the current one.
+pub fn parse(content: &str) -> Result<LibraryDocument> {
+    let value = upgrade(serde_json::from_str(content)?)?;
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Reads the games of a document that does not fully parse, skipping the
+/// ones that are broken. A document cut short or garbled part way keeps the
+/// games before the damage. Returns them with the number skipped, or `None`
+/// when not even the start of the list of games can be found.
+pub fn salvage(content: &str) -> Option<(Vec<GameEntry>, usize)> {
+    let mut salvaged = Salvaged::default();
+    // The error only marks where the damage starts; what came before is kept.
+    let _ = serde_json::Deserializer::from_str(content).deserialize_any(&mut salvaged);
+    if !salvaged.found {
+        return None;
+    }
+    let document = match salvaged.version {
+        None => Value::Array(salvaged.games),
+        Some(version) => json!({ "version": version, "games": salvaged.games }),
+    };
+    let Value::Array(values) = upgrade(document).ok()?.get_mut("games")?.take() else {
+        return None;
+    };
+    let total = values.len();
+    let games: Vec<GameEntry> = values
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    let skipped = total - games.len();
+    Some((games, skipped))
+}
+
+/// What [`salvage`] could read of a document: its format version, `None`
+/// for the bare list of version 1, and the games up to the first error.
+#[derive(Debug, Default)]
+struct Salvaged {
+    version: Option<u64>,
+    games: Vec<Value>,
+    found: bool,
+}
+
+impl<'de> Visitor<'de> for &mut Salvaged {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a library document")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, seq: A) -> Result<(), A::Error> {
+        self.found = true;
+        Games(&mut self.games).visit_seq(seq)
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<(), A::Error> {
+        // Documents without a readable version are taken to be current.
+        self.version = Some(LIBRARY_FORMAT_VERSION);
+        while let Some(key) = map.next_key::<String>()? {
+            match key.as_str() {
+                "version" => {
+                    if let Some(version) = map.next_value::<Value>()?.as_u64() {
+                        self.version = Some(version);
+                    }
+                }
+                "games" => {
+                    self.found = true;
+                    map.next_value_seed(Games(&mut self.games))?;
+                }
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a list into the vector one element at a time, so the elements
+/// before an error stay in it.
+struct Games<'a>(&'a mut Vec<Value>);
+
+impl<'de> DeserializeSeed<'de> for Games<'_> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for Games<'_> {
+    type Value = ();
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("a list of games")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<(), A::Error> {
+        while let Some(game) = seq.next_element()? {
+            self.0.push(game);
+        }
+        Ok(())
+    }
+}
+
+fn upgrade(mut value: Value) -> Result<Value> {
+    let mut version = match &value {
+        // Version 1 was the bare list of games.
+        Value::Array(_) => 1,
+        Value::Object(fields) => fields
+            .get("version")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| anyhow!("Library has no format version"))?,
+        _ => return Err(anyhow!("Library is neither a list nor an object")),
+    };
+    if version == 0 {
+        return Err(anyhow!("Library has an invalid format version"));
+    }
+    if version > LIBRARY_FORMAT_VERSION {
+        return Err(NewerFormatError(version).into());
+    }
+    while version < LIBRARY_FORMAT_VERSION {
+        value = UPGRADES[(version - 1) as usize](value)?;
+        version += 1;
+    }
+    Ok(value)
+}
+
+fn wrap_games_array(games: Value) -> Result<Value> {
+    Ok(json!({ "version": 2, "games": games }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Completion, InstallStatus};
+
+    const VERSION_1: &str = include_str!("../tests/fixtures/library-v1.json");
+    const VERSION_2: &str = include_str!("../tests/fixtures/library-v2.json");
+
+    fn game_json(id: &str) -> String {
+        format!(
+            r#"{{"id":"{id}","title":"Game {id}","status":"installed","addedAt":"2024-01-02T03:04:05Z","updatedAt":"2024-01-02T03:04:05Z"}}"#
+        )
+    }
+
+    #[test]
+    fn salvages_the_games_before_a_truncation() {
+        let content = format!(
+            r#"{{"version":2,"games":[{},{},{}"#,
+            game_json("a"),
+            game_json("b"),
+            &game_json("c")[..30]
+        );
+        assert!(parse(&content).is_err());
+
+        let (games, skipped) = salvage(&content).unwrap();
+        let ids: Vec<&str> = games.iter().map(|game| game.id.as_str()).collect();
+        assert_eq!(ids, ["a", "b"]);
+        assert_eq!(skipped, 0);
+    }
+
+    #[test]
+    fn salvages_a_garbled_version_1_list() {
+        let content = format!(
+            r#"[{},{{"id":"b","title":}}, {}]"#,
+            game_json("a"),
+            game_json("c")
+        );
+
+        let (games, _) = salvage(&content).unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].id, "a");
+    }
+
+    #[test]
+    fn skips_games_that_do_not_parse() {
+        let content = format!(
+            r#"{{"version":2,"games":[{},{{"id":"broken"}}]}}"#,
+            game_json("a")
+        );
+
+        let (games, skipped) = salvage(&content).unwrap();
+        assert_eq!(games.len(), 1);
+        assert_eq!(skipped, 1);
+    }
+
+    #[test]
+    fn salvages_nothing_without_a_list_of_games() {
+        assert!(salvage("not json at all").is_none());
+        assert!(salvage(r#"{"version":2,"#).is_none());
+    }
+
+    #[test]
+    fn upgrades_the_version_1_list() {
+        let document = parse(VERSION_1).unwrap();
+        assert_eq!(document.version, LIBRARY_FORMAT_VERSION);
+        assert!(document.extra.is_empty());
+        assert_eq!(document.games.len(), 2);
+
+        let game = &document.games[0];
+        assert_eq!(game.id, "4f9c2d1e-0b7a-4c55-9e3a-1d2b3c4d5e6f");
+        assert_eq!(game.title, "Hollow Depths");
+        assert_eq!(game.version.as_deref(), Some("1.2.0"));
+        assert_eq!(
+            game.archive_path.as_deref(),
+            Some(r"D:\Repacks\Hollow Depths.rar")
+        );
+        assert_eq!(game.repacker.as_deref(), Some("FitGirl"));
+        assert_eq!(game.tags, ["metroidvania", "indie"]);
+        assert_eq!(game.status, InstallStatus::Installed);
+        assert_eq!(game.size_bytes, Some(8_589_934_592));
+        assert_eq!(game.added_at.to_rfc3339(), "2023-03-04T10:00:00+00:00");
+        assert!(!game.favorite);
+        assert_eq!(game.play_count, 0);
+
+        let game = &document.games[1];
+        assert_eq!(game.status, InstallStatus::NotInstalled);
+        assert_eq!(game.size_bytes, None);
+    }
+
+    #[test]
+    fn reads_version_2() {
+        let document = parse(VERSION_2).unwrap();
+        assert_eq!(document.version, LIBRARY_FORMAT_VERSION);
+        assert_eq!(document.games.len(), 1);
+
+        let game = &document.games[0];
+        assert_eq!(game.alternate_titles, ["HD"]);
+        assert_eq!(game.launch_args.as_deref(), Some("-windowed"));
+        assert_eq!(game.env.get("DXVK_HUD").map(String::as_str), Some("fps"));
+        assert!(game.favorite);
+        assert_eq!(game.rating, Some(4));
+        assert_eq!(game.completion, Some(Completion::Playing));
+        assert_eq!(
+            game.custom_fields.get("platform").map(String::as_str),
+            Some("pc")
+        );
+        assert_eq!(game.play_count, 12);
+        assert_eq!(game.total_playtime_seconds, 43_200);
+    }
+
+    #[test]
+    fn keeps_unknown_fields_through_a_save() {
+        let document = parse(VERSION_2).unwrap();
+        assert_eq!(
+            document.extra.get("collectionsLayout"),
+            Some(&json!({ "sidebar": "collapsed" }))
+        );
+
+        let saved = parse(&document.to_json().unwrap()).unwrap();
+        assert_eq!(saved.extra, document.extra);
+        assert_eq!(
+            saved.games[0].extra.get("achievements"),
+            Some(&json!({ "unlocked": 17 }))
+        );
+        assert_eq!(saved.games, document.games);
+    }
+
+    #[test]
+    fn refuses_a_newer_format() {
+        let content = format!(r#"{{"version":{},"games":[]}}"#, LIBRARY_FORMAT_VERSION + 1);
+        assert!(parse(&content).unwrap_err().is::<NewerFormatError>());
+    }
+}
@@ -0,0 +1,472 @@
+use crate::error::CommandError;
+use crate::{Completion, GameEntry, InstallStatus, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+use uuid::Uuid;
+
+/// Version written into JSON exports, bumped whenever their shape changes.
+const EXPORT_VERSION: u32 = 1;
+
+const CSV_COLUMNS: &[&str] = &[
+    "id",
+    "title",
+    "sortTitle",
+    "alternateTitles",
+    "version",
+    "developer",
+    "publisher",
+    "releaseYear",
+    "description",
+    "archivePath",
+    "installPath",
+    "executablePath",
+    "launchArgs",
+    "workingDir",
+    "env",
+    "wrapperCommand",
+    "runElevated",
+    "repacker",
+    "tags",
+    "status",
+    "favorite",
+    "rating",
+    "completion",
+    "notes",
+    "customFields",
+    "checksum",
+    "color",
+    "coverPath",
+    "backgroundPath",
+    "iconPath",
+    "sizeBytes",
+    "downloadId",
+    "statusBeforeDownload",
+    "addedAt",
+    "updatedAt",
+    "lastPlayedAt",
+    "playCount",
+    "totalPlaytimeSeconds",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ExportFormat {
+    Json,
+    Csv,
+}
+
+impl ExportFormat {
+    fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "csv" => Ok(Self::Csv),
+            other => Err(anyhow!("Unsupported export format: {other}")),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ImportMode {
+    Merge,
+    Replace,
+    Append,
+}
+
+impl ImportMode {
+    fn parse(value: &str) -> Result<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "merge" => Ok(Self::Merge),
+            "replace" => Ok(Self::Replace),
+            "append" => Ok(Self::Append),
+            other => Err(anyhow!("Unsupported import mode: {other}")),
+        }
+    }
+}
+
+/// Layout of a JSON export. The version lets later releases read files
+/// written by older ones.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct LibraryExport {
+    version: u32,
+    exported_at: DateTime<Utc>,
+    games: Vec<GameEntry>,
+}
+
+/// A game as read from an export. Everything but the title may be missing so
+/// files written by older versions, or a plain copy of `library.json`, load too.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImportedGame {
+    id: Option<String>,
+    title: String,
+    sort_title: Option<String>,
+    #[serde(default)]
+    alternate_titles: Vec<String>,
+    version: Option<String>,
+    developer: Option<String>,
+    publisher: Option<String>,
+    /// Checked by `read_import` like `rating`.
+    release_year: Option<i64>,
+    description: Option<String>,
+    archive_path: Option<String>,
+    install_path: Option<String>,
+    executable_path: Option<String>,
+    launch_args: Option<String>,
+    working_dir: Option<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    wrapper_command: Option<String>,
+    #[serde(default)]
+    run_elevated: bool,
+    repacker: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default)]
+    status: InstallStatus,
+    #[serde(default)]
+    favorite: bool,
+    /// Checked by `read_import`, which refuses values outside 0 to 10.
+    rating: Option<i64>,
+    completion: Option<Completion>,
+    status_before_download: Option<InstallStatus>,
+    notes: Option<String>,
+    #[serde(default)]
+    custom_fields: BTreeMap<String, String>,
+    checksum: Option<String>,
+    color: Option<String>,
+    cover_path: Option<String>,
+    background_path: Option<String>,
+    icon_path: Option<String>,
+    size_bytes: Option<u64>,
+    added_at: Option<DateTime<Utc>>,
+    updated_at: Option<DateTime<Utc>>,
+    last_played_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    play_count: u64,
+    #[serde(default)]
+    total_playtime_seconds: u64,
+}
+
+impl ImportedGame {
+    fn into_entry(self, now: DateTime<Utc>) -> GameEntry {
+        // Downloads are not part of an export, so nothing is downloading after an import.
+        let status = match self.status {
+            InstallStatus::Downloading => self.status_before_download.unwrap_or_default(),
+            status => status,
+        };
+        let updated_at = self.updated_at.unwrap_or(now);
+        GameEntry {
+            id: self
+                .id
+                .filter(|id| !id.trim().is_empty())
+                .unwrap_or_else(|| Uuid::new_v4().to_string()),
+            title: self.title,
+            sort_title: self.sort_title,
+            alternate_titles: self.alternate_titles,
+            version: self.version,
+            developer: self.developer,
+            publisher: self.publisher,
+            release_year: self.release_year.and_then(|year| u16::try_from(year).ok()),
+            description: self.description,
+            archive_path: self.archive_path,
+            install_path: self.install_path,
+            executable_path: self.executable_path,
+            launch_args: self.launch_args,
+            working_dir: self.working_dir,
+            env: self.env,
+            wrapper_command: self.wrapper_command,
+            run_elevated: self.run_elevated,
+            repacker: self.repacker,
+            tags: self.tags,
+            status,
+            favorite: self.favorite,
+            rating: self.rating.and_then(|rating| u8::try_from(rating).ok()),
+            completion: self.completion,
+            notes: self.notes,
+            custom_fields: {
+                let mut fields = BTreeMap::new();
+                crate::merge_custom_fields(&mut fields, self.custom_fields);
+                fields
+            },
+            checksum: self.checksum,
+            color: self.color,
+            cover_path: self.cover_path,
+            background_path: self.background_path,
+            icon_path: self.icon_path,
+            size_bytes: self.size_bytes,
+            download_id: None,
+            status_before_download: None,
+            added_at: self.added_at.unwrap_or(updated_at),
+            updated_at,
+            last_played_at: self.last_played_at,
+            play_count: self.play_count,
+            total_playtime_seconds: self.total_playtime_seconds,
+            deleted_at: None,
+            extra: serde_json::Map::new(),
+        }
+    }
+}
+
+/// Accepts both the versioned export object and a bare array of games.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum ImportFile {
+    Export {
+        version: u32,
+        games: Vec<ImportedGame>,
+    },
+    Games(Vec<ImportedGame>),
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportSummary {
+    added: usize,
+    updated: usize,
+    /// Matched games whose imported copy was not newer than the library's.
+    skipped: usize,
+}
+
+/// Writes the whole library to `path` as pretty JSON or CSV and returns the
+/// number of games written. An existing file is only replaced with `overwrite`.
+#[tauri::command]
+pub fn export_library(
+    app: AppHandle,
+    library: State<'_, Library>,
+    path: String,
+    format: String,
+    overwrite: Option<bool>,
+) -> Result<usize, CommandError> {
+    if path.trim().is_empty() {
+        return Err(CommandError::invalid_input("Path cannot be empty"));
+    }
+    let format = ExportFormat::parse(&format).map_err(CommandError::invalid)?;
+    let target = PathBuf::from(&path);
+    if target.exists() && !overwrite.unwrap_or(false) {
+        return Err(CommandError::already_exists(format!(
+            "{path} already exists"
+        )));
+    }
+
+    let games = library.games(&app)?;
+    write_export(&target, format, &games).context("Failed to export library")?;
+    Ok(games.len())
+}
+
+/// Loads a JSON export into the library. `merge` updates games matching by id,
+/// or by title and install folder, when the imported copy is newer and adds
+/// the rest; `replace` swaps the whole library for the file; `append` adds
+/// every game as a new entry. The file is fully validated first, so a
+/// malformed one leaves the library untouched.
+#[tauri::command]
+pub fn import_library(
+    app: AppHandle,
+    library: State<'_, Library>,
+    path: String,
+    mode: String,
+) -> Result<ImportSummary, CommandError> {
+    let mode = ImportMode::parse(&mode).map_err(CommandError::invalid)?;
+    let imported = read_import(Path::new(&path)).context("Failed to import library")?;
+
+    let mut changed = Vec::new();
+    let summary = library.update(&app, |games| {
+        Ok(apply_import(games, imported, mode, &mut changed))
+    })?;
+    crate::emit_library_updated(&app, changed);
+    Ok(summary)
+}
+
+fn read_import(path: &Path) -> Result<Vec<GameEntry>> {
+    let content =
+        fs::read_to_string(path).with_context(|| format!("Cannot read {}", path.display()))?;
+    let games = match serde_json::from_str(&content).context("Not a library export")? {
+        ImportFile::Export { version, .. } if version > EXPORT_VERSION => {
+            return Err(anyhow!(
+                "The file was exported by a newer version of the launcher (format {version})"
+            ))
+        }
+        ImportFile::Export { games, .. } | ImportFile::Games(games) => games,
+    };
+
+    let now = Utc::now();
+    let mut entries = Vec::with_capacity(games.len());
+    for (index, game) in games.into_iter().enumerate() {
+        if game.title.trim().is_empty() {
+            return Err(
+                CommandError::invalid_input(format!("Game {} has no title", index + 1)).into(),
+            );
+        }
+        crate::launcher::validate_env(&game.env).with_context(|| format!("Game {}", index + 1))?;
+        crate::checked_rating(game.rating).with_context(|| format!("Game {}", index + 1))?;
+        crate::checked_release_year(game.release_year)
+            .with_context(|| format!("Game {}", index + 1))?;
+        crate::check_custom_fields(&game.custom_fields)
+            .with_context(|| format!("Game {}", index + 1))?;
+        entries.push(game.into_entry(now));
+    }
+    Ok(entries)
+}
+
+fn apply_import(
+    games: &mut Vec<GameEntry>,
+    imported: Vec<GameEntry>,
+    mode: ImportMode,
+    changed: &mut Vec<String>,
+) -> ImportSummary {
+    let mut summary = ImportSummary::default();
+    if mode == ImportMode::Replace {
+        changed.extend(games.drain(..).map(|game| game.id));
+    }
+    let mut ids: HashSet<String> = games.iter().map(|game| game.id.clone()).collect();
+
+    for mut entry in imported {
+        if mode == ImportMode::Merge {
+            if let Some(existing) = games.iter_mut().find(|game| is_same_game(game, &entry)) {
+                if entry.updated_at > existing.updated_at {
+                    entry.id = existing.id.clone();
+                    entry.added_at = existing.added_at;
+                    *existing = entry;
+                    changed.push(existing.id.clone());
+                    summary.updated += 1;
+                } else {
+                    summary.skipped += 1;
+                }
+                continue;
+            }
+        }
+
+        // Replace keeps the exported ids; everything else gets fresh ones, as
+        // does any id that is already taken.
+        if mode != ImportMode::Replace || ids.contains(&entry.id) {
+            entry.id = Uuid::new_v4().to_string();
+        }
+        ids.insert(entry.id.clone());
+        changed.push(entry.id.clone());
+        games.push(entry);
+        summary.added += 1;
+    }
+    summary
+}
+
+fn is_same_game(existing: &GameEntry, imported: &GameEntry) -> bool {
+    existing.id == imported.id
+        || (existing.title == imported.title && existing.install_path == imported.install_path)
+}
+
+fn write_export(path: &Path, format: ExportFormat, games: &[GameEntry]) -> Result<()> {
+    let content = match format {
+        ExportFormat::Json => serde_json::to_string_pretty(&LibraryExport {
+            version: EXPORT_VERSION,
+            exported_at: Utc::now(),
+            games: games.to_vec(),
+        })?,
+        ExportFormat::Csv => to_csv(games)?,
+    };
+    crate::write_atomic(path, content.as_bytes())
+}
+
+fn to_csv(games: &[GameEntry]) -> Result<String> {
+    let mut csv = csv_row(CSV_COLUMNS.iter().map(|column| column.to_string()));
+    for game in games {
+        csv.push_str(&csv_row([
+            game.id.clone(),
+            game.title.clone(),
+            game.sort_title.clone().unwrap_or_default(),
+            game.alternate_titles.join("\n"),
+            game.version.clone().unwrap_or_default(),
+            game.developer.clone().unwrap_or_default(),
+            game.publisher.clone().unwrap_or_default(),
+            game.release_year
+                .map(|year| year.to_string())
+                .unwrap_or_default(),
+            game.description.clone().unwrap_or_default(),
+            game.archive_path.clone().unwrap_or_default(),
+            game.install_path.clone().unwrap_or_default(),
+            game.executable_path.clone().unwrap_or_default(),
+            game.launch_args.clone().unwrap_or_default(),
+            game.working_dir.clone().unwrap_or_default(),
+            key_value_lines(&game.env),
+            game.wrapper_command.clone().unwrap_or_default(),
+            game.run_elevated.to_string(),
+            game.repacker.clone().unwrap_or_default(),
+            game.tags.join(";"),
+            enum_name(&game.status)?,
+            game.favorite.to_string(),
+            game.rating
+                .map(|rating| rating.to_string())
+                .unwrap_or_default(),
+            game.completion
+                .as_ref()
+                .map(enum_name)
+                .transpose()?
+                .unwrap_or_default(),
+            game.notes.clone().unwrap_or_default(),
+            key_value_lines(&game.custom_fields),
+            game.checksum.clone().unwrap_or_default(),
+            game.color.clone().unwrap_or_default(),
+            game.cover_path.clone().unwrap_or_default(),
+            game.background_path.clone().unwrap_or_default(),
+            game.icon_path.clone().unwrap_or_default(),
+            game.size_bytes
+                .map(|size| size.to_string())
+                .unwrap_or_default(),
+            game.download_id.clone().unwrap_or_default(),
+            game.status_before_download
+                .as_ref()
+                .map(enum_name)
+                .transpose()?
+                .unwrap_or_default(),
+            game.added_at.to_rfc3339(),
+            game.updated_at.to_rfc3339(),
+            game.last_played_at
+                .map(|played| played.to_rfc3339())
+                .unwrap_or_default(),
+            game.play_count.to_string(),
+            game.total_playtime_seconds.to_string(),
+        ]));
+    }
+    Ok(csv)
+}
+
+/// Environment variables or custom fields as sorted `name=value` lines.
+fn key_value_lines<'a>(pairs: impl IntoIterator<Item = (&'a String, &'a String)>) -> String {
+    let mut lines: Vec<String> = pairs
+        .into_iter()
+        .map(|(name, value)| format!("{name}={value}"))
+        .collect();
+    lines.sort();
+    lines.join("\n")
+}
+
+/// The serialized (kebab-case) name of a unit enum variant.
+fn enum_name<T: Serialize>(value: &T) -> Result<String> {
+    match serde_json::to_value(value)? {
+        serde_json::Value::String(name) => Ok(name),
+        other => Err(anyhow!("Expected a unit variant, got {other}")),
+    }
+}
+
+fn csv_row(fields: impl IntoIterator<Item = String>) -> String {
+    let mut row = fields
+        .into_iter()
+        .map(|field| csv_field(&field))
+        .collect::<Vec<_>>()
+        .join(",");
+    row.push_str("\r\n");
+    row
+}
+
+/// Quotes a field per RFC 4180 when it contains a separator, a quote or a
+/// line break.
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
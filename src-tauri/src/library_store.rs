@@ -0,0 +1,684 @@
+use crate::launcher::PlaySession;
+use crate::{Completion, GameEntry, InstallStatus};
+use anyhow::{anyhow, Context, Result};
+use rusqlite::types::{FromSql, FromSqlError, FromSqlResult, ToSqlOutput, ValueRef};
+use rusqlite::{params, Connection, OptionalExtension, Row, ToSql, Transaction};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Schema changes in the order they were introduced. `PRAGMA user_version`
+/// records how many have been applied, so only newer ones run on start.
+const MIGRATIONS: &[&str] = &[
+    "
+    CREATE TABLE games (
+        id TEXT PRIMARY KEY,
+        title TEXT NOT NULL,
+        version TEXT,
+        archive_path TEXT,
+        install_path TEXT,
+        executable_path TEXT,
+        repacker TEXT,
+        status TEXT NOT NULL,
+        notes TEXT,
+        checksum TEXT,
+        color TEXT,
+        size_bytes INTEGER,
+        download_id TEXT,
+        status_before_download TEXT,
+        added_at TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );
+    CREATE TABLE game_tags (
+        game_id TEXT NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        tag TEXT NOT NULL,
+        PRIMARY KEY (game_id, position)
+    );
+    ",
+    "ALTER TABLE games ADD COLUMN deleted_at TEXT;",
+    "ALTER TABLE games ADD COLUMN favorite INTEGER NOT NULL DEFAULT 0;",
+    "
+    ALTER TABLE games ADD COLUMN last_played_at TEXT;
+    ALTER TABLE games ADD COLUMN play_count INTEGER NOT NULL DEFAULT 0;
+    ",
+    "
+    ALTER TABLE games ADD COLUMN total_playtime_seconds INTEGER NOT NULL DEFAULT 0;
+    CREATE TABLE play_sessions (
+        id INTEGER PRIMARY KEY,
+        game_id TEXT NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        started_at TEXT NOT NULL,
+        ended_at TEXT NOT NULL,
+        duration_seconds INTEGER NOT NULL
+    );
+    CREATE INDEX play_sessions_by_game ON play_sessions (game_id, started_at);
+    ",
+    "
+    ALTER TABLE games ADD COLUMN launch_args TEXT;
+    ALTER TABLE games ADD COLUMN working_dir TEXT;
+    ",
+    "
+    CREATE TABLE game_env (
+        game_id TEXT NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        name TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (game_id, name)
+    );
+    ",
+    "
+    ALTER TABLE games ADD COLUMN wrapper_command TEXT;
+    ALTER TABLE games ADD COLUMN run_elevated INTEGER NOT NULL DEFAULT 0;
+    ",
+    "
+    ALTER TABLE games ADD COLUMN cover_path TEXT;
+    ALTER TABLE games ADD COLUMN background_path TEXT;
+    ",
+    "
+    ALTER TABLE games ADD COLUMN release_year INTEGER;
+    ALTER TABLE games ADD COLUMN description TEXT;
+    ",
+    "
+    CREATE TABLE settings (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    ",
+    "
+    ALTER TABLE games ADD COLUMN rating INTEGER;
+    ALTER TABLE games ADD COLUMN completion TEXT;
+    ",
+    "
+    CREATE TABLE game_custom_fields (
+        game_id TEXT NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        PRIMARY KEY (game_id, key)
+    );
+    ",
+    "
+    ALTER TABLE games ADD COLUMN sort_title TEXT;
+    CREATE TABLE game_alternate_titles (
+        game_id TEXT NOT NULL REFERENCES games (id) ON DELETE CASCADE,
+        position INTEGER NOT NULL,
+        title TEXT NOT NULL,
+        PRIMARY KEY (game_id, position)
+    );
+    ",
+    "
+    ALTER TABLE games ADD COLUMN developer TEXT;
+    ALTER TABLE games ADD COLUMN publisher TEXT;
+    ",
+    "ALTER TABLE games ADD COLUMN icon_path TEXT;",
+    "ALTER TABLE games ADD COLUMN extra TEXT;",
+];
+
+const GAME_COLUMNS: &str = "id, title, version, archive_path, install_path, executable_path, \
+    repacker, status, notes, checksum, color, size_bytes, download_id, status_before_download, \
+    added_at, updated_at, deleted_at, favorite, last_played_at, play_count, \
+    total_playtime_seconds, launch_args, working_dir, wrapper_command, run_elevated, \
+    cover_path, background_path, release_year, description, rating, completion, sort_title, \
+    developer, publisher, icon_path, extra";
+
+#[derive(Debug, thiserror::Error)]
+#[error("The library was created by a newer version of the launcher (schema {0})")]
+pub struct NewerSchemaError(pub usize);
+
+/// The games table of `library.db`. Callers work with whole `GameEntry`
+/// lists; only rows that actually changed are written.
+#[derive(Debug)]
+pub struct LibraryStore {
+    conn: Connection,
+}
+
+impl LibraryStore {
+    /// Opens the database at `path`, creating it and applying pending
+    /// migrations. When the database is new, `legacy` supplies games from the
+    /// JSON library that predates it; they are imported in the same
+    /// transaction. Returns whether such an import took place.
+    pub fn open(
+        path: &Path,
+        legacy: impl FnOnce() -> Result<Option<Vec<GameEntry>>>,
+    ) -> Result<(Self, bool)> {
+        let mut conn =
+            Connection::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+        conn.pragma_update(None, "foreign_keys", true)?;
+
+        let version: usize = conn.query_row("PRAGMA user_version", [], |row| row.get(0))?;
+        if version > MIGRATIONS.len() {
+            return Err(NewerSchemaError(version).into());
+        }
+
+        let tx = conn.transaction()?;
+        for migration in &MIGRATIONS[version..] {
+            tx.execute_batch(migration)?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())?;
+        let imported = match version {
+            0 => match legacy()? {
+                Some(games) => {
+                    for game in &games {
+                        upsert_game(&tx, game)?;
+                    }
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        };
+        tx.commit()?;
+
+        Ok((Self { conn }, imported))
+    }
+
+    pub fn load(&self) -> Result<Vec<GameEntry>> {
+        let mut tags: HashMap<String, Vec<String>> = HashMap::new();
+        let mut statement = self
+            .conn
+            .prepare("SELECT game_id, tag FROM game_tags ORDER BY game_id, position")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (game_id, tag): (String, String) = row?;
+            tags.entry(game_id).or_default().push(tag);
+        }
+
+        let mut alternate_titles: HashMap<String, Vec<String>> = HashMap::new();
+        let mut statement = self.conn.prepare(
+            "SELECT game_id, title FROM game_alternate_titles ORDER BY game_id, position",
+        )?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+        for row in rows {
+            let (game_id, title): (String, String) = row?;
+            alternate_titles.entry(game_id).or_default().push(title);
+        }
+
+        let mut env: HashMap<String, HashMap<String, String>> = HashMap::new();
+        let mut statement = self
+            .conn
+            .prepare("SELECT game_id, name, value FROM game_env")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        for row in rows {
+            let (game_id, name, value): (String, String, String) = row?;
+            env.entry(game_id).or_default().insert(name, value);
+        }
+
+        let mut custom_fields: HashMap<String, BTreeMap<String, String>> = HashMap::new();
+        let mut statement = self
+            .conn
+            .prepare("SELECT game_id, key, value FROM game_custom_fields")?;
+        let rows = statement.query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?;
+        for row in rows {
+            let (game_id, key, value): (String, String, String) = row?;
+            custom_fields.entry(game_id).or_default().insert(key, value);
+        }
+
+        let mut statement = self
+            .conn
+            .prepare(&format!("SELECT {GAME_COLUMNS} FROM games ORDER BY rowid"))?;
+        let rows = statement.query_map([], read_game)?;
+        let mut games = Vec::new();
+        for row in rows {
+            let mut game = row?;
+            game.tags = tags.remove(&game.id).unwrap_or_default();
+            game.alternate_titles = alternate_titles.remove(&game.id).unwrap_or_default();
+            game.env = env.remove(&game.id).unwrap_or_default();
+            game.custom_fields = custom_fields.remove(&game.id).unwrap_or_default();
+            games.push(game);
+        }
+        Ok(games)
+    }
+
+    /// Brings the database from `previous` to `games` in one transaction.
+    pub fn save(&mut self, previous: &[GameEntry], games: &[GameEntry]) -> Result<()> {
+        self.save_merging(previous, games, &[])
+    }
+
+    /// Like [`LibraryStore::save`], but first moves the play sessions of
+    /// each `(from, to)` in `merged` to `to`, so removing `from` keeps them.
+    pub fn save_merging(
+        &mut self,
+        previous: &[GameEntry],
+        games: &[GameEntry],
+        merged: &[(&str, &str)],
+    ) -> Result<()> {
+        let before: HashMap<&str, &GameEntry> = previous
+            .iter()
+            .map(|game| (game.id.as_str(), game))
+            .collect();
+        let remaining: HashSet<&str> = games.iter().map(|game| game.id.as_str()).collect();
+
+        let tx = self.conn.transaction()?;
+        for game in games {
+            if before.get(game.id.as_str()) != Some(&game) {
+                upsert_game(&tx, game)?;
+            }
+        }
+        for (from, to) in merged {
+            tx.execute(
+                "UPDATE play_sessions SET game_id = ?2 WHERE game_id = ?1",
+                [from, to],
+            )?;
+        }
+        for game in previous {
+            if !remaining.contains(game.id.as_str()) {
+                tx.execute("DELETE FROM games WHERE id = ?1", [&game.id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Saves `game`, whose playtime already includes `session`, together
+    /// with the session itself.
+    pub fn record_session(&mut self, game: &GameEntry, session: &PlaySession) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        upsert_game(&tx, game)?;
+        tx.execute(
+            "INSERT INTO play_sessions (game_id, started_at, ended_at, duration_seconds)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                session.game_id,
+                session.started_at,
+                session.ended_at,
+                session.duration_seconds,
+            ],
+        )?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// A library-wide setting, `None` when it was never set.
+    pub fn setting(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT value FROM settings WHERE key = ?1", [key], |row| {
+                row.get(0)
+            })
+            .optional()?)
+    }
+
+    pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2)
+             ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    /// Play sessions of a game, oldest first.
+    pub fn sessions(&self, game_id: &str) -> Result<Vec<PlaySession>> {
+        let mut statement = self.conn.prepare(
+            "SELECT game_id, started_at, ended_at, duration_seconds FROM play_sessions
+             WHERE game_id = ?1 ORDER BY started_at",
+        )?;
+        let rows = statement.query_map([game_id], |row| {
+            Ok(PlaySession {
+                game_id: row.get(0)?,
+                started_at: row.get(1)?,
+                ended_at: row.get(2)?,
+                duration_seconds: row.get(3)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+}
+
+/// Inserts or fully overwrites a game. The values are bound in `GAME_COLUMNS` order.
+fn upsert_game(tx: &Transaction<'_>, game: &GameEntry) -> Result<()> {
+    let columns: Vec<&str> = GAME_COLUMNS.split(',').map(str::trim).collect();
+    let placeholders: Vec<String> = (1..=columns.len())
+        .map(|index| format!("?{index}"))
+        .collect();
+    let updates: Vec<String> = columns[1..]
+        .iter()
+        .map(|column| format!("{column} = excluded.{column}"))
+        .collect();
+    let extra = (!game.extra.is_empty())
+        .then(|| serde_json::to_string(&game.extra))
+        .transpose()?;
+    tx.execute(
+        &format!(
+            "INSERT INTO games ({GAME_COLUMNS}) VALUES ({})
+             ON CONFLICT (id) DO UPDATE SET {}",
+            placeholders.join(", "),
+            updates.join(", ")
+        ),
+        params![
+            game.id,
+            game.title,
+            game.version,
+            game.archive_path,
+            game.install_path,
+            game.executable_path,
+            game.repacker,
+            game.status,
+            game.notes,
+            game.checksum,
+            game.color,
+            game.size_bytes,
+            game.download_id,
+            game.status_before_download,
+            game.added_at,
+            game.updated_at,
+            game.deleted_at,
+            game.favorite,
+            game.last_played_at,
+            game.play_count,
+            game.total_playtime_seconds,
+            game.launch_args,
+            game.working_dir,
+            game.wrapper_command,
+            game.run_elevated,
+            game.cover_path,
+            game.background_path,
+            game.release_year,
+            game.description,
+            game.rating,
+            game.completion,
+            game.sort_title,
+            game.developer,
+            game.publisher,
+            game.icon_path,
+            extra,
+        ],
+    )?;
+
+    tx.execute("DELETE FROM game_tags WHERE game_id = ?1", [&game.id])?;
+    for (position, tag) in game.tags.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO game_tags (game_id, position, tag) VALUES (?1, ?2, ?3)",
+            params![game.id, position, tag],
+        )?;
+    }
+
+    tx.execute(
+        "DELETE FROM game_alternate_titles WHERE game_id = ?1",
+        [&game.id],
+    )?;
+    for (position, title) in game.alternate_titles.iter().enumerate() {
+        tx.execute(
+            "INSERT INTO game_alternate_titles (game_id, position, title) VALUES (?1, ?2, ?3)",
+            params![game.id, position, title],
+        )?;
+    }
+
+    tx.execute("DELETE FROM game_env WHERE game_id = ?1", [&game.id])?;
+    for (name, value) in &game.env {
+        tx.execute(
+            "INSERT INTO game_env (game_id, name, value) VALUES (?1, ?2, ?3)",
+            params![game.id, name, value],
+        )?;
+    }
+
+    tx.execute(
+        "DELETE FROM game_custom_fields WHERE game_id = ?1",
+        [&game.id],
+    )?;
+    for (key, value) in &game.custom_fields {
+        tx.execute(
+            "INSERT INTO game_custom_fields (game_id, key, value) VALUES (?1, ?2, ?3)",
+            params![game.id, key, value],
+        )?;
+    }
+    Ok(())
+}
+
+/// Reads a `games` row selected with `GAME_COLUMNS`. Tags, alternate titles,
+/// environment variables and custom fields are filled in by the caller.
+fn read_game(row: &Row<'_>) -> rusqlite::Result<GameEntry> {
+    Ok(GameEntry {
+        id: row.get(0)?,
+        title: row.get(1)?,
+        version: row.get(2)?,
+        archive_path: row.get(3)?,
+        install_path: row.get(4)?,
+        executable_path: row.get(5)?,
+        repacker: row.get(6)?,
+        tags: Vec::new(),
+        env: HashMap::new(),
+        custom_fields: BTreeMap::new(),
+        status: row.get(7)?,
+        notes: row.get(8)?,
+        checksum: row.get(9)?,
+        color: row.get(10)?,
+        size_bytes: row.get(11)?,
+        download_id: row.get(12)?,
+        status_before_download: row.get(13)?,
+        added_at: row.get(14)?,
+        updated_at: row.get(15)?,
+        deleted_at: row.get(16)?,
+        favorite: row.get(17)?,
+        last_played_at: row.get(18)?,
+        play_count: row.get(19)?,
+        total_playtime_seconds: row.get(20)?,
+        launch_args: row.get(21)?,
+        working_dir: row.get(22)?,
+        wrapper_command: row.get(23)?,
+        run_elevated: row.get(24)?,
+        cover_path: row.get(25)?,
+        background_path: row.get(26)?,
+        release_year: row.get(27)?,
+        description: row.get(28)?,
+        rating: row.get(29)?,
+        completion: row.get(30)?,
+        sort_title: row.get(31)?,
+        developer: row.get(32)?,
+        publisher: row.get(33)?,
+        icon_path: row.get(34)?,
+        alternate_titles: Vec::new(),
+        extra: row
+            .get::<_, Option<ExtraFields>>(35)?
+            .map(|extra| extra.0)
+            .unwrap_or_default(),
+    })
+}
+
+/// Fields of a game this build does not know, stored as a JSON object in the
+/// `extra` column. The column is `NULL` when there are none.
+struct ExtraFields(Map<String, Value>);
+
+impl FromSql for ExtraFields {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        serde_json::from_str(value.as_str()?)
+            .map(Self)
+            .map_err(|error| FromSqlError::Other(error.into()))
+    }
+}
+
+/// Statuses are stored under the same names the frontend sees.
+impl ToSql for InstallStatus {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let name = match self {
+            Self::NotInstalled => "not-installed",
+            Self::Downloading => "downloading",
+            Self::Installed => "installed",
+            Self::Archived => "archived",
+        };
+        Ok(name.into())
+    }
+}
+
+impl FromSql for InstallStatus {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "not-installed" => Ok(Self::NotInstalled),
+            "downloading" => Ok(Self::Downloading),
+            "installed" => Ok(Self::Installed),
+            "archived" => Ok(Self::Archived),
+            other => Err(FromSqlError::Other(
+                anyhow!("Unknown install status: {other}").into(),
+            )),
+        }
+    }
+}
+
+impl ToSql for Completion {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        let name = match self {
+            Self::NotStarted => "not-started",
+            Self::Playing => "playing",
+            Self::Completed => "completed",
+            Self::Abandoned => "abandoned",
+            Self::OnHold => "on-hold",
+        };
+        Ok(name.into())
+    }
+}
+
+impl FromSql for Completion {
+    fn column_result(value: ValueRef<'_>) -> FromSqlResult<Self> {
+        match value.as_str()? {
+            "not-started" => Ok(Self::NotStarted),
+            "playing" => Ok(Self::Playing),
+            "completed" => Ok(Self::Completed),
+            "abandoned" => Ok(Self::Abandoned),
+            "on-hold" => Ok(Self::OnHold),
+            other => Err(FromSqlError::Other(
+                anyhow!("Unknown completion: {other}").into(),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A database at schema `version`, with one game and a tag.
+    fn database_at(path: &Path, version: usize) {
+        let conn = Connection::open(path).unwrap();
+        for migration in &MIGRATIONS[..version] {
+            conn.execute_batch(migration).unwrap();
+        }
+        conn.execute(
+            "INSERT INTO games (id, title, status, added_at, updated_at)
+             VALUES ('game-1', 'Old Game', 'installed', '2024-01-02T03:04:05Z', '2024-01-02T03:04:05Z')",
+            [],
+        )
+        .unwrap();
+        conn.execute(
+            "INSERT INTO game_tags (game_id, position, tag) VALUES ('game-1', 0, 'rpg')",
+            [],
+        )
+        .unwrap();
+        conn.pragma_update(None, "user_version", version).unwrap();
+    }
+
+    #[test]
+    fn opens_an_old_database_migrated() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.db");
+        database_at(&path, 1);
+
+        let (store, imported) =
+            LibraryStore::open(&path, || panic!("only new databases import")).unwrap();
+        assert!(!imported);
+        let version: usize = store
+            .conn
+            .query_row("PRAGMA user_version", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(version, MIGRATIONS.len());
+
+        let games = store.load().unwrap();
+        assert_eq!(games.len(), 1);
+        let game = &games[0];
+        assert_eq!(game.title, "Old Game");
+        assert_eq!(game.status, InstallStatus::Installed);
+        assert_eq!(game.tags, ["rpg"]);
+        assert!(!game.favorite);
+        assert_eq!(game.play_count, 0);
+        assert!(game.env.is_empty());
+        assert!(game.extra.is_empty());
+    }
+
+    #[test]
+    fn games_from_before_favorites_are_not_favorites() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.db");
+        let favorites = MIGRATIONS
+            .iter()
+            .position(|migration| migration.contains("favorite"))
+            .unwrap();
+        database_at(&path, favorites);
+
+        let (store, _) = LibraryStore::open(&path, || Ok(None)).unwrap();
+        let games = store.load().unwrap();
+        assert_eq!(games.len(), 1);
+        assert!(!games[0].favorite);
+    }
+
+    /// The version 2 fixture's game, whose `achievements` no build knows.
+    fn game_from_the_future() -> GameEntry {
+        let library: Value =
+            serde_json::from_str(include_str!("../tests/fixtures/library-v2.json")).unwrap();
+        serde_json::from_value(library["games"][0].clone()).unwrap()
+    }
+
+    #[test]
+    fn keeps_unknown_fields_through_save_and_reload() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.db");
+        let game = game_from_the_future();
+        assert_eq!(
+            game.extra["achievements"],
+            serde_json::json!({ "unlocked": 17 })
+        );
+
+        let (store, _) = LibraryStore::open(&path, || Ok(Some(vec![game.clone()]))).unwrap();
+        let loaded = store.load().unwrap();
+        assert_eq!(loaded.len(), 1);
+        assert_eq!(loaded[0], game);
+
+        let (mut store, _) = LibraryStore::open(&path, || Ok(None)).unwrap();
+        let mut renamed = loaded.clone();
+        renamed[0].title = "Hollow Depths: Remastered".into();
+        store.save(&loaded, &renamed).unwrap();
+        drop(store);
+
+        let (store, _) = LibraryStore::open(&path, || Ok(None)).unwrap();
+        let reloaded = store.load().unwrap();
+        assert_eq!(reloaded, renamed);
+        assert_eq!(reloaded[0].extra, game.extra);
+    }
+
+    #[test]
+    fn merging_keeps_the_removed_game_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.db");
+        let primary = game_from_the_future();
+        let mut secondary = primary.clone();
+        secondary.id = "duplicate".into();
+        let games = vec![primary.clone(), secondary.clone()];
+        let (mut store, _) = LibraryStore::open(&path, || Ok(Some(games.clone()))).unwrap();
+        let session = PlaySession {
+            game_id: secondary.id.clone(),
+            started_at: primary.added_at,
+            ended_at: primary.updated_at,
+            duration_seconds: 600,
+        };
+        store.record_session(&secondary, &session).unwrap();
+
+        store
+            .save_merging(&games, &games[..1], &[(&secondary.id, &primary.id)])
+            .unwrap();
+
+        assert_eq!(store.load().unwrap().len(), 1);
+        assert!(store.sessions(&secondary.id).unwrap().is_empty());
+        let moved = store.sessions(&primary.id).unwrap();
+        assert_eq!(moved.len(), 1);
+        assert_eq!(moved[0].duration_seconds, 600);
+    }
+
+    #[test]
+    fn refuses_a_newer_schema() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.db");
+        let conn = Connection::open(&path).unwrap();
+        conn.pragma_update(None, "user_version", MIGRATIONS.len() + 1)
+            .unwrap();
+        drop(conn);
+
+        let error = LibraryStore::open(&path, || Ok(None)).unwrap_err();
+        assert!(error.is::<NewerSchemaError>());
+    }
+}
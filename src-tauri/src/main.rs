@@ -1,383 +1,1187 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod archives;
+mod artwork;
+mod checksum;
+mod cli;
+mod collections;
+mod data_dir;
+mod deep_link;
+mod disk;
+mod disk_usage;
+mod downloads;
+mod elevation;
+mod error;
+mod executables;
+mod extract;
+mod game_patch;
+mod history;
+mod hook;
+mod icons;
+mod launcher;
+mod library_format;
+mod library_io;
+mod library_store;
+mod metadata;
+mod notifications;
+mod path_access;
+mod relocation;
+mod repackers;
+mod scan;
+mod search;
+mod settings;
+mod shortcuts;
+mod size_cache;
+mod sizes;
+mod stats;
+mod steam;
+mod stores;
+mod tags;
+mod taskbar;
+mod terminal;
+mod trash;
+mod tray;
+mod uninstall;
+mod validation;
+
 use anyhow::{anyhow, Context, Result};
-use chrono::{DateTime, Utc};
-use reqwest::blocking::Client;
+use chrono::{DateTime, Datelike, Utc};
+use collections::Collections;
+use downloads::DownloadManager;
+use error::CommandError;
+use extract::Extractions;
+use game_patch::GamePatch;
+use history::DownloadHistory;
+use launcher::{Launcher, PlaySession};
+use library_format::{LibraryDocument, NewerFormatError};
+use library_store::{LibraryStore, NewerSchemaError};
+use metadata::MetadataService;
+use search::SortKey;
+use settings::SettingsStore;
+use size_cache::SizeCache;
+use sizes::SizeJobs;
+use tags::{TagPolicy, TagRules, TAG_POLICY_SETTING};
+use tray::Tray;
 use serde::{Deserialize, Serialize};
-use std::fs::{self, File};
-use std::io::{Read, Write};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::thread;
-use tauri::{AppHandle, Manager};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
 use uuid::Uuid;
-use walkdir::WalkDir;
 
+/// The library database.
+const LIBRARY_DB: &str = "library.db";
+/// Where the library lived before the database; imported into it once.
 const LIBRARY_FILE: &str = "library.json";
-const DOWNLOAD_BUFFER: usize = 1024 * 128;
+/// The JSON library is renamed to this after it has been imported.
+const MIGRATED_LIBRARY_FILE: &str = "library.json.migrated";
+/// Previous versions of the library kept as `library.backup.<n>.json`, newest first.
+const LIBRARY_BACKUPS: usize = 5;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "kebab-case")]
 pub enum InstallStatus {
+    #[default]
     NotInstalled,
     Downloading,
     Installed,
     Archived,
 }
 
-impl Default for InstallStatus {
-    fn default() -> Self {
-        Self::NotInstalled
-    }
+/// How far the user got with a game.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Completion {
+    NotStarted,
+    Playing,
+    Completed,
+    Abandoned,
+    OnHold,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Highest value `rating` accepts.
+const MAX_RATING: u8 = 10;
+
+/// Earliest `release_year` accepted. The latest is a few years from now, for
+/// announced games.
+const MIN_RELEASE_YEAR: u16 = 1950;
+const RELEASE_YEARS_AHEAD: i32 = 10;
+
+/// Longest custom field name accepted, in characters.
+const MAX_CUSTOM_FIELD_KEY_CHARS: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct GameEntry {
     pub id: String,
     pub title: String,
+    /// Title used for ordering instead of `title`, such as "Witcher 3".
+    pub sort_title: Option<String>,
+    /// Other names the game is known by, such as a localized title.
+    #[serde(default)]
+    pub alternate_titles: Vec<String>,
     pub version: Option<String>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    #[serde(default)]
+    pub release_year: Option<u16>,
+    /// Summary of the game, as opposed to the user's own `notes`.
+    pub description: Option<String>,
     pub archive_path: Option<String>,
     pub install_path: Option<String>,
     pub executable_path: Option<String>,
+    /// Arguments passed to the executable, quoted the way a shell would.
+    pub launch_args: Option<String>,
+    /// Folder the executable starts in, instead of its own.
+    pub working_dir: Option<String>,
+    /// Variables set for the game on top of the launcher's environment. An
+    /// empty value removes the variable instead.
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    /// Command the game is started through, such as `gamemoderun %command%`.
+    pub wrapper_command: Option<String>,
+    /// Start the game as administrator (Windows) or root through pkexec (Linux).
+    #[serde(default)]
+    pub run_elevated: bool,
     pub repacker: Option<String>,
     #[serde(default)]
     pub tags: Vec<String>,
     pub status: InstallStatus,
+    #[serde(default)]
+    pub favorite: bool,
+    /// The user's score from 0 to `MAX_RATING`.
+    #[serde(default)]
+    pub rating: Option<u8>,
+    #[serde(default)]
+    pub completion: Option<Completion>,
     pub notes: Option<String>,
+    /// Fields the user named themselves, such as a save location or a forum
+    /// thread.
+    #[serde(default)]
+    pub custom_fields: BTreeMap<String, String>,
     pub checksum: Option<String>,
     pub color: Option<String>,
+    /// Cover image stored under the app's `artwork` folder.
+    pub cover_path: Option<String>,
+    /// Wide image shown behind the game's details.
+    pub background_path: Option<String>,
+    /// Small icon taken from the executable by `extract_icon`.
+    #[serde(default)]
+    pub icon_path: Option<String>,
     #[serde(default)]
     pub size_bytes: Option<u64>,
+    /// Download currently fetching this game's archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub download_id: Option<String>,
+    /// Status to restore if that download fails or is cancelled. Kept on the
+    /// entry so the revert still works after a restart mid-download.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status_before_download: Option<InstallStatus>,
     pub added_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default)]
+    pub last_played_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub play_count: u64,
+    /// Time spent in sessions started by the launcher.
+    #[serde(default)]
+    pub total_playtime_seconds: u64,
+    /// When the game was moved to the trash; `None` for games in the library.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+    /// Fields this build does not know, written by a newer one. They are
+    /// kept and written back so running an older build loses nothing.
+    #[serde(flatten)]
+    pub extra: serde_json::Map<String, serde_json::Value>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct GamePayload {
     title: String,
+    sort_title: Option<String>,
+    #[serde(default)]
+    alternate_titles: Vec<String>,
     version: Option<String>,
+    developer: Option<String>,
+    publisher: Option<String>,
+    /// Wider than the stored `u16` so out-of-range values reach `checked_release_year`.
+    release_year: Option<i64>,
+    description: Option<String>,
     archive_path: Option<String>,
     install_path: Option<String>,
     executable_path: Option<String>,
+    launch_args: Option<String>,
+    working_dir: Option<String>,
+    #[serde(default)]
+    env: HashMap<String, String>,
+    wrapper_command: Option<String>,
+    #[serde(default)]
+    run_elevated: bool,
     repacker: Option<String>,
     #[serde(default)]
     tags: Vec<String>,
     #[serde(default)]
     status: InstallStatus,
+    /// Wider than the stored `u8` so out-of-range values reach `checked_rating`.
+    rating: Option<i64>,
+    completion: Option<Completion>,
     notes: Option<String>,
+    /// Merged into the existing fields on update; an empty value removes
+    /// the field.
+    #[serde(default)]
+    custom_fields: BTreeMap<String, String>,
     checksum: Option<String>,
     color: Option<String>,
-    size_override: Option<u64>,
+    /// As for `GamePatch::size_override`.
+    #[serde(default, deserialize_with = "settings::present")]
+    size_override: Option<Option<u64>>,
+    #[serde(default)]
+    favorite: bool,
+    /// Adds the game even when it looks like one already in the library.
+    #[serde(default)]
+    allow_duplicate: bool,
+}
+
+/// Field through which a new game matched an existing one.
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+enum DuplicateField {
+    Title,
+    InstallPath,
+    ArchivePath,
 }
 
 #[derive(Debug, Serialize)]
-#[serde(rename_all = "camelCase")]
-struct DownloadQueuedPayload {
-    id: String,
-    file_name: String,
-    destination: String,
+#[serde(tag = "outcome", rename_all = "kebab-case")]
+enum AddGameResult {
+    Added {
+        game: Box<GameEntry>,
+    },
+    /// Nothing was added; resend with `allowDuplicate` to add it anyway.
+    #[serde(rename_all = "camelCase")]
+    Duplicate {
+        existing_id: String,
+        field: DuplicateField,
+    },
 }
 
+/// Sent whenever the backend changes library entries on its own, so open
+/// views can refresh.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct DownloadProgressEvent {
-    id: String,
-    file_name: String,
-    processed: u64,
-    total: Option<u64>,
+struct LibraryUpdatedEvent {
+    game_ids: Vec<String>,
 }
 
+/// In-memory copy of the library database, shared by every command. Changes
+/// go through [`Library::update`], which holds the lock until they are on
+/// disk, so concurrent commands cannot overwrite each other's edits.
+#[derive(Debug, Default)]
+struct Library {
+    /// `None` until the database has been opened successfully.
+    loaded: Mutex<Option<LoadedLibrary>>,
+}
+
+#[derive(Debug)]
+struct LoadedLibrary {
+    store: LibraryStore,
+    games: Vec<GameEntry>,
+    /// Removed games, kept until they are restored or purged.
+    trash: Vec<GameEntry>,
+    tag_policy: TagPolicy,
+}
+
+impl Library {
+    /// Runs `action` on the library while holding its lock, opening the
+    /// database first if needed.
+    fn with_loaded<T>(
+        &self,
+        app: &AppHandle,
+        action: impl FnOnce(&mut LoadedLibrary) -> Result<T>,
+    ) -> Result<T> {
+        let mut loaded = self.loaded.lock().expect("library poisoned");
+        let loaded = match &mut *loaded {
+            Some(loaded) => loaded,
+            empty => empty.insert(open_library(app)?),
+        };
+        action(loaded)
+    }
+
+    fn load(&self, app: &AppHandle) -> Result<()> {
+        self.with_loaded(app, |_| Ok(()))
+    }
+
+    fn games(&self, app: &AppHandle) -> Result<Vec<GameEntry>> {
+        self.with_loaded(app, |loaded| Ok(loaded.games.clone()))
+    }
+
+    fn trash(&self, app: &AppHandle) -> Result<Vec<GameEntry>> {
+        self.with_loaded(app, |loaded| Ok(loaded.trash.clone()))
+    }
+
+    fn tag_policy(&self, app: &AppHandle) -> Result<TagPolicy> {
+        self.with_loaded(app, |loaded| Ok(loaded.tag_policy))
+    }
+
+    fn set_tag_policy(&self, app: &AppHandle, policy: TagPolicy) -> Result<()> {
+        self.with_loaded(app, |loaded| {
+            loaded.store.set_setting(TAG_POLICY_SETTING, policy.name())?;
+            loaded.tag_policy = policy;
+            Ok(())
+        })
+    }
+
+    /// Rules for normalizing tags under the library's policy, seeded with the
+    /// spellings already in use.
+    fn tag_rules(&self, app: &AppHandle) -> Result<TagRules> {
+        self.with_loaded(app, |loaded| Ok(TagRules::new(loaded.tag_policy, &loaded.games)))
+    }
+
+    /// Stores a finished play session and adds it to its game's playtime.
+    /// Returns the updated game, or `None` when it was removed meanwhile.
+    fn record_session(&self, app: &AppHandle, session: &PlaySession) -> Result<Option<GameEntry>> {
+        self.with_loaded(app, |loaded| {
+            let Some(index) = loaded
+                .games
+                .iter()
+                .position(|game| game.id == session.game_id)
+            else {
+                return Ok(None);
+            };
+            let mut game = loaded.games[index].clone();
+            game.total_playtime_seconds += session.duration_seconds;
+            loaded.store.record_session(&game, session)?;
+            loaded.games[index] = game.clone();
+            Ok(Some(game))
+        })
+    }
+
+    fn sessions(&self, app: &AppHandle, game_id: &str) -> Result<Vec<PlaySession>> {
+        self.with_loaded(app, |loaded| loaded.store.sessions(game_id))
+    }
+
+    /// Applies `change` to a copy of the library and saves the entries that
+    /// changed. The in-memory library only takes the new state once it is
+    /// stored, and the state it replaces becomes the newest backup.
+    fn update<T>(
+        &self,
+        app: &AppHandle,
+        change: impl FnOnce(&mut Vec<GameEntry>) -> Result<T>,
+    ) -> Result<T> {
+        self.update_with_trash(app, |games, _| change(games))
+    }
+
+    /// Like [`Library::update`], but `change` also gets the trash. A game
+    /// left in both lists stays in the library and leaves the trash.
+    fn update_with_trash<T>(
+        &self,
+        app: &AppHandle,
+        change: impl FnOnce(&mut Vec<GameEntry>, &mut Vec<GameEntry>) -> Result<T>,
+    ) -> Result<T> {
+        self.update_merging(app, &[], change)
+    }
+
+    /// Like [`Library::update_with_trash`], but the play sessions of each
+    /// `(from, to)` in `merged` move to `to` in the same write, so removing
+    /// `from` keeps them.
+    fn update_merging<T>(
+        &self,
+        app: &AppHandle,
+        merged: &[(&str, &str)],
+        change: impl FnOnce(&mut Vec<GameEntry>, &mut Vec<GameEntry>) -> Result<T>,
+    ) -> Result<T> {
+        self.with_loaded(app, |loaded| {
+            let mut games = loaded.games.clone();
+            let mut trash = loaded.trash.clone();
+            let value = change(&mut games, &mut trash)?;
+            let live: HashSet<&str> = games.iter().map(|game| game.id.as_str()).collect();
+            trash.retain(|game| !live.contains(game.id.as_str()));
+
+            let games_changed = games != loaded.games;
+            if games_changed || trash != loaded.trash {
+                let previous = [loaded.games.as_slice(), loaded.trash.as_slice()].concat();
+                let current = [games.as_slice(), trash.as_slice()].concat();
+                loaded.store.save_merging(&previous, &current, merged)?;
+                if games_changed {
+                    // Losing a backup is not worth refusing to save the library over.
+                    let _ = backup_library(app, &loaded.games);
+                }
+                loaded.games = games;
+                loaded.trash = trash;
+            }
+            Ok(value)
+        })
+    }
+}
+
+/// Sent when `library.json` could not be parsed, or `library.db` could not
+/// be opened, and the library was rebuilt.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct DownloadCompleteEvent {
-    id: String,
-    file_name: String,
-    destination: String,
+struct LibraryRecoveredEvent {
+    /// Where the unreadable file was moved.
+    corrupt_path: String,
+    error: String,
+    /// Entries salvaged into the new library, or restored from a backup.
+    recovered: usize,
+    /// Entries that could not be salvaged, or `None` when the file was not
+    /// even a list and nothing could be read from it.
+    skipped: Option<usize>,
 }
 
+/// A rotated copy of the library the user can restore.
 #[derive(Debug, Clone, Serialize)]
 #[serde(rename_all = "camelCase")]
-struct DownloadErrorEvent {
-    id: String,
-    file_name: String,
-    message: String,
+struct LibraryBackup {
+    name: String,
+    modified_at: Option<DateTime<Utc>>,
+    size_bytes: u64,
+    /// Entries in the backup, `None` when it cannot be parsed.
+    game_count: Option<usize>,
 }
 
+/// What `load_library` returns: the whole library when called without
+/// arguments, as it always has, or one page of it.
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+enum LibraryListing {
+    All(Vec<GameEntry>),
+    Page(LibraryPage),
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LibraryPage {
+    games: Vec<GameEntry>,
+    /// Number of games listed across all pages, for scrollbars and page counts.
+    total: usize,
+}
+
+/// Lists the library, most recently updated first unless `sort_by` says
+/// otherwise, optionally only its favorites. Passing any argument returns a
+/// [`LibraryPage`] instead of the bare list.
 #[tauri::command]
-fn load_library(app: AppHandle) -> Result<Vec<GameEntry>, String> {
-    read_library(&app)
-        .map_err(|error| format!("Failed to load library: {error}"))
-        .map(|mut collection| {
-            collection.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
-            collection
-        })
+fn load_library(
+    app: AppHandle,
+    library: State<'_, Library>,
+    sort_by: Option<SortKey>,
+    descending: Option<bool>,
+    limit: Option<usize>,
+    offset: Option<usize>,
+    favorites_only: Option<bool>,
+) -> Result<LibraryListing, CommandError> {
+    let mut games = library.games(&app).context("Failed to load library")?;
+    if favorites_only == Some(true) {
+        games.retain(|game| game.favorite);
+    }
+
+    let paged = sort_by.is_some()
+        || descending.is_some()
+        || limit.is_some()
+        || offset.is_some()
+        || favorites_only.is_some();
+    let descending = descending.unwrap_or(sort_by.is_none());
+    search::sort_games(&mut games, sort_by.unwrap_or_default(), descending);
+    if !paged {
+        return Ok(LibraryListing::All(games));
+    }
+
+    let total = games.len();
+    let games = games
+        .into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .collect();
+    Ok(LibraryListing::Page(LibraryPage { games, total }))
 }
 
+/// Adds a game unless it looks like one already in the library, in which
+/// case the existing entry is reported instead. Without `sizeOverride` the
+/// size is measured afterwards and sent as `game-size-updated`.
 #[tauri::command]
-fn add_game(app: AppHandle, payload: GamePayload) -> Result<GameEntry, String> {
-    let mut library = read_library(&app).map_err(|error| error.to_string())?;
-    let mut entry = game_from_payload(payload, None);
+fn add_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    payload: GamePayload,
+) -> Result<AddGameResult, CommandError> {
+    check_payload(&payload).map_err(CommandError::invalid)?;
+    let allow_duplicate = payload.allow_duplicate;
+    let measure = payload.size_override.is_none();
+    let mut rules = library.tag_rules(&app)?;
+    let mut entry = game_from_payload(payload, None, &mut rules);
     entry.id = Uuid::new_v4().to_string();
     entry.added_at = Utc::now();
     entry.updated_at = entry.added_at;
 
-    library.push(entry.clone());
-    write_library(&app, &library).map_err(|error| error.to_string())?;
+    let result = library.update(&app, |games| {
+        if !allow_duplicate {
+            if let Some((existing, field)) = find_duplicate(games, &entry) {
+                return Ok(AddGameResult::Duplicate {
+                    existing_id: existing.id.clone(),
+                    field,
+                });
+            }
+        }
+        games.push(entry.clone());
+        Ok(AddGameResult::Added {
+            game: Box::new(entry),
+        })
+    })?;
+    if let AddGameResult::Added { game } = &result {
+        if measure {
+            sizes::measure(&app, game);
+        }
+    }
+    Ok(result)
+}
 
-    Ok(entry)
+/// The first game that is probably `candidate` under another entry: same
+/// install folder, same archive, or the same title once case, punctuation
+/// and version or edition tags are ignored.
+fn find_duplicate<'a>(
+    games: &'a [GameEntry],
+    candidate: &GameEntry,
+) -> Option<(&'a GameEntry, DuplicateField)> {
+    let same_path = |a: &Option<String>, b: &Option<String>| match (a, b) {
+        (Some(a), Some(b)) => Path::new(a) == Path::new(b),
+        _ => false,
+    };
+    let title = search::title_key(&candidate.title);
+    games.iter().find_map(|game| {
+        if same_path(&game.install_path, &candidate.install_path) {
+            Some((game, DuplicateField::InstallPath))
+        } else if same_path(&game.archive_path, &candidate.archive_path) {
+            Some((game, DuplicateField::ArchivePath))
+        } else if !title.is_empty() && search::title_key(&game.title) == title {
+            Some((game, DuplicateField::Title))
+        } else {
+            None
+        }
+    })
 }
 
+/// Adds many games in one write. All or nothing: if any payload is invalid
+/// the call fails, naming its position in `payloads`, and nothing is added.
+/// Blank titles become "Untitled", as for `add_game`.
 #[tauri::command]
-fn update_game(app: AppHandle, id: String, payload: GamePayload) -> Result<GameEntry, String> {
-    let mut library = read_library(&app).map_err(|error| error.to_string())?;
-    let mut entry = library
+fn add_games(
+    app: AppHandle,
+    library: State<'_, Library>,
+    payloads: Vec<GamePayload>,
+) -> Result<Vec<GameEntry>, CommandError> {
+    let measure: Vec<bool> = payloads
         .iter()
-        .find(|game| game.id == id)
-        .cloned()
-        .ok_or_else(|| format!("Game {id} not found"))?;
-
-    entry = game_from_payload(payload, Some(entry));
-    entry.id = id.clone();
-    entry.updated_at = Utc::now();
-
-    if let Some(existing) = library.iter_mut().find(|game| game.id == id) {
-        *existing = entry.clone();
+        .map(|payload| payload.size_override.is_none())
+        .collect();
+    let mut rules = library.tag_rules(&app)?;
+    let entries = games_from_payloads(payloads, &mut rules, Utc::now())?;
+
+    library.update(&app, |games| {
+        games.extend(entries.iter().cloned());
+        Ok(())
+    })?;
+    emit_library_updated(&app, entries.iter().map(|entry| entry.id.clone()).collect());
+    for (entry, _) in entries.iter().zip(measure).filter(|(_, measure)| *measure) {
+        sizes::measure(&app, entry);
     }
 
-    write_library(&app, &library).map_err(|error| error.to_string())?;
+    Ok(entries)
+}
 
-    Ok(entry)
+/// New entries for `payloads`, all added at `now`, once every payload has
+/// been checked. Fails on the first invalid one, naming its position.
+fn games_from_payloads(
+    payloads: Vec<GamePayload>,
+    rules: &mut TagRules,
+    now: DateTime<Utc>,
+) -> Result<Vec<GameEntry>, CommandError> {
+    for (index, payload) in payloads.iter().enumerate() {
+        check_payload(payload)
+            .map_err(|error| CommandError::invalid_input(format!("Game {index}: {error}")))?;
+    }
+    Ok(payloads
+        .into_iter()
+        .map(|payload| {
+            let mut entry = game_from_payload(payload, None, rules);
+            entry.id = Uuid::new_v4().to_string();
+            entry.added_at = now;
+            entry.updated_at = now;
+            entry
+        })
+        .collect())
 }
 
+/// Replaces a game's editable fields with `payload`. With
+/// `expected_updated_at`, the edit is refused with `Conflict` when the game
+/// changed since the caller read it; the error's details hold the stored
+/// entry. Leaving it out keeps last-write-wins. Without `sizeOverride` the
+/// size is measured afterwards, as for `add_game`.
 #[tauri::command]
-fn remove_game(app: AppHandle, id: String) -> Result<(), String> {
-    let mut library = read_library(&app).map_err(|error| error.to_string())?;
-    let initial_len = library.len();
-    library.retain(|game| game.id != id);
-
-    if library.len() == initial_len {
-        return Err(format!("Game {id} not found"));
+fn update_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+    payload: GamePayload,
+    expected_updated_at: Option<DateTime<Utc>>,
+) -> Result<GameEntry, CommandError> {
+    check_payload(&payload).map_err(CommandError::invalid)?;
+    let changes_size = payload.size_override.is_none();
+    let mut payload = Some(payload);
+    let mut rules = library.tag_rules(&app)?;
+    let updated = library.update(&app, |games| {
+        check_unchanged(games, &id, expected_updated_at)?;
+        let mut updated = None;
+        modify_games(games, std::slice::from_ref(&id), |existing| {
+            if let Some(payload) = payload.take() {
+                *existing = game_from_payload(payload, Some(existing.clone()), &mut rules);
+                updated = Some(existing.clone());
+            }
+        });
+        Ok(updated.ok_or_else(|| CommandError::game_not_found(&id))?)
+    })?;
+    if changes_size {
+        sizes::measure(&app, &updated);
     }
+    Ok(updated)
+}
 
-    write_library(&app, &library).map_err(|error| error.to_string())?;
-    Ok(())
+/// Sets the status of several games in one write. Returns the ids that were found.
+#[tauri::command]
+fn set_games_status(
+    app: AppHandle,
+    library: State<'_, Library>,
+    ids: Vec<String>,
+    status: InstallStatus,
+) -> Result<Vec<String>, CommandError> {
+    library
+        .update(&app, |games| {
+            Ok(modify_games(games, &ids, |game| game.status = status.clone()))
+        })
+        .map_err(CommandError::from)
 }
 
+/// Stamps a game as played now and counts the launch, for games the
+/// frontend starts itself. Like favorites, this leaves `updated_at` alone.
 #[tauri::command]
-fn open_path(app: AppHandle, path: String) -> Result<(), String> {
-    let resolved = PathBuf::from(&path);
-    if !resolved.exists() {
-        return Err(format!("Path does not exist: {path}"));
-    }
+fn record_launch(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+) -> Result<GameEntry, CommandError> {
+    stamp_launch(&app, &library, &id).map_err(CommandError::from)
+}
 
-    let path_string = resolved.to_string_lossy().to_string();
+fn stamp_launch(app: &AppHandle, library: &Library, id: &str) -> Result<GameEntry> {
+    library.update(app, |games| {
+        let game = games
+            .iter_mut()
+            .find(|game| game.id == id)
+            .ok_or_else(|| CommandError::game_not_found(id))?;
+        game.last_played_at = Some(Utc::now());
+        game.play_count += 1;
+        Ok(game.clone())
+    })
+}
 
-    tauri::api::shell::open(&app.shell_scope(), path_string, None)
-        .map_err(|error| format!("Failed to open path: {error}"))
+/// Flips a game's favorite flag. `updated_at` is left alone so starring a
+/// game does not move it up the recently changed list.
+#[tauri::command]
+fn toggle_favorite(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+) -> Result<GameEntry, CommandError> {
+    library
+        .update(&app, |games| {
+            let game = games
+                .iter_mut()
+                .find(|game| game.id == id)
+                .ok_or_else(|| CommandError::game_not_found(&id))?;
+            game.favorite = !game.favorite;
+            Ok(game.clone())
+        })
+        .map_err(CommandError::from)
 }
 
+/// Moves a game to the trash. `expected_updated_at` works as for
+/// `update_game`.
 #[tauri::command]
-fn scan_path_size(path: String) -> Result<u64, String> {
-    let target = PathBuf::from(path.clone());
-    if !target.exists() {
-        return Err(format!("Path does not exist: {path}"));
-    }
-    compute_path_size(&target).map_err(|error| error.to_string())
+fn remove_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+    expected_updated_at: Option<DateTime<Utc>>,
+) -> Result<(), CommandError> {
+    library
+        .update_with_trash(&app, |games, trash| {
+            check_unchanged(games, &id, expected_updated_at)?;
+            if trash_by_id(games, trash, std::slice::from_ref(&id)).is_empty() {
+                return Err(CommandError::game_not_found(&id).into());
+            }
+            Ok(())
+        })
+        .map_err(CommandError::from)
 }
 
+/// Removes several games in one write. Returns the ids that were found.
 #[tauri::command]
-fn queue_download(
+fn remove_games(
     app: AppHandle,
-    url: String,
-    destination: String,
-    file_name: Option<String>,
-) -> Result<DownloadQueuedPayload, String> {
-    if url.trim().is_empty() {
-        return Err("URL cannot be empty".into());
+    library: State<'_, Library>,
+    ids: Vec<String>,
+) -> Result<Vec<String>, CommandError> {
+    library
+        .update_with_trash(&app, |games, trash| Ok(trash_by_id(games, trash, &ids)))
+        .map_err(CommandError::from)
+}
+
+/// Folds `secondary_id` into `primary_id` and deletes it, in one write. The
+/// primary keeps its own values and takes the secondary's where it has none;
+/// tags are combined and the earlier `added_at` wins. Playtime and play
+/// counts add up, the later `last_played_at` wins, and the primary is a
+/// favorite when either was. The secondary's play sessions move to the
+/// primary. The secondary's artwork is deleted unless the primary took it
+/// over, and the primary takes its place in collections. The merged game is
+/// measured again afterwards.
+#[tauri::command]
+fn merge_games(
+    app: AppHandle,
+    library: State<'_, Library>,
+    primary_id: String,
+    secondary_id: String,
+) -> Result<GameEntry, CommandError> {
+    if primary_id == secondary_id {
+        return Err(CommandError::invalid_input(
+            "Cannot merge a game with itself",
+        ));
+    }
+
+    let mut rules = library.tag_rules(&app)?;
+    let merging = [(secondary_id.as_str(), primary_id.as_str())];
+    let merged = library.update_merging(&app, &merging, |games, _| {
+        let secondary = games
+            .iter()
+            .position(|game| game.id == secondary_id)
+            .ok_or_else(|| CommandError::game_not_found(&secondary_id))?;
+        let secondary = games.remove(secondary);
+        let primary = games
+            .iter_mut()
+            .find(|game| game.id == primary_id)
+            .ok_or_else(|| CommandError::game_not_found(&primary_id))?;
+        merge_into(primary, secondary, &mut rules);
+        Ok(primary.clone())
+    })?;
+
+    if !artwork::uses_artwork_of(&app, &merged, &secondary_id) {
+        artwork::remove_game_artwork(&app, &secondary_id);
+    }
+    let _ = collections::replace_game(&app, &secondary_id, &primary_id);
+    emit_library_updated(&app, vec![primary_id, secondary_id]);
+    sizes::measure(&app, &merged);
+    Ok(merged)
+}
+
+fn merge_into(primary: &mut GameEntry, secondary: GameEntry, rules: &mut TagRules) {
+    let fill = |field: &mut Option<String>, other: Option<String>| {
+        if field.is_none() {
+            *field = other;
+        }
+    };
+    fill(&mut primary.sort_title, secondary.sort_title);
+    for title in secondary.alternate_titles {
+        if !primary.alternate_titles.contains(&title) {
+            primary.alternate_titles.push(title);
+        }
     }
-    if destination.trim().is_empty() {
-        return Err("Destination cannot be empty".into());
+    fill(&mut primary.version, secondary.version);
+    fill(&mut primary.developer, secondary.developer);
+    fill(&mut primary.publisher, secondary.publisher);
+    fill(&mut primary.description, secondary.description);
+    primary.release_year = primary.release_year.or(secondary.release_year);
+    fill(&mut primary.archive_path, secondary.archive_path);
+    fill(&mut primary.install_path, secondary.install_path);
+    fill(&mut primary.executable_path, secondary.executable_path);
+    fill(&mut primary.launch_args, secondary.launch_args);
+    fill(&mut primary.working_dir, secondary.working_dir);
+    fill(&mut primary.wrapper_command, secondary.wrapper_command);
+    for (name, value) in secondary.env {
+        primary.env.entry(name).or_insert(value);
+    }
+    fill(&mut primary.repacker, secondary.repacker);
+    fill(&mut primary.notes, secondary.notes);
+    for (key, value) in secondary.custom_fields {
+        primary.custom_fields.entry(key).or_insert(value);
+    }
+    fill(&mut primary.checksum, secondary.checksum);
+    fill(&mut primary.color, secondary.color);
+    primary.rating = primary.rating.or(secondary.rating);
+    primary.completion = primary.completion.or(secondary.completion);
+    fill(&mut primary.cover_path, secondary.cover_path);
+    fill(&mut primary.background_path, secondary.background_path);
+    fill(&mut primary.icon_path, secondary.icon_path);
+    for (key, value) in secondary.extra {
+        primary.extra.entry(key).or_insert(value);
     }
 
-    let id = Uuid::new_v4().to_string();
-    let resolved_destination = PathBuf::from(destination);
-    let inferred_name = file_name
-        .filter(|name| !name.trim().is_empty())
-        .or_else(|| infer_file_name(&url))
-        .unwrap_or_else(|| format!("download-{id}"));
+    primary.tags = rules.normalize(primary.tags.drain(..).chain(secondary.tags).collect());
+    primary.favorite |= secondary.favorite;
+    primary.play_count += secondary.play_count;
+    primary.total_playtime_seconds += secondary.total_playtime_seconds;
+    primary.last_played_at = primary.last_played_at.max(secondary.last_played_at);
+    primary.added_at = primary.added_at.min(secondary.added_at);
+    primary.size_bytes = primary.size_bytes.or(secondary.size_bytes);
+    primary.updated_at = Utc::now();
+}
 
-    let mut target_path = resolved_destination.clone();
-    if target_path.is_dir() || !target_path.as_path().extension().is_some() {
-        target_path = target_path.join(&inferred_name);
+/// Applies `change` to each game listed in `ids` and bumps its `updated_at`.
+/// Returns the ids that matched a game, in library order.
+fn modify_games(
+    games: &mut [GameEntry],
+    ids: &[String],
+    mut change: impl FnMut(&mut GameEntry),
+) -> Vec<String> {
+    let now = Utc::now();
+    let mut found = Vec::new();
+    for game in games.iter_mut().filter(|game| ids.contains(&game.id)) {
+        game.updated_at = now;
+        change(game);
+        found.push(game.id.clone());
     }
+    found
+}
 
-    if let Some(parent) = target_path.parent() {
-        if let Err(error) = fs::create_dir_all(parent) {
-            return Err(format!("Failed to create destination folder: {error}"));
-        }
+/// Fails with `Conflict` when `expected` is given and game `id` was updated at
+/// another time, so a stale edit does not overwrite a newer one. The error's
+/// details hold the stored entry. Unknown games pass; callers report those.
+fn check_unchanged(games: &[GameEntry], id: &str, expected: Option<DateTime<Utc>>) -> Result<()> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+    match games.iter().find(|game| game.id == id) {
+        Some(game) if game.updated_at != expected => Err(CommandError::conflict(format!(
+            "{} was changed elsewhere",
+            game.title
+        ))
+        .with_details(serde_json::to_value(game)?)
+        .into()),
+        _ => Ok(()),
     }
+}
 
-    let app_handle = app.clone();
-    let url_clone = url.clone();
-    let file_name_clone = inferred_name.clone();
-    let destination_clone = target_path.clone();
-
-    thread::spawn(move || {
-        if let Err(error) = download_file(app_handle.clone(), &id, &url_clone, &destination_clone, &file_name_clone) {
-            let _ = app_handle.emit_all(
-                "download-error",
-                DownloadErrorEvent {
-                    id: id.clone(),
-                    file_name: file_name_clone.clone(),
-                    message: error.to_string(),
-                },
-            );
-        } else {
-            let _ = app_handle.emit_all(
-                "download-complete",
-                DownloadCompleteEvent {
-                    id: id.clone(),
-                    file_name: file_name_clone.clone(),
-                    destination: destination_clone.to_string_lossy().to_string(),
-                },
-            );
+/// Moves every game listed in `ids` to the trash. Returns the ids that
+/// matched a game.
+fn trash_by_id(
+    games: &mut Vec<GameEntry>,
+    trash: &mut Vec<GameEntry>,
+    ids: &[String],
+) -> Vec<String> {
+    let now = Utc::now();
+    let mut removed = Vec::new();
+    let (deleted, kept) = games.drain(..).partition(|game| ids.contains(&game.id));
+    *games = kept;
+    for mut game in deleted {
+        game.deleted_at = Some(now);
+        removed.push(game.id.clone());
+        trash.push(game);
+    }
+    removed
+}
+
+#[tauri::command]
+fn get_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+) -> Result<GameEntry, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == id)
+        .ok_or_else(|| CommandError::game_not_found(&id))?;
+    refresh_missing_size(&app, &game);
+    Ok(game)
+}
+
+/// Looks up several games at once, in the order requested. Unknown ids are skipped.
+#[tauri::command]
+fn get_games(
+    app: AppHandle,
+    library: State<'_, Library>,
+    ids: Vec<String>,
+) -> Result<Vec<GameEntry>, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    let found: Vec<GameEntry> = ids
+        .iter()
+        .filter_map(|id| games.iter().find(|game| &game.id == id).cloned())
+        .collect();
+    for game in &found {
+        refresh_missing_size(&app, game);
+    }
+    Ok(found)
+}
+
+/// Names of the custom fields used by any game in the library, sorted, for
+/// suggesting them while editing.
+#[tauri::command]
+fn list_custom_field_keys(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<Vec<String>, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    let keys: BTreeSet<String> = games
+        .into_iter()
+        .flat_map(|game| game.custom_fields.into_keys())
+        .collect();
+    Ok(keys.into_iter().collect())
+}
+
+/// Measures an installed game that has no size yet in the background and
+/// stores the result, announcing it through `library-updated`.
+fn refresh_missing_size(app: &AppHandle, game: &GameEntry) {
+    if game.size_bytes.is_some() {
+        return;
+    }
+    let Some(install_path) = game.install_path.clone() else {
+        return;
+    };
+    let app = app.clone();
+    let id = game.id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let Ok(size) = compute_path_size(Path::new(&install_path)) else {
+            return;
+        };
+        let updated = app.state::<Library>().update(&app, |games| {
+            let Some(game) = games
+                .iter_mut()
+                .find(|game| game.id == id && game.size_bytes.is_none())
+            else {
+                return Ok(false);
+            };
+            game.size_bytes = Some(size);
+            Ok(true)
+        });
+        if matches!(updated, Ok(true)) {
+            emit_library_updated(&app, vec![id]);
         }
     });
+}
 
-    Ok(DownloadQueuedPayload {
-        id,
-        file_name: inferred_name,
-        destination: target_path.to_string_lossy().to_string(),
-    })
+/// Opens a file or folder with the system's default handler. Only paths the
+/// library knows are opened; see [`path_access::check`].
+#[tauri::command]
+fn open_path(app: AppHandle, path: String) -> Result<(), CommandError> {
+    path_access::check(&app, &path)?;
+    let resolved = PathBuf::from(&path);
+
+    let path_string = resolved.to_string_lossy().to_string();
+
+    tauri::api::shell::open(&app.shell_scope(), path_string, None)
+        .context("Failed to open path")
+        .map_err(CommandError::from)
 }
 
-fn download_file(
+/// Size of the file or folder at `path`, in one call. Prefer
+/// `start_size_scan` for folders that may be large. Links inside a folder
+/// are only followed with `follow_symlinks`. Otherwise a folder that looks
+/// unchanged since it was last measured keeps its cached size, unless
+/// `force` is set.
+#[tauri::command]
+fn scan_path_size(
     app: AppHandle,
-    id: &str,
-    url: &str,
-    target: &Path,
-    file_name: &str,
-) -> Result<()> {
-    let client = Client::builder()
-        .danger_accept_invalid_certs(true)
-        .build()
-        .context("Failed to create HTTP client")?;
-
-    let mut response = client.get(url).send().context("Failed to start download")?;
+    path: String,
+    follow_symlinks: Option<bool>,
+    force: Option<bool>,
+) -> Result<u64, CommandError> {
+    let target = PathBuf::from(path.clone());
+    if !target.exists() {
+        return Err(CommandError::not_found(format!(
+            "Path does not exist: {path}"
+        )));
+    }
+    if follow_symlinks == Some(true) {
+        return sizes::path_size(&target, true).map_err(CommandError::from);
+    }
+    size_cache::cached_size(&app, &target, force.unwrap_or(false)).map_err(CommandError::from)
+}
 
-    if !response.status().is_success() {
-        return Err(anyhow!("Download failed with status {}", response.status()));
+#[tauri::command]
+fn list_library_backups(app: AppHandle) -> Result<Vec<LibraryBackup>, CommandError> {
+    let dir = resolve_data_dir(&app)?;
+    let mut backups = Vec::new();
+    for index in 1..=LIBRARY_BACKUPS {
+        let path = library_backup_path(&dir, index);
+        let Ok(metadata) = fs::metadata(&path) else {
+            continue;
+        };
+        let game_count = fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| library_format::parse(&content).ok())
+            .map(|document| document.games.len());
+        backups.push(LibraryBackup {
+            name: path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            modified_at: metadata.modified().ok().map(DateTime::<Utc>::from),
+            size_bytes: metadata.len(),
+            game_count,
+        });
     }
+    Ok(backups)
+}
 
-    let total = response.content_length();
-    let mut file = File::create(target).context("Failed to create destination file")?;
-    let mut downloaded: u64 = 0;
-    let mut buffer = vec![0u8; DOWNLOAD_BUFFER];
+/// Replaces the library with one of its backups. The current library becomes
+/// the newest backup, so a restore can itself be undone.
+#[tauri::command]
+fn restore_library_backup(
+    app: AppHandle,
+    library: State<'_, Library>,
+    name: String,
+) -> Result<Vec<GameEntry>, CommandError> {
+    let dir = resolve_data_dir(&app)?;
+    // Only names this launcher produces are accepted, never arbitrary paths.
+    let path = (1..=LIBRARY_BACKUPS)
+        .map(|index| library_backup_path(&dir, index))
+        .find(|path| path.file_name().is_some_and(|file| file == name.as_str()))
+        .ok_or_else(|| CommandError::not_found(format!("Unknown library backup: {name}")))?;
+
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read backup {name}"))?;
+    let restored = library_format::parse(&content)
+        .with_context(|| format!("Backup {name} is unreadable"))?
+        .games;
+
+    library.update(&app, |games| {
+        *games = restored.clone();
+        Ok(())
+    })?;
+    emit_library_updated(&app, restored.iter().map(|game| game.id.clone()).collect());
+    Ok(restored)
+}
 
-    loop {
-        let bytes_read = response.read(&mut buffer)?;
-        if bytes_read == 0 {
-            break;
-        }
-        file.write_all(&buffer[..bytes_read])?;
-        downloaded += bytes_read as u64;
-
-        let _ = app.emit_all(
-            "download-progress",
-            DownloadProgressEvent {
-                id: id.to_string(),
-                file_name: file_name.to_string(),
-                processed: downloaded,
-                total,
-            },
-        );
+/// Refuses payload values `game_from_payload` would otherwise have to drop.
+fn check_payload(payload: &GamePayload) -> Result<()> {
+    launcher::validate_env(&payload.env)?;
+    checked_rating(payload.rating)?;
+    checked_release_year(payload.release_year)?;
+    check_custom_fields(&payload.custom_fields)
+}
+
+/// Refuses ratings outside 0 to `MAX_RATING` instead of clamping them.
+fn checked_rating(rating: Option<i64>) -> Result<Option<u8>> {
+    match rating {
+        None => Ok(None),
+        Some(rating) => u8::try_from(rating)
+            .ok()
+            .filter(|rating| *rating <= MAX_RATING)
+            .map(Some)
+            .ok_or_else(|| anyhow!("Rating must be between 0 and {MAX_RATING}, got {rating}")),
     }
+}
 
-    file.flush()?;
+/// Refuses release years before `MIN_RELEASE_YEAR` or more than
+/// `RELEASE_YEARS_AHEAD` years from now.
+fn checked_release_year(year: Option<i64>) -> Result<Option<u16>> {
+    let Some(year) = year else {
+        return Ok(None);
+    };
+    let latest = Utc::now().year() + RELEASE_YEARS_AHEAD;
+    u16::try_from(year)
+        .ok()
+        .filter(|year| *year >= MIN_RELEASE_YEAR && i32::from(*year) <= latest)
+        .map(Some)
+        .ok_or_else(|| {
+            anyhow!("Release year must be between {MIN_RELEASE_YEAR} and {latest}, got {year}")
+        })
+}
 
+/// Refuses custom field names that are empty or too long once trimmed, or
+/// that only differ from another by surrounding whitespace.
+fn check_custom_fields(fields: &BTreeMap<String, String>) -> Result<()> {
+    let mut seen = HashSet::new();
+    for key in fields.keys().map(|key| key.trim()) {
+        if key.is_empty() {
+            return Err(anyhow!("Custom field names cannot be empty"));
+        }
+        if key.chars().count() > MAX_CUSTOM_FIELD_KEY_CHARS {
+            return Err(anyhow!(
+                "Custom field names are limited to {MAX_CUSTOM_FIELD_KEY_CHARS} characters: {key}"
+            ));
+        }
+        if !seen.insert(key) {
+            return Err(anyhow!("Custom field {key} is given twice"));
+        }
+    }
     Ok(())
 }
 
-fn infer_file_name(url: &str) -> Option<String> {
-    let parsed = url::Url::parse(url).ok()?;
-    let last = parsed.path_segments()?.last()?;
-    if last.is_empty() {
-        None
-    } else {
-        Some(last.to_string())
-    }
-}
-
-fn game_from_payload(payload: GamePayload, existing: Option<GameEntry>) -> GameEntry {
-    let GamePayload {
-        title,
-        version,
-        archive_path,
-        install_path,
-        executable_path,
-        repacker,
-        tags,
-        status,
-        notes,
-        checksum,
-        color,
-        size_override,
-    } = payload;
+/// Applies `changes` to `fields` under trimmed names. Values are kept as
+/// given, except that a blank one removes the field.
+fn merge_custom_fields(fields: &mut BTreeMap<String, String>, changes: BTreeMap<String, String>) {
+    for (key, value) in changes {
+        let key = key.trim();
+        if value.trim().is_empty() {
+            fields.remove(key);
+        } else {
+            fields.insert(key.to_string(), value);
+        }
+    }
+}
 
+fn game_from_payload(
+    payload: GamePayload,
+    existing: Option<GameEntry>,
+    rules: &mut TagRules,
+) -> GameEntry {
     let now = Utc::now();
-
     let mut entry = existing.unwrap_or_else(|| GameEntry {
         id: Uuid::new_v4().to_string(),
         title: String::new(),
+        sort_title: None,
+        alternate_titles: Vec::new(),
         version: None,
+        developer: None,
+        publisher: None,
+        release_year: None,
+        description: None,
         archive_path: None,
         install_path: None,
         executable_path: None,
+        launch_args: None,
+        working_dir: None,
+        env: HashMap::new(),
+        wrapper_command: None,
+        run_elevated: false,
         repacker: None,
         tags: Vec::new(),
         status: InstallStatus::default(),
+        favorite: false,
+        rating: None,
+        completion: None,
         notes: None,
+        custom_fields: BTreeMap::new(),
         checksum: None,
         color: None,
+        cover_path: None,
+        background_path: None,
+        icon_path: None,
         size_bytes: None,
+        download_id: None,
+        status_before_download: None,
         added_at: now,
         updated_at: now,
+        last_played_at: None,
+        play_count: 0,
+        total_playtime_seconds: 0,
+        deleted_at: None,
+        extra: serde_json::Map::new(),
     });
 
-    let title = title.trim();
-    entry.title = if title.is_empty() {
-        "Untitled".to_string()
-    } else {
-        title.to_string()
-    };
-
-    let archive_path = archive_path.and_then(non_empty);
-    let install_path = install_path.and_then(non_empty);
-    let executable_path = executable_path.and_then(non_empty);
-
-    entry.version = version.and_then(non_empty);
-    entry.archive_path = archive_path.clone();
-    entry.install_path = install_path.clone();
-    entry.executable_path = executable_path;
-    entry.repacker = repacker.and_then(non_empty);
-    entry.tags = normalize_tags(tags);
-    entry.status = status;
-    entry.notes = notes.and_then(non_empty);
-    entry.checksum = checksum.and_then(non_empty);
-    entry.color = color.and_then(non_empty);
-
-    if let Some(size) = size_override
-        .or_else(|| {
-            archive_path
-                .as_ref()
-                .or(install_path.as_ref())
-                .and_then(|path| compute_path_size(Path::new(path)).ok())
-        })
-    {
-        entry.size_bytes = Some(size);
-    }
-
+    GamePatch::from(payload).apply(&mut entry, rules);
     entry
 }
 
@@ -390,93 +1194,508 @@ fn non_empty(value: String) -> Option<String> {
     }
 }
 
-fn read_library(app: &AppHandle) -> Result<Vec<GameEntry>> {
-    let path = resolve_library_path(app)?;
+/// Opens `library.db`, importing `library.json` the first time. The JSON
+/// file is kept, renamed, as a backup of the library before the move. A
+/// database that cannot be read is replaced, see [`recover_database`].
+fn open_library(app: &AppHandle) -> Result<LoadedLibrary> {
+    let data_dir = resolve_data_dir(app)?;
+    let db_path = data_dir.join(LIBRARY_DB);
+    let legacy_path = resolve_library_path(app)?;
+    let opened = open_store(&db_path, || read_legacy_library(app, &legacy_path));
+    let (store, games) = match opened {
+        Ok((store, imported, games)) => {
+            if imported && legacy_path.exists() {
+                let _ = fs::rename(&legacy_path, data_dir.join(MIGRATED_LIBRARY_FILE));
+            }
+            (store, games)
+        }
+        Err(error) if is_damaged_database(&error) => {
+            recover_database(app, &data_dir, &db_path, &error)?
+        }
+        Err(error) => return Err(error),
+    };
+    let (trash, games) = games
+        .into_iter()
+        .partition(|game| game.deleted_at.is_some());
+    let tag_policy = store
+        .setting(TAG_POLICY_SETTING)?
+        .and_then(|name| TagPolicy::from_name(&name))
+        .unwrap_or_default();
+    Ok(LoadedLibrary {
+        store,
+        games,
+        trash,
+        tag_policy,
+    })
+}
+
+fn open_store(
+    path: &Path,
+    legacy: impl FnOnce() -> Result<Option<Vec<GameEntry>>>,
+) -> Result<(LibraryStore, bool, Vec<GameEntry>)> {
+    let (store, imported) = LibraryStore::open(path, legacy)?;
+    let games = store.load()?;
+    Ok((store, imported, games))
+}
+
+/// Whether opening the library failed in SQLite itself rather than, say,
+/// while importing `library.json`. A database from a newer build is not
+/// damaged, just out of reach.
+fn is_damaged_database(error: &anyhow::Error) -> bool {
+    !error.is::<NewerSchemaError>() && error.chain().any(|cause| cause.is::<rusqlite::Error>())
+}
+
+/// Moves an unreadable `library.db` aside and starts a new one from the
+/// newest JSON backup that still parses, so a damaged database does not
+/// lock the user out. The damaged copy is kept for manual recovery.
+fn recover_database(
+    app: &AppHandle,
+    data_dir: &Path,
+    path: &Path,
+    error: &anyhow::Error,
+) -> Result<(LibraryStore, Vec<GameEntry>)> {
+    let suffix = format!(".corrupt-{}", Utc::now().format("%Y%m%d-%H%M%S"));
+    let corrupt_path = with_file_suffix(path, &suffix);
+    fs::rename(path, &corrupt_path).with_context(|| {
+        format!("Library is unreadable ({error:#}) and could not be moved aside")
+    })?;
+    // SQLite would roll a leftover journal back into the new database.
+    let journal = with_file_suffix(path, "-journal");
+    if journal.exists() {
+        let _ = fs::rename(&journal, with_file_suffix(&corrupt_path, "-journal"));
+    }
+
+    let (store, _, games) = open_store(path, || Ok(newest_backup(data_dir)))?;
+    let _ = app.emit_all(
+        "library-recovered",
+        LibraryRecoveredEvent {
+            corrupt_path: corrupt_path.to_string_lossy().to_string(),
+            error: format!("{error:#}"),
+            recovered: games.len(),
+            skipped: None,
+        },
+    );
+    Ok((store, games))
+}
+
+/// Games of the newest library backup that can be read.
+fn newest_backup(dir: &Path) -> Option<Vec<GameEntry>> {
+    (1..=LIBRARY_BACKUPS).find_map(|index| {
+        let content = fs::read_to_string(library_backup_path(dir, index)).ok()?;
+        library_format::parse(&content)
+            .ok()
+            .map(|document| document.games)
+    })
+}
 
+fn read_legacy_library(app: &AppHandle, path: &Path) -> Result<Option<Vec<GameEntry>>> {
     if !path.exists() {
-        return Ok(Vec::new());
+        return Ok(None);
     }
 
     let content = fs::read_to_string(path)?;
     if content.trim().is_empty() {
-        return Ok(Vec::new());
+        return Ok(Some(Vec::new()));
     }
 
-    let games: Vec<GameEntry> = serde_json::from_str(&content)?;
+    match library_format::parse(&content) {
+        Ok(document) => Ok(Some(document.games)),
+        // Not damaged, just out of reach of this build: leave it alone.
+        Err(error) if error.is::<NewerFormatError>() => Err(error),
+        Err(error) => recover_library(app, path, &content, &error).map(Some),
+    }
+}
+
+/// Moves an unreadable `library.json` aside and salvages whichever entries
+/// still parse, so one bad write does not lock the user out.
+/// The corrupt copy is kept for manual recovery and never deleted.
+fn recover_library(
+    app: &AppHandle,
+    path: &Path,
+    content: &str,
+    error: &anyhow::Error,
+) -> Result<Vec<GameEntry>> {
+    let (games, skipped) = match library_format::salvage(content) {
+        Some((games, skipped)) => (games, Some(skipped)),
+        None => (Vec::new(), None),
+    };
+
+    let corrupt_path = with_file_suffix(
+        path,
+        &format!(".corrupt-{}", Utc::now().format("%Y%m%d-%H%M%S")),
+    );
+    fs::rename(path, &corrupt_path).with_context(|| {
+        format!("Library is unreadable ({error}) and could not be moved aside")
+    })?;
+
+    let _ = app.emit_all(
+        "library-recovered",
+        LibraryRecoveredEvent {
+            corrupt_path: corrupt_path.to_string_lossy().to_string(),
+            error: error.to_string(),
+            recovered: games.len(),
+            skipped,
+        },
+    );
     Ok(games)
 }
 
-fn write_library(app: &AppHandle, games: &[GameEntry]) -> Result<()> {
-    let path = resolve_library_path(app)?;
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent)?;
+fn with_file_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(suffix);
+    PathBuf::from(name)
+}
+
+/// Stores `games` as the newest JSON backup of the library.
+fn backup_library(app: &AppHandle, games: &[GameEntry]) -> Result<()> {
+    let payload = LibraryDocument::new(games.to_vec()).to_json()?;
+    rotate_library_backups(&resolve_data_dir(app)?, payload.as_bytes())
+}
+
+fn library_backup_path(dir: &Path, index: usize) -> PathBuf {
+    dir.join(format!("library.backup.{index}.json"))
+}
+
+/// Shifts every backup one slot down, dropping the oldest, and stores
+/// `content` as the newest.
+fn rotate_library_backups(dir: &Path, content: &[u8]) -> Result<()> {
+    let _ = fs::remove_file(library_backup_path(dir, LIBRARY_BACKUPS));
+    for index in (1..LIBRARY_BACKUPS).rev() {
+        let from = library_backup_path(dir, index);
+        if from.exists() {
+            fs::rename(&from, library_backup_path(dir, index + 1))?;
+        }
     }
-    let payload = serde_json::to_string_pretty(games)?;
-    fs::write(path, payload)?;
-    Ok(())
+    write_atomic(&library_backup_path(dir, 1), content)
 }
 
-fn resolve_library_path(app: &AppHandle) -> Result<PathBuf> {
-    let resolver = app.path_resolver();
-    let base = resolver
-        .app_config_dir()
-        .or_else(|| resolver.app_data_dir())
-        .context("Unable to resolve application data folder")?;
-    fs::create_dir_all(&base)?;
-    Ok(base.join(LIBRARY_FILE))
+/// Replaces `path` with `contents` so that a crash or a full disk leaves
+/// either the old or the new file, never a truncated one: the data goes to a
+/// temporary file next to it, is flushed to disk, then renamed over it.
+fn write_atomic(path: &Path, contents: &[u8]) -> Result<()> {
+    write_atomic_with(path, |file| file.write_all(contents))
 }
 
-fn compute_path_size(path: &Path) -> Result<u64> {
-    if path.is_file() {
-        let metadata = fs::metadata(path)?;
-        return Ok(metadata.len());
+/// [`write_atomic`] with the temporary file filled in by `write`.
+fn write_atomic_with(
+    path: &Path,
+    write: impl FnOnce(&mut fs::File) -> std::io::Result<()>,
+) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .context("Cannot write to a path without a file name")?
+        .to_string_lossy();
+    let temp_path = path.with_file_name(format!(".{file_name}.{}.tmp", Uuid::new_v4()));
+
+    let written = fs::File::create(&temp_path).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+    let result = written.and_then(|_| replace_file(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
     }
+    result.with_context(|| format!("Failed to write {}", path.display()))
+}
 
-    if path.is_dir() {
-        let mut total: u64 = 0;
-        for entry in WalkDir::new(path).follow_links(true) {
-            let entry = entry?;
-            if entry.file_type().is_file() {
-                total += entry.metadata()?.len();
+/// `fs::rename` already replaces an existing target on Windows, but fails
+/// while another process (typically a virus scanner) briefly holds the old
+/// file open, so the rename is retried for a moment there.
+#[cfg(windows)]
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    let mut attempts = 0;
+    loop {
+        match fs::rename(from, to) {
+            Err(error) if error.kind() == std::io::ErrorKind::PermissionDenied && attempts < 10 => {
+                attempts += 1;
+                std::thread::sleep(std::time::Duration::from_millis(50));
             }
+            result => return result,
         }
-        return Ok(total);
     }
+}
 
-    Err(anyhow!("Unsupported path type"))
+#[cfg(not(windows))]
+fn replace_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    fs::rename(from, to)
 }
 
-fn normalize_tags(tags: Vec<String>) -> Vec<String> {
-    let mut parsed: Vec<String> = Vec::new();
+fn emit_library_updated(app: &AppHandle, game_ids: Vec<String>) {
+    let _ = app.emit_all("library-updated", LibraryUpdatedEvent { game_ids });
+}
 
-    for tag in tags {
-        for value in tag.split(',') {
-            let trimmed = value.trim();
-            if !trimmed.is_empty() {
-                parsed.push(trimmed.to_string());
-            }
+fn game_exists(app: &AppHandle, id: &str) -> Result<bool> {
+    Ok(app
+        .state::<Library>()
+        .games(app)?
+        .iter()
+        .any(|game| game.id == id))
+}
+
+/// Flags a game as `Downloading` while `download_id` runs, remembering the
+/// status to fall back to. Returns `false` when the game does not exist.
+fn mark_game_downloading(app: &AppHandle, id: &str, download_id: &str) -> Result<bool> {
+    let found = app.state::<Library>().update(app, |games| {
+        let Some(entry) = games.iter_mut().find(|game| game.id == id) else {
+            return Ok(false);
+        };
+
+        // A game re-queued while already downloading keeps its original fallback.
+        if entry.download_id.is_none() {
+            entry.status_before_download = Some(entry.status.clone());
         }
+        entry.download_id = Some(download_id.to_string());
+        entry.status = InstallStatus::Downloading;
+        entry.updated_at = Utc::now();
+        Ok(true)
+    })?;
+
+    if found {
+        emit_library_updated(app, vec![id.to_string()]);
     }
+    Ok(found)
+}
 
-    parsed.sort();
-    parsed.dedup();
-    parsed
+/// Points a game's archive at a finished download and refreshes its size.
+/// Returns `None` when the game was removed in the meantime.
+fn link_download_to_game(
+    app: &AppHandle,
+    id: &str,
+    download_id: &str,
+    destination: &Path,
+) -> Result<Option<GameEntry>> {
+    let size = compute_path_size(destination).ok();
+    let updated = app.state::<Library>().update(app, |games| {
+        let Some(entry) = games.iter_mut().find(|game| game.id == id) else {
+            return Ok(None);
+        };
+
+        entry.archive_path = Some(destination.to_string_lossy().to_string());
+        if size.is_some() {
+            entry.size_bytes = size;
+        }
+        let previous = if entry.download_id.as_deref() == Some(download_id) {
+            entry.download_id = None;
+            entry.status_before_download.take()
+        } else {
+            None
+        };
+        if matches!(
+            entry.status,
+            InstallStatus::NotInstalled | InstallStatus::Downloading
+        ) {
+            // An installed game that fetched a new archive is still installed.
+            entry.status = match previous {
+                Some(InstallStatus::Installed) => InstallStatus::Installed,
+                _ => InstallStatus::Archived,
+            };
+        }
+        entry.updated_at = Utc::now();
+        Ok(Some(entry.clone()))
+    })?;
+
+    if let Some(updated) = &updated {
+        emit_library_updated(app, vec![updated.id.clone()]);
+    }
+    Ok(updated)
+}
+
+/// Puts back the status a game had before `download_id` started, unless
+/// another download has taken over the game since.
+fn revert_game_download(app: &AppHandle, id: &str, download_id: &str) -> Result<()> {
+    let reverted = app.state::<Library>().update(app, |games| {
+        let Some(entry) = games
+            .iter_mut()
+            .find(|game| game.id == id && game.download_id.as_deref() == Some(download_id))
+        else {
+            return Ok(false);
+        };
+
+        entry.download_id = None;
+        if entry.status == InstallStatus::Downloading {
+            entry.status = entry.status_before_download.take().unwrap_or_default();
+        }
+        entry.status_before_download = None;
+        entry.updated_at = Utc::now();
+        Ok(true)
+    })?;
+
+    if reverted {
+        emit_library_updated(app, vec![id.to_string()]);
+    }
+    Ok(())
+}
+
+fn resolve_library_path(app: &AppHandle) -> Result<PathBuf> {
+    Ok(resolve_data_dir(app)?.join(LIBRARY_FILE))
+}
+
+/// Folder holding the library and every other file the launcher persists.
+fn resolve_data_dir(app: &AppHandle) -> Result<PathBuf> {
+    data_dir::resolve(app)
+}
+
+/// Size of the file or folder at `path`, without following links inside it.
+fn compute_path_size(path: &Path) -> Result<u64> {
+    sizes::path_size(path, false)
 }
 
 pub fn run() {
+    let mut args = match cli::parse(std::env::args().skip(1)) {
+        Ok(args) => args,
+        Err(error) => {
+            eprintln!("{error}\n\n{}", cli::USAGE);
+            // Release builds carry on, since the system may add arguments of its own.
+            if cfg!(debug_assertions) {
+                std::process::exit(2);
+            }
+            cli::Args::default()
+        }
+    };
+    if args.help {
+        println!("{}", cli::USAGE);
+        return;
+    }
+    deep_link::prepare(args.link.as_deref());
+    if let Some(dir) = args.data_dir.take() {
+        data_dir::set_argument(dir);
+    }
+
     tauri::Builder::default()
         .invoke_handler(tauri::generate_handler![
             load_library,
             add_game,
+            add_games,
             update_game,
+            game_patch::patch_game,
             remove_game,
+            remove_games,
+            merge_games,
+            set_games_status,
+            toggle_favorite,
+            record_launch,
+            launcher::launch_game,
+            launcher::is_game_running,
+            launcher::stop_game,
+            launcher::get_play_sessions,
+            launcher::set_follow_game_processes,
+            artwork::set_game_artwork,
+            artwork::get_artwork_path,
+            metadata::fetch_metadata,
+            metadata::apply_metadata,
+            metadata::metadata_available,
+            metadata::set_metadata_credentials,
+            get_game,
+            get_games,
+            list_custom_field_keys,
             open_path,
+            terminal::open_terminal,
+            uninstall::uninstall_game,
             scan_path_size,
-            queue_download
+            list_library_backups,
+            restore_library_backup,
+            library_io::export_library,
+            library_io::import_library,
+            search::search_games,
+            search::list_values,
+            shortcuts::create_shortcut,
+            repackers::repacker_stats,
+            repackers::list_repackers,
+            stats::library_stats,
+            steam::import_from_steam,
+            stores::import_from_gog,
+            stores::import_from_epic,
+            validation::validate_library,
+            sizes::refresh_sizes,
+            sizes::cancel_refresh,
+            sizes::start_size_scan,
+            sizes::scan_paths_size,
+            sizes::cancel_scan,
+            disk_usage::analyze_path,
+            size_cache::clear_size_cache,
+            relocation::relocate_installs,
+            relocation::apply_relocations,
+            relocation::move_install,
+            archives::manage_archive,
+            extract::extract_archive,
+            extract::cancel_extraction,
+            extract::extraction_support,
+            scan::scan_for_games,
+            executables::list_executables,
+            icons::extract_icon,
+            icons::extract_all_icons,
+            trash::list_trash,
+            trash::restore_game,
+            trash::purge_trash,
+            tags::list_tags,
+            tags::rename_tag,
+            tags::delete_tag,
+            tags::get_tag_policy,
+            tags::set_tag_policy,
+            tags::apply_tag_policy,
+            collections::list_collections,
+            collections::create_collection,
+            collections::rename_collection,
+            collections::delete_collection,
+            collections::add_to_collection,
+            collections::remove_from_collection,
+            disk::get_free_space,
+            disk::list_volumes,
+            data_dir::move_data_directory,
+            downloads::queue_download,
+            downloads::probe_download,
+            downloads::list_downloads,
+            downloads::set_download_concurrency,
+            downloads::set_download_speed_limit,
+            downloads::set_allow_invalid_certs,
+            downloads::set_post_download_hook,
+            downloads::cancel_download,
+            downloads::pause_download,
+            downloads::resume_download,
+            downloads::mark_downloading,
+            history::get_download_history,
+            history::clear_download_history,
+            notifications::set_download_notifications,
+            settings::get_settings,
+            settings::update_settings,
+            tray::set_close_to_tray
         ])
-        .setup(|app| {
+        .manage(DownloadManager::default())
+        .manage(DownloadHistory::default())
+        .manage(Library::default())
+        .manage(Launcher::default())
+        .manage(MetadataService::default())
+        .manage(Collections::default())
+        .manage(Tray::default())
+        .manage(SettingsStore::default())
+        .manage(SizeJobs::default())
+        .manage(SizeCache::default())
+        .manage(Extractions::default())
+        .on_window_event(tray::on_window_event)
+        .on_system_tray_event(tray::on_tray_event)
+        .setup(move |app| {
             // ensure data directory exists on start
             let _ = resolve_library_path(&app.handle());
+            // A library that fails to load here is retried, and reported, by the first command.
+            let _ = app.state::<Library>().load(&app.handle());
+            let _ = trash::purge_expired(&app.handle(), &app.state::<Library>());
+            app.state::<SettingsStore>().load(&app.handle());
+            app.state::<DownloadManager>().restore(&app.handle());
+            let settings = app.state::<SettingsStore>().get();
+            app.state::<DownloadManager>().apply_settings(&app.handle(), &settings);
+            app.state::<MetadataService>().restore(&app.handle());
+            if let Ok(dir) = resolve_data_dir(&app.handle()) {
+                // Artwork is only served from the folders allowed in tauri.conf.json,
+                // which do not cover a portable or moved data folder.
+                let _ = app
+                    .asset_protocol_scope()
+                    .allow_directory(dir.join("artwork"), true);
+            }
+            tray::setup(&app.handle());
+            if args.minimized {
+                tray::start_minimized(&app.handle());
+            }
+            deep_link::register(&app.handle(), args.link.take());
             Ok(())
         })
         .run(tauri::generate_context!())
@@ -486,3 +1705,105 @@ pub fn run() {
 fn main() {
     run();
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    #[test]
+    fn interrupted_write_leaves_the_original_intact() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.json");
+        let original = br#"{"version":2,"games":[]}"#;
+        fs::write(&path, original).unwrap();
+
+        let result = write_atomic_with(&path, |file| {
+            file.write_all(b"{\"version\":2,\"ga")?;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::StorageFull,
+                "disk full",
+            ))
+        });
+
+        assert!(result.is_err());
+        assert_eq!(fs::read(&path).unwrap(), original);
+        let leftovers: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .filter(|name| name != "library.json")
+            .collect();
+        assert!(leftovers.is_empty(), "temporary files left: {leftovers:?}");
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("library.json");
+        fs::write(&path, "old").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "new");
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 1);
+    }
+
+    fn titled(title: &str) -> GamePayload {
+        GamePayload {
+            title: title.into(),
+            ..GamePayload::default()
+        }
+    }
+
+    #[test]
+    fn add_games_names_the_invalid_payload_and_adds_nothing() {
+        let payloads = vec![
+            titled("Hollow Depths"),
+            titled("Starfall"),
+            GamePayload {
+                rating: Some(i64::from(MAX_RATING) + 1),
+                ..titled("Brine")
+            },
+        ];
+        let mut rules = TagRules::new(TagPolicy::default(), &[]);
+
+        let error = games_from_payloads(payloads, &mut rules, Utc::now()).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::InvalidInput));
+        assert!(error.message.starts_with("Game 2: "), "{}", error.message);
+    }
+
+    #[test]
+    fn add_games_names_blank_titles_like_add_game() {
+        let now = Utc::now();
+        let mut rules = TagRules::new(TagPolicy::default(), &[]);
+
+        let games =
+            games_from_payloads(vec![titled("  "), titled("Starfall")], &mut rules, now).unwrap();
+        assert_eq!(games[0].title, "Untitled");
+        assert_eq!(games[1].title, "Starfall");
+        assert_ne!(games[0].id, games[1].id);
+        assert!(games.iter().all(|game| game.added_at == now));
+    }
+
+    #[test]
+    fn merging_keeps_play_history_and_favorites() {
+        let mut rules = TagRules::new(TagPolicy::default(), &[]);
+        let mut primary = game_from_payload(titled("Starfall"), None, &mut rules);
+        primary.play_count = 2;
+        primary.total_playtime_seconds = 3600;
+        primary.last_played_at = Some(primary.added_at);
+        let mut secondary = game_from_payload(titled("Starfall (GOG)"), None, &mut rules);
+        secondary.favorite = true;
+        secondary.play_count = 3;
+        secondary.total_playtime_seconds = 600;
+        let later = primary.added_at + chrono::Duration::days(1);
+        secondary.last_played_at = Some(later);
+
+        merge_into(&mut primary, secondary, &mut rules);
+
+        assert!(primary.favorite);
+        assert_eq!(primary.play_count, 5);
+        assert_eq!(primary.total_playtime_seconds, 4200);
+        assert_eq!(primary.last_played_at, Some(later));
+    }
+}
@@ -0,0 +1,408 @@
+use crate::error::{CommandError, ErrorCode};
+use crate::{artwork, GameEntry, Library};
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Utc};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Client, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, State};
+
+/// Where the IGDB credentials are kept between sessions.
+const METADATA_FILE: &str = "metadata.json";
+const TOKEN_URL: &str = "https://id.twitch.tv/oauth2/token";
+const SEARCH_URL: &str = "https://api.igdb.com/v4/games";
+/// `t_cover_big` is 264x374, enough for a grid card.
+const COVER_URL: &str = "https://images.igdb.com/igdb/image/upload/t_cover_big";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(15);
+/// IGDB allows four requests per second; staying under it avoids most 429s.
+const MIN_REQUEST_INTERVAL: Duration = Duration::from_millis(300);
+const SEARCH_FIELDS: &str = "name,first_release_date,summary,cover.image_id,\
+    involved_companies.company.name,involved_companies.developer,involved_companies.publisher";
+/// Candidates returned by one search.
+const SEARCH_LIMIT: usize = 10;
+/// Tokens are renewed this long before IGDB says they expire.
+const TOKEN_MARGIN: Duration = Duration::from_secs(60);
+
+/// Why a metadata lookup failed, in a shape the frontend can branch on.
+#[derive(Debug, Serialize, thiserror::Error)]
+#[serde(tag = "kind", rename_all = "kebab-case")]
+pub enum MetadataError {
+    #[error("No IGDB credentials are configured")]
+    NotConfigured,
+    #[error("IGDB rejected the configured credentials")]
+    Unauthorized,
+    #[error("Too many requests to IGDB, try again later")]
+    #[serde(rename_all = "camelCase")]
+    RateLimited { retry_after_seconds: Option<u64> },
+    #[error("IGDB returned {status}: {message}")]
+    Api { status: u16, message: String },
+    #[error("{message}")]
+    Failed { message: String },
+}
+
+impl From<anyhow::Error> for MetadataError {
+    fn from(error: anyhow::Error) -> Self {
+        Self::Failed {
+            message: error.to_string(),
+        }
+    }
+}
+
+/// Keeps the variant in `details`, where the frontend finds
+/// `retryAfterSeconds`.
+impl From<MetadataError> for CommandError {
+    fn from(error: MetadataError) -> Self {
+        let code = match error {
+            MetadataError::NotConfigured => ErrorCode::NotConfigured,
+            MetadataError::Unauthorized => ErrorCode::PermissionDenied,
+            MetadataError::RateLimited { .. } => ErrorCode::RateLimited,
+            MetadataError::Api { .. } => ErrorCode::Network,
+            MetadataError::Failed { .. } => ErrorCode::Internal,
+        };
+        let details = serde_json::to_value(&error).ok();
+        Self {
+            code,
+            message: error.to_string(),
+            details,
+        }
+    }
+}
+
+impl From<reqwest::Error> for MetadataError {
+    fn from(error: reqwest::Error) -> Self {
+        Self::Failed {
+            message: format!("Failed to reach IGDB: {error}"),
+        }
+    }
+}
+
+/// A possible match for a game, as found by `fetch_metadata`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetadataCandidate {
+    pub source_id: u64,
+    pub title: String,
+    pub year: Option<i32>,
+    pub developer: Option<String>,
+    pub publisher: Option<String>,
+    pub cover_url: Option<String>,
+    pub description: Option<String>,
+}
+
+/// Twitch application credentials, which IGDB uses for its API keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Credentials {
+    client_id: String,
+    client_secret: String,
+}
+
+#[derive(Debug)]
+struct AccessToken {
+    value: String,
+    expires_at: Instant,
+}
+
+/// IGDB access for metadata lookups. The credentials are stored in
+/// `metadata.json`; the access token only lives in memory.
+#[derive(Default)]
+pub struct MetadataService {
+    credentials: Mutex<Option<Credentials>>,
+    token: tokio::sync::Mutex<Option<AccessToken>>,
+    last_request: tokio::sync::Mutex<Option<Instant>>,
+}
+
+impl MetadataService {
+    /// Loads the credentials saved by a previous session.
+    pub fn restore(&self, app: &AppHandle) {
+        let stored = metadata_path(app)
+            .and_then(|path| Ok(fs::read_to_string(path)?))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok());
+        *self
+            .credentials
+            .lock()
+            .expect("metadata credentials poisoned") = stored;
+    }
+
+    fn credentials(&self) -> Option<Credentials> {
+        self.credentials
+            .lock()
+            .expect("metadata credentials poisoned")
+            .clone()
+    }
+
+    /// Waits until enough time has passed since the previous request.
+    async fn throttle(&self) {
+        let mut last = self.last_request.lock().await;
+        if let Some(wait) = last.map(|last| MIN_REQUEST_INTERVAL.saturating_sub(last.elapsed())) {
+            tokio::time::sleep(wait).await;
+        }
+        *last = Some(Instant::now());
+    }
+
+    async fn access_token(
+        &self,
+        client: &Client,
+        credentials: &Credentials,
+    ) -> Result<String, MetadataError> {
+        let mut token = self.token.lock().await;
+        if let Some(token) = token
+            .as_ref()
+            .filter(|token| token.expires_at > Instant::now())
+        {
+            return Ok(token.value.clone());
+        }
+
+        #[derive(Deserialize)]
+        struct TokenResponse {
+            access_token: String,
+            expires_in: u64,
+        }
+
+        self.throttle().await;
+        let response = client
+            .post(TOKEN_URL)
+            .query(&[
+                ("client_id", credentials.client_id.as_str()),
+                ("client_secret", credentials.client_secret.as_str()),
+                ("grant_type", "client_credentials"),
+            ])
+            .send()
+            .await?;
+        let issued: TokenResponse = check_status(response).await?.json().await?;
+        let lifetime = Duration::from_secs(issued.expires_in).saturating_sub(TOKEN_MARGIN);
+        *token = Some(AccessToken {
+            value: issued.access_token.clone(),
+            expires_at: Instant::now() + lifetime,
+        });
+        Ok(issued.access_token)
+    }
+
+    async fn search(&self, query: &str) -> Result<Vec<MetadataCandidate>, MetadataError> {
+        let credentials = self.credentials().ok_or(MetadataError::NotConfigured)?;
+        let client = Client::builder().timeout(REQUEST_TIMEOUT).build()?;
+        let token = self.access_token(&client, &credentials).await?;
+
+        #[derive(Deserialize)]
+        struct Cover {
+            image_id: String,
+        }
+        #[derive(Deserialize)]
+        struct Company {
+            name: String,
+        }
+        #[derive(Deserialize)]
+        struct InvolvedCompany {
+            company: Company,
+            #[serde(default)]
+            developer: bool,
+            #[serde(default)]
+            publisher: bool,
+        }
+        #[derive(Deserialize)]
+        struct Game {
+            id: u64,
+            name: String,
+            first_release_date: Option<i64>,
+            summary: Option<String>,
+            cover: Option<Cover>,
+            #[serde(default)]
+            involved_companies: Vec<InvolvedCompany>,
+        }
+        // The first company credited with a role, as IGDB lists the lead one first.
+        let credited = |companies: &[InvolvedCompany], role: fn(&InvolvedCompany) -> bool| {
+            companies
+                .iter()
+                .find(|company| role(company))
+                .map(|company| company.company.name.clone())
+        };
+
+        self.throttle().await;
+        let response = client
+            .post(SEARCH_URL)
+            .header("Client-ID", &credentials.client_id)
+            .bearer_auth(token)
+            .body(format!(
+                "search \"{}\"; fields {SEARCH_FIELDS}; limit {SEARCH_LIMIT};",
+                query.replace('\\', "\\\\").replace('"', "\\\"")
+            ))
+            .send()
+            .await?;
+        let response = match check_status(response).await {
+            Err(MetadataError::Unauthorized) => {
+                // The token may have been revoked early; the next search fetches a new one.
+                *self.token.lock().await = None;
+                return Err(MetadataError::Unauthorized);
+            }
+            response => response?,
+        };
+        let games: Vec<Game> = response.json().await?;
+
+        Ok(games
+            .into_iter()
+            .map(|game| MetadataCandidate {
+                source_id: game.id,
+                title: game.name,
+                year: game
+                    .first_release_date
+                    .and_then(|timestamp| DateTime::<Utc>::from_timestamp(timestamp, 0))
+                    .map(|released| released.year()),
+                developer: credited(&game.involved_companies, |company| company.developer),
+                publisher: credited(&game.involved_companies, |company| company.publisher),
+                cover_url: game
+                    .cover
+                    .map(|cover| format!("{COVER_URL}/{}.jpg", cover.image_id)),
+                description: game.summary.filter(|summary| !summary.trim().is_empty()),
+            })
+            .collect())
+    }
+}
+
+/// Searches IGDB for games matching `query`, best match first.
+#[tauri::command]
+pub async fn fetch_metadata(
+    service: State<'_, MetadataService>,
+    query: String,
+) -> Result<Vec<MetadataCandidate>, CommandError> {
+    let query = query.trim();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+    Ok(service.search(query).await?)
+}
+
+/// Fills the fields of a game that are still empty from `candidate`: the
+/// release year, developer, publisher, description and cover. Anything
+/// already set is kept.
+#[tauri::command]
+pub async fn apply_metadata(
+    app: AppHandle,
+    library: State<'_, Library>,
+    game_id: String,
+    candidate: MetadataCandidate,
+) -> Result<GameEntry, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == game_id)
+        .ok_or_else(|| CommandError::game_not_found(&game_id))?;
+
+    // The cover goes first, so a failed download leaves the entry untouched.
+    if let (None, Some(url)) = (&game.cover_path, &candidate.cover_url) {
+        artwork::set_artwork(&app, &library, &game_id, artwork::ArtworkKind::Cover, url).await?;
+    }
+
+    let updated = library.update(&app, |games| {
+        let game = games
+            .iter_mut()
+            .find(|game| game.id == game_id)
+            .ok_or_else(|| CommandError::game_not_found(&game_id))?;
+        let mut changed = false;
+        if game.release_year.is_none() {
+            // Years IGDB has but the library does not accept are left out.
+            game.release_year = candidate
+                .year
+                .and_then(|year| crate::checked_release_year(Some(year.into())).ok())
+                .flatten();
+            changed |= game.release_year.is_some();
+        }
+        for (field, value) in [
+            (&mut game.developer, &candidate.developer),
+            (&mut game.publisher, &candidate.publisher),
+            (&mut game.description, &candidate.description),
+        ] {
+            if field.is_none() && value.is_some() {
+                *field = value.clone();
+                changed = true;
+            }
+        }
+        if changed {
+            game.updated_at = Utc::now();
+        }
+        Ok(game.clone())
+    })?;
+    crate::emit_library_updated(&app, vec![updated.id.clone()]);
+    Ok(updated)
+}
+
+/// Whether IGDB credentials are configured. The metadata search is hidden
+/// without them.
+#[tauri::command]
+pub fn metadata_available(service: State<'_, MetadataService>) -> bool {
+    service.credentials().is_some()
+}
+
+/// Stores the Twitch client id and secret used for IGDB. Clearing either one
+/// turns the metadata search off.
+#[tauri::command]
+pub async fn set_metadata_credentials(
+    app: AppHandle,
+    service: State<'_, MetadataService>,
+    client_id: Option<String>,
+    client_secret: Option<String>,
+) -> Result<(), CommandError> {
+    let credentials = match (client_id, client_secret) {
+        (Some(client_id), Some(client_secret))
+            if !client_id.trim().is_empty() && !client_secret.trim().is_empty() =>
+        {
+            Some(Credentials {
+                client_id: client_id.trim().to_string(),
+                client_secret: client_secret.trim().to_string(),
+            })
+        }
+        _ => None,
+    };
+
+    let path = metadata_path(&app)?;
+    match &credentials {
+        Some(credentials) => {
+            let content =
+                serde_json::to_string_pretty(credentials).context("Failed to save credentials")?;
+            crate::write_atomic(&path, content.as_bytes())?;
+        }
+        None if path.exists() => fs::remove_file(&path).context("Failed to remove credentials")?,
+        None => {}
+    }
+
+    *service
+        .credentials
+        .lock()
+        .expect("metadata credentials poisoned") = credentials;
+    *service.token.lock().await = None;
+    Ok(())
+}
+
+fn metadata_path(app: &AppHandle) -> anyhow::Result<PathBuf> {
+    Ok(crate::resolve_data_dir(app)?.join(METADATA_FILE))
+}
+
+/// Maps IGDB's error statuses onto `MetadataError`.
+async fn check_status(response: Response) -> Result<Response, MetadataError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(MetadataError::Unauthorized),
+        StatusCode::BAD_REQUEST if response.url().as_str().starts_with(TOKEN_URL) => {
+            Err(MetadataError::Unauthorized)
+        }
+        StatusCode::TOO_MANY_REQUESTS => Err(MetadataError::RateLimited {
+            retry_after_seconds: response
+                .headers()
+                .get(RETRY_AFTER)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.trim().parse().ok()),
+        }),
+        status => Err(MetadataError::Api {
+            status: status.as_u16(),
+            message: response.text().await.unwrap_or_default(),
+        }),
+    }
+}
@@ -0,0 +1,71 @@
+use crate::error::CommandError;
+use crate::settings::{SettingsPatch, SettingsStore};
+use std::time::Duration;
+use tauri::{AppHandle, Manager, State};
+
+/// Downloads that took less time than this are not announced, so small
+/// fetches do not flood the desktop.
+const MIN_ACTIVE_TIME: Duration = Duration::from_secs(5);
+
+/// Turns desktop notifications for finished and failed downloads on or off.
+/// On by default.
+#[tauri::command]
+pub fn set_download_notifications(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    let patch = SettingsPatch {
+        download_notifications: Some(enabled),
+        ..SettingsPatch::default()
+    };
+    settings.update(&app, patch).map(drop)
+}
+
+/// Announces the end of a download that spent at least `MIN_ACTIVE_TIME`
+/// transferring. Where the platform reports clicks, clicking the
+/// notification brings the window up on the downloads view.
+pub fn download_finished(app: &AppHandle, title: &str, body: &str, active_time: Duration) {
+    if !app.state::<SettingsStore>().get().download_notifications || active_time < MIN_ACTIVE_TIME {
+        return;
+    }
+    show(app, title, body);
+}
+
+/// Tauri's notifications cannot report clicks, so on Linux the desktop's
+/// notification service is used directly.
+#[cfg(target_os = "linux")]
+fn show(app: &AppHandle, title: &str, body: &str) {
+    let shown = notify_rust::Notification::new()
+        .appname("RoseLauncher")
+        .summary(title)
+        .body(body)
+        .action("default", "Show downloads")
+        .show();
+    let Ok(handle) = shown else {
+        return;
+    };
+    let app = app.clone();
+    // Waiting blocks until the notification is clicked or dismissed.
+    std::thread::spawn(move || {
+        handle.wait_for_action(|action| {
+            if action == "default" {
+                show_downloads(&app);
+            }
+        })
+    });
+}
+
+#[cfg(not(target_os = "linux"))]
+fn show(app: &AppHandle, title: &str, body: &str) {
+    let _ = tauri::api::notification::Notification::new(&app.config().tauri.bundle.identifier)
+        .title(title)
+        .body(body)
+        .show();
+}
+
+#[cfg(target_os = "linux")]
+fn show_downloads(app: &AppHandle) {
+    crate::tray::show_window(app);
+    let _ = app.emit_all("show-downloads", ());
+}
@@ -0,0 +1,156 @@
+use crate::error::{CommandError, ErrorCode};
+use crate::settings::SettingsStore;
+use crate::Library;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager};
+
+/// Checks that `path` exists and may be handed to the system. Allowed are
+/// the install, archive and executable paths of library entries and anything
+/// inside them, the download folders and the data folder, unless the
+/// `allowArbitraryPaths` setting lifts the check.
+/// Both sides are canonicalized first, so `..` segments and symlinks cannot
+/// lead elsewhere.
+pub fn check(app: &AppHandle, path: &str) -> Result<(), CommandError> {
+    let settings = app.state::<SettingsStore>().get();
+    if settings.allow_arbitrary_paths {
+        return resolve(path).map(drop);
+    }
+    check_within(path, &allowed_roots(app)?)
+}
+
+fn resolve(path: &str) -> Result<PathBuf, CommandError> {
+    Path::new(path)
+        .canonicalize()
+        .map_err(|_| CommandError::not_found(format!("Path does not exist: {path}")))
+}
+
+/// Checks that `path` exists and lies in one of `roots`.
+fn check_within(path: &str, roots: &[PathBuf]) -> Result<(), CommandError> {
+    let resolved = resolve(path)?;
+    let allowed = roots
+        .iter()
+        .filter_map(|root| root.canonicalize().ok())
+        .any(|root| resolved.starts_with(root));
+    if allowed {
+        Ok(())
+    } else {
+        Err(CommandError::new(
+            ErrorCode::PermissionDenied,
+            format!("{path} is not part of the library, a download folder or the data folder"),
+        ))
+    }
+}
+
+fn allowed_roots(app: &AppHandle) -> Result<Vec<PathBuf>> {
+    let games = app
+        .state::<Library>()
+        .games(app)
+        .context("Failed to load library")?;
+    let settings = app.state::<SettingsStore>().get();
+    let mut roots: Vec<PathBuf> = games
+        .into_iter()
+        .flat_map(|game| [game.install_path, game.archive_path, game.executable_path])
+        .chain(std::iter::once(settings.default_download_dir))
+        .flatten()
+        .chain(settings.repacker_download_dirs.into_values())
+        .map(PathBuf::from)
+        .collect();
+    if let Ok(dir) = crate::resolve_data_dir(app) {
+        roots.push(dir);
+    }
+    Ok(roots)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    /// A folder with `library/game/game.exe` and `secret.txt` beside
+    /// `library`, and the game folder as the only root.
+    fn layout() -> (tempfile::TempDir, PathBuf, Vec<PathBuf>) {
+        let dir = tempfile::tempdir().unwrap();
+        let game = dir.path().join("library").join("game");
+        fs::create_dir_all(&game).unwrap();
+        fs::write(game.join("game.exe"), "").unwrap();
+        fs::write(dir.path().join("secret.txt"), "").unwrap();
+        (dir, game.clone(), vec![game])
+    }
+
+    fn denied(result: Result<(), CommandError>) -> bool {
+        matches!(result, Err(error) if matches!(error.code, ErrorCode::PermissionDenied))
+    }
+
+    #[test]
+    fn allows_paths_inside_a_root() {
+        let (_dir, game, roots) = layout();
+        assert!(check_within(game.to_str().unwrap(), &roots).is_ok());
+        let exe = game.join("game.exe");
+        assert!(check_within(exe.to_str().unwrap(), &roots).is_ok());
+    }
+
+    #[test]
+    fn refuses_parent_segments_out_of_a_root() {
+        let (_dir, game, roots) = layout();
+        let escape = game.join("..").join("..").join("secret.txt");
+        assert!(denied(check_within(escape.to_str().unwrap(), &roots)));
+    }
+
+    #[test]
+    fn refuses_missing_paths_as_not_found() {
+        let (_dir, game, roots) = layout();
+        let missing = game.join("missing.exe");
+        let error = check_within(missing.to_str().unwrap(), &roots).unwrap_err();
+        assert!(matches!(error.code, ErrorCode::NotFound));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn refuses_links_out_of_a_root() {
+        let (dir, game, roots) = layout();
+        let link = game.join("saves");
+        std::os::unix::fs::symlink(dir.path(), &link).unwrap();
+        let escape = link.join("secret.txt");
+        assert!(denied(check_within(escape.to_str().unwrap(), &roots)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follows_a_root_that_is_a_link() {
+        let (dir, game, _) = layout();
+        let link = dir.path().join("shortcut");
+        std::os::unix::fs::symlink(&game, &link).unwrap();
+        let exe = game.join("game.exe");
+        assert!(check_within(exe.to_str().unwrap(), &[link]).is_ok());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn allows_a_verbatim_spelling_of_a_root() {
+        let (_dir, game, roots) = layout();
+        let verbatim = format!(r"\\?\{}", game.join("game.exe").display());
+        assert!(check_within(&verbatim, &roots).is_ok());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn refuses_verbatim_paths_out_of_a_root() {
+        let (dir, _game, roots) = layout();
+        let verbatim = format!(r"\\?\{}", dir.path().join("secret.txt").display());
+        assert!(denied(check_within(&verbatim, &roots)));
+        // Verbatim paths take `..` literally, so it cannot climb out either.
+        let climb = format!(r"\\?\{}\..\..\secret.txt", roots[0].display());
+        assert!(check_within(&climb, &roots).is_err());
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn refuses_unc_paths_to_a_local_root() {
+        let (_dir, game, roots) = layout();
+        let exe = game.join("game.exe").display().to_string();
+        let (drive, rest) = exe.split_once(':').unwrap();
+        let unc = format!(r"\\localhost\{drive}${rest}");
+        assert!(check_within(&unc, &roots).is_err());
+    }
+}
@@ -0,0 +1,487 @@
+use crate::disk;
+use crate::error::CommandError;
+use crate::launcher::Launcher;
+use crate::{GameEntry, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Manager, State};
+use walkdir::WalkDir;
+
+/// `relocation-progress` is sent once per this many folders walked.
+const PROGRESS_INTERVAL: usize = 500;
+/// `move-progress` is sent at most this often while copying.
+const PROGRESS_EVERY: Duration = Duration::from_millis(200);
+const COPY_BUFFER_SIZE: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RelocationProgress {
+    root: String,
+    folders_scanned: usize,
+    candidates_found: usize,
+}
+
+/// Where a game whose install folder is gone may have moved to.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocationProposal {
+    game_id: String,
+    title: String,
+    old_install_path: String,
+    /// Folders with the same name, those holding the game's executable first.
+    candidates: Vec<RelocationCandidate>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RelocationCandidate {
+    install_path: String,
+    /// The executable inside the candidate, when it has one at the same place.
+    executable_path: Option<String>,
+}
+
+/// Looks under `search_roots` for the install folders of games whose
+/// `install_path` no longer exists, matching folders by name. Nothing is
+/// changed; pass the chosen paths to `apply_relocations`. Walking whole
+/// drives takes a while, so `relocation-progress` reports how far it got.
+#[tauri::command]
+pub async fn relocate_installs(
+    app: AppHandle,
+    library: State<'_, Library>,
+    search_roots: Vec<String>,
+) -> Result<Vec<RelocationProposal>, CommandError> {
+    let roots: Vec<PathBuf> = search_roots
+        .into_iter()
+        .filter_map(crate::non_empty)
+        .map(PathBuf::from)
+        .collect();
+    if let Some(root) = roots.iter().find(|root| !root.is_dir()) {
+        return Err(CommandError::not_found(format!(
+            "{} is not a folder",
+            root.display()
+        )));
+    }
+    let lost: Vec<GameEntry> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .filter(|game| game.download_id.is_none())
+        .filter(|game| {
+            game.install_path
+                .as_ref()
+                .is_some_and(|path| !Path::new(path).exists())
+        })
+        .collect();
+    if lost.is_empty() || roots.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    tauri::async_runtime::spawn_blocking(move || find_candidates(&app, &roots, lost))
+        .await
+        .map_err(CommandError::from)
+}
+
+/// Moves games to the install folders chosen from `relocate_installs`, given
+/// as `(game id, new install path)` pairs. Executables are moved along when
+/// found at the same place inside the new folder, and sizes are measured
+/// again. Everything is saved in one write.
+#[tauri::command]
+pub async fn apply_relocations(
+    app: AppHandle,
+    library: State<'_, Library>,
+    relocations: Vec<(String, String)>,
+) -> Result<Vec<GameEntry>, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    let mut moves = Vec::with_capacity(relocations.len());
+    for (id, install_path) in relocations {
+        let game = games
+            .iter()
+            .find(|game| game.id == id)
+            .ok_or_else(|| CommandError::game_not_found(&id))?;
+        if !Path::new(&install_path).is_dir() {
+            return Err(CommandError::not_found(format!(
+                "{install_path} is not a folder"
+            )));
+        }
+        moves.push((game.clone(), install_path));
+    }
+
+    // Measuring happens before the write so the library is not held meanwhile.
+    let moves = tauri::async_runtime::spawn_blocking(move || {
+        moves
+            .into_iter()
+            .map(|(game, install_path)| {
+                let executable_path = moved_executable(&game, Path::new(&install_path));
+                let size = game
+                    .archive_path
+                    .as_ref()
+                    .filter(|path| Path::new(path).exists())
+                    .unwrap_or(&install_path);
+                let size_bytes = crate::compute_path_size(Path::new(size)).ok();
+                (game.id, install_path, executable_path, size_bytes)
+            })
+            .collect::<Vec<_>>()
+    })
+    .await?;
+
+    let updated = library.update(&app, |games| {
+        let now = Utc::now();
+        let mut updated = Vec::new();
+        for (id, install_path, executable_path, size_bytes) in moves {
+            let Some(game) = games.iter_mut().find(|game| game.id == id) else {
+                continue;
+            };
+            game.install_path = Some(install_path);
+            if executable_path.is_some() {
+                game.executable_path = executable_path;
+            }
+            game.size_bytes = size_bytes.or(game.size_bytes);
+            game.updated_at = now;
+            updated.push(game.clone());
+        }
+        Ok(updated)
+    })?;
+    crate::emit_library_updated(&app, updated.iter().map(|game| game.id.clone()).collect());
+    Ok(updated)
+}
+
+/// Walks `roots` once, collecting every folder named like the install folder
+/// of a game in `lost`. Matched folders are not descended into.
+fn find_candidates(
+    app: &AppHandle,
+    roots: &[PathBuf],
+    lost: Vec<GameEntry>,
+) -> Vec<RelocationProposal> {
+    let mut wanted: HashMap<String, Vec<usize>> = HashMap::new();
+    for (index, game) in lost.iter().enumerate() {
+        if let Some(name) = game
+            .install_path
+            .as_deref()
+            .and_then(|path| Path::new(path).file_name())
+        {
+            wanted
+                .entry(name.to_string_lossy().to_lowercase())
+                .or_default()
+                .push(index);
+        }
+    }
+
+    let mut found: Vec<Vec<RelocationCandidate>> = vec![Vec::new(); lost.len()];
+    let mut folders_scanned = 0;
+    let mut candidates_found = 0;
+    for root in roots {
+        let mut walker = WalkDir::new(root).min_depth(1).into_iter();
+        while let Some(entry) = walker.next() {
+            // Unreadable folders are common on whole drives and just skipped.
+            let Ok(entry) = entry else {
+                continue;
+            };
+            if !entry.file_type().is_dir() {
+                continue;
+            }
+            folders_scanned += 1;
+            if folders_scanned % PROGRESS_INTERVAL == 0 {
+                let _ = app.emit_all(
+                    "relocation-progress",
+                    RelocationProgress {
+                        root: root.to_string_lossy().into_owned(),
+                        folders_scanned,
+                        candidates_found,
+                    },
+                );
+            }
+
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            let Some(indices) = wanted.get(&name) else {
+                continue;
+            };
+            for &index in indices {
+                found[index].push(RelocationCandidate {
+                    install_path: entry.path().to_string_lossy().into_owned(),
+                    executable_path: moved_executable(&lost[index], entry.path()),
+                });
+                candidates_found += 1;
+            }
+            walker.skip_current_dir();
+        }
+    }
+
+    lost.into_iter()
+        .zip(found)
+        .filter(|(_, candidates)| !candidates.is_empty())
+        .map(|(game, mut candidates)| {
+            candidates.sort_by_key(|candidate| candidate.executable_path.is_none());
+            RelocationProposal {
+                game_id: game.id,
+                title: game.title,
+                old_install_path: game.install_path.unwrap_or_default(),
+                candidates,
+            }
+        })
+        .collect()
+}
+
+/// Where `game`'s executable is inside `install_path`, if it sits at the
+/// same place relative to the install folder as before the move.
+fn moved_executable(game: &GameEntry, install_path: &Path) -> Option<String> {
+    let old_install = Path::new(game.install_path.as_deref()?);
+    let executable = Path::new(game.executable_path.as_deref()?);
+    let relative = executable
+        .strip_prefix(old_install)
+        .ok()
+        .map(Path::to_path_buf)
+        .or_else(|| executable.file_name().map(PathBuf::from))?;
+    let moved = install_path.join(relative);
+    moved
+        .is_file()
+        .then(|| moved.to_string_lossy().into_owned())
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MoveProgress {
+    game_id: String,
+    copied_bytes: u64,
+    total_bytes: u64,
+}
+
+/// Moves `id`'s install folder into `new_parent`, keeping its name, and
+/// points the install, executable and working paths at the new place. A
+/// rename is tried first; across drives the folder is copied, checked
+/// against the original file by file and only then removed, with
+/// `move-progress` reporting the bytes copied. Free space is checked before
+/// anything is copied.
+#[tauri::command]
+pub async fn move_install(
+    app: AppHandle,
+    library: State<'_, Library>,
+    launcher: State<'_, Launcher>,
+    id: String,
+    new_parent: String,
+) -> Result<GameEntry, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == id)
+        .ok_or_else(|| CommandError::game_not_found(&id))?;
+    if launcher.is_running(&id) {
+        return Err(CommandError::conflict(format!("{} is running", game.title)));
+    }
+    let source = game
+        .install_path
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|path| path.is_dir())
+        .ok_or_else(|| CommandError::not_found(format!("{} has no install folder", game.title)))?;
+    let parent = PathBuf::from(new_parent.trim());
+    if !parent.is_absolute() {
+        return Err(CommandError::invalid_input(
+            "The new location must be an absolute path",
+        ));
+    }
+    let target = source
+        .file_name()
+        .map(|name| parent.join(name))
+        .ok_or_else(|| {
+            CommandError::invalid_input(format!("{} cannot be moved", source.display()))
+        })?;
+    if target.exists() {
+        return Err(CommandError::already_exists(format!(
+            "{} already exists",
+            target.display()
+        )));
+    }
+    let inside = source.canonicalize().is_ok_and(|source| {
+        std::path::absolute(&parent).is_ok_and(|parent| parent.starts_with(source))
+    });
+    if inside {
+        return Err(CommandError::invalid_input(
+            "An install folder cannot be moved inside itself",
+        ));
+    }
+
+    let worker_app = app.clone();
+    let (game_id, from, to) = (id.clone(), source.clone(), target.clone());
+    tauri::async_runtime::spawn_blocking(move || move_folder(&worker_app, &game_id, &from, &to))
+        .await??;
+
+    let updated = library
+        .update(&app, |games| {
+            let game = games
+                .iter_mut()
+                .find(|game| game.id == id)
+                .ok_or_else(|| CommandError::game_not_found(&id))?;
+            game.install_path = Some(target.to_string_lossy().into_owned());
+            for path in [
+                &mut game.executable_path,
+                &mut game.working_dir,
+                &mut game.archive_path,
+            ] {
+                if let Some(moved) = path
+                    .as_deref()
+                    .and_then(|path| Path::new(path).strip_prefix(&source).ok())
+                {
+                    *path = Some(target.join(moved).to_string_lossy().into_owned());
+                }
+            }
+            game.updated_at = Utc::now();
+            Ok(game.clone())
+        })
+        .context("Failed to save library")?;
+    crate::emit_library_updated(&app, vec![id]);
+    Ok(updated)
+}
+
+/// Renames `source` to `target`, or copies it over when they are on
+/// different drives. A failed copy removes what it created and leaves
+/// `source` alone.
+fn move_folder(app: &AppHandle, game_id: &str, source: &Path, target: &Path) -> Result<()> {
+    fs::create_dir_all(target.parent().unwrap_or(target))
+        .with_context(|| format!("Failed to create {}", target.display()))?;
+    if fs::rename(source, target).is_ok() {
+        return Ok(());
+    }
+
+    let total_bytes = crate::sizes::path_size(source, true)?;
+    disk::ensure_free_space(target, total_bytes)?;
+    let files = match copy_tree(app, game_id, source, target, total_bytes) {
+        Ok(files) => files,
+        Err(error) => {
+            // The target did not exist before, so all of it is ours.
+            let _ = fs::remove_dir_all(target);
+            return Err(error);
+        }
+    };
+    for (relative, len) in &files {
+        let copied = fs::metadata(target.join(relative)).map(|metadata| metadata.len());
+        if copied.ok() != Some(*len) {
+            let _ = fs::remove_dir_all(target);
+            return Err(anyhow!(
+                "{} did not copy intact",
+                source.join(relative).display()
+            ));
+        }
+    }
+    let copied_files = WalkDir::new(target)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .count();
+    if copied_files != files.len() {
+        let _ = fs::remove_dir_all(target);
+        return Err(anyhow!(
+            "{} files were copied instead of {}",
+            copied_files,
+            files.len()
+        ));
+    }
+    // The game already lives on, so leftovers are only a matter of space.
+    let _ = fs::remove_dir_all(source);
+    Ok(())
+}
+
+/// Copies every file under `source` to the same place under `target`,
+/// returning them relative to `source` with their sizes.
+fn copy_tree(
+    app: &AppHandle,
+    game_id: &str,
+    source: &Path,
+    target: &Path,
+    total_bytes: u64,
+) -> Result<Vec<(PathBuf, u64)>> {
+    let mut files = Vec::new();
+    let mut copier = Copier::new(app, game_id, total_bytes);
+    // Links are followed, as they were for the total.
+    for entry in WalkDir::new(source).follow_links(true) {
+        let entry = entry?;
+        let relative = entry.path().strip_prefix(source)?.to_path_buf();
+        let destination = target.join(&relative);
+        if entry.file_type().is_dir() {
+            fs::create_dir_all(&destination)
+                .with_context(|| format!("Failed to create {}", destination.display()))?;
+            continue;
+        }
+        let len = copier.copy(entry.path(), &destination)?;
+        files.push((relative, len));
+    }
+    copier.finish();
+    Ok(files)
+}
+
+/// Copies files for a game while reporting `move-progress` against the
+/// total bytes to copy.
+pub struct Copier<'a> {
+    app: &'a AppHandle,
+    game_id: &'a str,
+    copied_bytes: u64,
+    total_bytes: u64,
+    last_progress: Instant,
+    buffer: Vec<u8>,
+}
+
+impl<'a> Copier<'a> {
+    pub fn new(app: &'a AppHandle, game_id: &'a str, total_bytes: u64) -> Self {
+        Self {
+            app,
+            game_id,
+            copied_bytes: 0,
+            total_bytes,
+            last_progress: Instant::now(),
+            buffer: vec![0; COPY_BUFFER_SIZE],
+        }
+    }
+
+    /// Copies `source` to `destination` and flushes it to disk, returning
+    /// the bytes copied.
+    pub fn copy(&mut self, source: &Path, destination: &Path) -> Result<u64> {
+        let mut reader =
+            File::open(source).with_context(|| format!("Failed to open {}", source.display()))?;
+        let mut writer = File::create(destination)
+            .with_context(|| format!("Failed to create {}", destination.display()))?;
+        let mut len = 0;
+        loop {
+            let read = reader
+                .read(&mut self.buffer)
+                .with_context(|| format!("Failed to read {}", source.display()))?;
+            if read == 0 {
+                break;
+            }
+            writer
+                .write_all(&self.buffer[..read])
+                .with_context(|| format!("Failed to write {}", destination.display()))?;
+            len += read as u64;
+            self.copied_bytes += read as u64;
+            if self.last_progress.elapsed() >= PROGRESS_EVERY {
+                self.last_progress = Instant::now();
+                self.emit();
+            }
+        }
+        writer
+            .sync_all()
+            .with_context(|| format!("Failed to write {}", destination.display()))?;
+        Ok(len)
+    }
+
+    /// Reports the final count.
+    pub fn finish(&self) {
+        self.emit();
+    }
+
+    fn emit(&self) {
+        let _ = self.app.emit_all(
+            "move-progress",
+            MoveProgress {
+                game_id: self.game_id.to_string(),
+                copied_bytes: self.copied_bytes,
+                total_bytes: self.total_bytes,
+            },
+        );
+    }
+}
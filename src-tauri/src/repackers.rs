@@ -0,0 +1,89 @@
+use crate::error::CommandError;
+use crate::{search, InstallStatus, Library};
+use anyhow::Context;
+use serde::Serialize;
+use std::collections::HashMap;
+use tauri::{AppHandle, State};
+
+/// Group for games without a repacker.
+const UNKNOWN_REPACKER: &str = "Unknown";
+
+/// Games of one repacker, as listed by `repacker_stats`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RepackerStats {
+    /// The spelling seen first, or "Unknown" for games without a repacker.
+    repacker: String,
+    game_count: usize,
+    /// Sum over the games with a known size.
+    total_size_bytes: u64,
+    by_status: StatusCounts,
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StatusCounts {
+    not_installed: usize,
+    downloading: usize,
+    installed: usize,
+    archived: usize,
+}
+
+impl StatusCounts {
+    pub fn add(&mut self, status: &InstallStatus) {
+        let count = match status {
+            InstallStatus::NotInstalled => &mut self.not_installed,
+            InstallStatus::Downloading => &mut self.downloading,
+            InstallStatus::Installed => &mut self.installed,
+            InstallStatus::Archived => &mut self.archived,
+        };
+        *count += 1;
+    }
+}
+
+/// Games per repacker with their combined size and install states, largest
+/// first. Repackers are grouped ignoring case and accents, so "FitGirl" and
+/// "Fitgirl" count as one.
+#[tauri::command]
+pub fn repacker_stats(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<Vec<RepackerStats>, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    let mut groups: HashMap<String, RepackerStats> = HashMap::new();
+    for game in &games {
+        let repacker = game.repacker.as_deref().unwrap_or(UNKNOWN_REPACKER);
+        let stats = groups
+            .entry(search::normalize(repacker))
+            .or_insert_with(|| RepackerStats {
+                repacker: repacker.to_string(),
+                game_count: 0,
+                total_size_bytes: 0,
+                by_status: StatusCounts::default(),
+            });
+        stats.game_count += 1;
+        stats.total_size_bytes += game.size_bytes.unwrap_or(0);
+        stats.by_status.add(&game.status);
+    }
+
+    let mut stats: Vec<RepackerStats> = groups.into_values().collect();
+    stats.sort_by(|a, b| {
+        b.total_size_bytes
+            .cmp(&a.total_size_bytes)
+            .then_with(|| search::normalize(&a.repacker).cmp(&search::normalize(&b.repacker)))
+    });
+    Ok(stats)
+}
+
+/// Repackers used in the library, one spelling each, for suggesting them
+/// while adding a game.
+#[tauri::command]
+pub fn list_repackers(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<Vec<String>, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    Ok(search::distinct_values(
+        games.iter().filter_map(|game| game.repacker.as_deref()),
+    ))
+}
@@ -0,0 +1,135 @@
+use crate::error::CommandError;
+use crate::{executables, search, GamePayload, InstallStatus, Library};
+use anyhow::Context;
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Proposes a game for each folder directly inside `root` that holds an
+/// executable, with the title taken from the folder name and the largest
+/// plausible executable picked. Folders already used as an install path,
+/// also by games in the trash, are left out. Nothing is added; send the
+/// accepted proposals to `add_games`.
+#[tauri::command]
+pub async fn scan_for_games(
+    app: AppHandle,
+    library: State<'_, Library>,
+    root: String,
+    extensions: Option<Vec<String>>,
+) -> Result<Vec<GamePayload>, CommandError> {
+    let root = PathBuf::from(root.trim());
+    if !root.is_dir() {
+        return Err(CommandError::not_found(format!(
+            "{} is not a folder",
+            root.display()
+        )));
+    }
+    let extensions: Vec<String> = match extensions {
+        Some(extensions) => extensions
+            .iter()
+            .map(|extension| extension.trim().trim_start_matches('.').to_lowercase())
+            .filter(|extension| !extension.is_empty())
+            .collect(),
+        None => executables::DEFAULT_EXTENSIONS
+            .iter()
+            .map(|extension| extension.to_string())
+            .collect(),
+    };
+    if extensions.is_empty() {
+        return Err(CommandError::invalid_input(
+            "No executable extensions given",
+        ));
+    }
+
+    let known: Vec<PathBuf> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .chain(library.trash(&app).context("Failed to load library")?)
+        .filter_map(|game| game.install_path.map(PathBuf::from))
+        .collect();
+
+    tauri::async_runtime::spawn_blocking(move || scan(&root, &extensions, &known))
+        .await?
+        .map_err(CommandError::from)
+}
+
+fn scan(root: &Path, extensions: &[String], known: &[PathBuf]) -> anyhow::Result<Vec<GamePayload>> {
+    let mut proposals = Vec::new();
+    for entry in fs::read_dir(root)? {
+        let folder = entry?.path();
+        if !folder.is_dir() || known.contains(&folder) {
+            continue;
+        }
+        let Some(executable) = main_executable(&folder, extensions) else {
+            continue;
+        };
+        let name = folder
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        proposals.push(GamePayload {
+            title: infer_title(&name),
+            install_path: Some(folder.to_string_lossy().into_owned()),
+            executable_path: Some(executable.to_string_lossy().into_owned()),
+            status: InstallStatus::Installed,
+            ..GamePayload::default()
+        });
+    }
+    proposals.sort_by_key(|proposal| search::normalize(&proposal.title));
+    Ok(proposals)
+}
+
+/// The largest file in `folder` with one of `extensions`, skipping
+/// uninstallers, setups and redistributables.
+fn main_executable(folder: &Path, extensions: &[String]) -> Option<PathBuf> {
+    executables::find(folder, extensions, false)
+        .into_iter()
+        .max_by_key(|candidate| candidate.size_bytes)
+        .map(|candidate| PathBuf::from(candidate.path))
+}
+
+/// A title from a folder name such as "The.Witcher.3.v1.32 [FitGirl Repack]":
+/// bracketed tags and version numbers are dropped, and dots and underscores
+/// become spaces when the name has none.
+fn infer_title(folder: &str) -> String {
+    let mut unbracketed = String::new();
+    let mut depth = 0usize;
+    for c in folder.chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if depth == 0 => unbracketed.push(c),
+            _ => {}
+        }
+    }
+    let spaced = if unbracketed.trim().contains(' ') {
+        unbracketed.replace('_', " ")
+    } else {
+        // Dots between digits belong to a version number and are kept.
+        let chars: Vec<char> = unbracketed.chars().collect();
+        (0..chars.len())
+            .map(|index| match chars[index] {
+                '.' if index > 0
+                    && chars[index - 1].is_ascii_digit()
+                    && chars.get(index + 1).is_some_and(char::is_ascii_digit) =>
+                {
+                    '.'
+                }
+                '.' | '_' => ' ',
+                c => c,
+            })
+            .collect()
+    };
+    let title = spaced
+        .split_whitespace()
+        .filter(|word| !search::is_version_tag(word))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let title = title.trim_matches(|c: char| c == '-' || c.is_whitespace());
+    if title.is_empty() {
+        folder.trim().to_string()
+    } else {
+        title.to_string()
+    }
+}
@@ -0,0 +1,398 @@
+use crate::collections::Collections;
+use crate::error::CommandError;
+use crate::{Completion, GameEntry, InstallStatus, Library};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use tauri::{AppHandle, State};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Field a game listing is ordered by.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum SortKey {
+    Title,
+    AddedAt,
+    #[default]
+    UpdatedAt,
+    SizeBytes,
+    Status,
+    LastPlayedAt,
+    Rating,
+    Completion,
+}
+
+/// Filters for `search_games`. Every field is optional and set fields must all match.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameQuery {
+    /// Free text looked up in titles and alternate titles, descriptions,
+    /// notes, developers, publishers, repackers and custom field values.
+    text: Option<String>,
+    /// Tags a game must all carry.
+    #[serde(default)]
+    tags: Vec<String>,
+    status: Option<InstallStatus>,
+    #[serde(default)]
+    favorites_only: bool,
+    completion: Option<Completion>,
+    /// Only games rated at least this; unrated games never match.
+    min_rating: Option<u8>,
+    repacker: Option<String>,
+    developer: Option<String>,
+    publisher: Option<String>,
+    /// Release years, inclusive; games without one never match.
+    min_release_year: Option<u16>,
+    max_release_year: Option<u16>,
+    /// Only games in this collection, listed in the collection's order.
+    collection_id: Option<String>,
+    min_size_bytes: Option<u64>,
+    max_size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSearchResult {
+    games: Vec<GameEntry>,
+    total: usize,
+}
+
+/// Returns the games matching `query`, most recently updated first unless a
+/// collection sets the order. Text is compared case- and accent-insensitively,
+/// so "pokemon" finds "Pokémon".
+#[tauri::command]
+pub fn search_games(
+    app: AppHandle,
+    library: State<'_, Library>,
+    collections: State<'_, Collections>,
+    query: GameQuery,
+) -> Result<GameSearchResult, CommandError> {
+    let mut games = library.games(&app).context("Failed to load library")?;
+
+    let matcher = Matcher::new(&query);
+    games.retain(|game| matcher.matches(game));
+    if let Some(collection_id) = &query.collection_id {
+        let members = collections.game_ids(&app, collection_id)?;
+        games.retain(|game| members.contains(&game.id));
+        games.sort_by_key(|game| members.iter().position(|id| *id == game.id));
+    } else {
+        sort_games(&mut games, SortKey::UpdatedAt, true);
+    }
+    Ok(GameSearchResult {
+        total: games.len(),
+        games,
+    })
+}
+
+/// Distinct values of `field` across the library, for filter dropdowns.
+/// `field` is `developer`, `publisher` or `repacker`.
+#[tauri::command]
+pub fn list_values(
+    app: AppHandle,
+    library: State<'_, Library>,
+    field: String,
+) -> Result<Vec<String>, CommandError> {
+    let value: fn(&GameEntry) -> &Option<String> = match field.as_str() {
+        "developer" => |game| &game.developer,
+        "publisher" => |game| &game.publisher,
+        "repacker" => |game| &game.repacker,
+        other => {
+            return Err(CommandError::invalid_input(format!(
+                "Cannot list values of {other}"
+            )))
+        }
+    };
+    let games = library.games(&app).context("Failed to load library")?;
+    Ok(distinct_values(
+        games.iter().filter_map(|game| value(game).as_deref()),
+    ))
+}
+
+/// `values` with spellings that differ only in case or accents listed once,
+/// as first seen, sorted case-insensitively.
+pub fn distinct_values<'a>(values: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let mut distinct: BTreeMap<String, String> = BTreeMap::new();
+    for value in values {
+        distinct
+            .entry(normalize(value))
+            .or_insert_with(|| value.to_string());
+    }
+    distinct.into_values().collect()
+}
+
+/// A `GameQuery` with its text fields normalized once up front.
+struct Matcher<'a> {
+    query: &'a GameQuery,
+    text: Option<String>,
+    tags: Vec<String>,
+    repacker: Option<String>,
+    developer: Option<String>,
+    publisher: Option<String>,
+}
+
+impl<'a> Matcher<'a> {
+    fn new(query: &'a GameQuery) -> Self {
+        let normalized = |value: &Option<String>| {
+            value
+                .as_deref()
+                .map(normalize)
+                .filter(|value| !value.is_empty())
+        };
+        Self {
+            query,
+            text: normalized(&query.text),
+            tags: query
+                .tags
+                .iter()
+                .map(|tag| normalize(tag))
+                .filter(|tag| !tag.is_empty())
+                .collect(),
+            repacker: normalized(&query.repacker),
+            developer: normalized(&query.developer),
+            publisher: normalized(&query.publisher),
+        }
+    }
+
+    fn matches(&self, game: &GameEntry) -> bool {
+        if self.query.favorites_only && !game.favorite {
+            return false;
+        }
+        if self
+            .query
+            .status
+            .as_ref()
+            .is_some_and(|status| *status != game.status)
+        {
+            return false;
+        }
+        if self.query.completion.is_some() && self.query.completion != game.completion {
+            return false;
+        }
+        if let Some(min_rating) = self.query.min_rating {
+            if game.rating.is_none_or(|rating| rating < min_rating) {
+                return false;
+            }
+        }
+        if self.query.min_size_bytes.is_some() || self.query.max_size_bytes.is_some() {
+            let Some(size) = game.size_bytes else {
+                return false;
+            };
+            if self.query.min_size_bytes.is_some_and(|min| size < min)
+                || self.query.max_size_bytes.is_some_and(|max| size > max)
+            {
+                return false;
+            }
+        }
+        if self.query.min_release_year.is_some() || self.query.max_release_year.is_some() {
+            let Some(year) = game.release_year else {
+                return false;
+            };
+            if self.query.min_release_year.is_some_and(|min| year < min)
+                || self.query.max_release_year.is_some_and(|max| year > max)
+            {
+                return false;
+            }
+        }
+        for (wanted, value) in [
+            (&self.repacker, &game.repacker),
+            (&self.developer, &game.developer),
+            (&self.publisher, &game.publisher),
+        ] {
+            if let Some(wanted) = wanted {
+                if value.as_deref().map(normalize).as_ref() != Some(wanted) {
+                    return false;
+                }
+            }
+        }
+        if !self.tags.is_empty() {
+            let game_tags: Vec<String> = game.tags.iter().map(|tag| normalize(tag)).collect();
+            if !self.tags.iter().all(|tag| game_tags.contains(tag)) {
+                return false;
+            }
+        }
+        if let Some(text) = &self.text {
+            let found = [
+                Some(&game.title),
+                game.description.as_ref(),
+                game.developer.as_ref(),
+                game.publisher.as_ref(),
+                game.notes.as_ref(),
+                game.repacker.as_ref(),
+            ]
+            .into_iter()
+            .flatten()
+            .chain(&game.alternate_titles)
+            .chain(game.custom_fields.values())
+            .any(|field| normalize(field).contains(text.as_str()));
+            if !found {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Lowercases `value` and strips accents by decomposing it and dropping the
+/// combining marks.
+pub fn normalize(value: &str) -> String {
+    value
+        .trim()
+        .nfkd()
+        .filter(|c| !is_combining_mark(*c))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// A title reduced for duplicate checks: accents, case and punctuation are
+/// dropped, as are bracketed tags and version numbers, so
+/// "The Witcher 3 (GOTY) v1.32" and "the witcher 3" compare equal.
+pub fn title_key(title: &str) -> String {
+    let mut unbracketed = String::new();
+    let mut depth = 0usize;
+    for c in normalize(title).chars() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth = depth.saturating_sub(1),
+            c if depth == 0 => unbracketed.push(c),
+            _ => {}
+        }
+    }
+    unbracketed
+        .split_whitespace()
+        .filter(|word| !is_version_tag(word))
+        .flat_map(|word| word.split(|c: char| !c.is_alphanumeric()))
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// "v1", "v1.2.3" or "1.2.3", but not a bare "2" that may be part of the name.
+pub fn is_version_tag(word: &str) -> bool {
+    let digits = word.strip_prefix('v').unwrap_or(word);
+    let numeric = !digits.is_empty()
+        && digits.starts_with(|c: char| c.is_ascii_digit())
+        && digits.chars().all(|c| c.is_ascii_digit() || c == '.');
+    numeric && (digits.len() < word.len() || digits.contains('.'))
+}
+
+/// Orders `games` by `key`. Titles are compared through `sort_title`,
+/// case-insensitively with digit runs read as numbers, so "Game 2" comes
+/// before "Game 10"; games without a size
+/// sort as smallest, and games never played, unrated or without a completion
+/// come last in either direction. Ties fall back to the title.
+pub fn sort_games(games: &mut [GameEntry], key: SortKey, descending: bool) {
+    games.sort_by(|a, b| {
+        let present = |game: &GameEntry| match key {
+            SortKey::LastPlayedAt => game.last_played_at.is_some(),
+            SortKey::Rating => game.rating.is_some(),
+            SortKey::Completion => game.completion.is_some(),
+            _ => true,
+        };
+        match (present(a), present(b)) {
+            (true, false) => return Ordering::Less,
+            (false, true) => return Ordering::Greater,
+            _ => {}
+        }
+        let ordering = match key {
+            SortKey::Title => Ordering::Equal,
+            SortKey::AddedAt => a.added_at.cmp(&b.added_at),
+            SortKey::UpdatedAt => a.updated_at.cmp(&b.updated_at),
+            SortKey::SizeBytes => a.size_bytes.cmp(&b.size_bytes),
+            SortKey::Status => status_rank(&a.status).cmp(&status_rank(&b.status)),
+            SortKey::LastPlayedAt => a.last_played_at.cmp(&b.last_played_at),
+            SortKey::Rating => a.rating.cmp(&b.rating),
+            SortKey::Completion => a
+                .completion
+                .map(completion_rank)
+                .cmp(&b.completion.map(completion_rank)),
+        }
+        .then_with(|| natural_cmp(sort_title(a), sort_title(b)));
+        if descending {
+            ordering.reverse()
+        } else {
+            ordering
+        }
+    });
+}
+
+/// The title a game is ordered by: its own `sort_title` if set, otherwise
+/// its title without a leading "The", "A" or "An".
+pub fn sort_title(game: &GameEntry) -> &str {
+    if let Some(sort_title) = &game.sort_title {
+        return sort_title;
+    }
+    let title = game.title.as_str();
+    ["the ", "a ", "an "]
+        .into_iter()
+        .find_map(|article| {
+            title
+                .get(..article.len())
+                .filter(|prefix| prefix.eq_ignore_ascii_case(article))
+                .map(|_| title[article.len()..].trim_start())
+        })
+        .filter(|rest| !rest.is_empty())
+        .unwrap_or(title)
+}
+
+fn status_rank(status: &InstallStatus) -> u8 {
+    match status {
+        InstallStatus::NotInstalled => 0,
+        InstallStatus::Downloading => 1,
+        InstallStatus::Archived => 2,
+        InstallStatus::Installed => 3,
+    }
+}
+
+/// From not yet begun to done with, either way.
+fn completion_rank(completion: Completion) -> u8 {
+    match completion {
+        Completion::NotStarted => 0,
+        Completion::Playing => 1,
+        Completion::OnHold => 2,
+        Completion::Completed => 3,
+        Completion::Abandoned => 4,
+    }
+}
+
+/// Case-insensitive comparison that orders runs of digits by their value.
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut left = a.chars().flat_map(char::to_lowercase).peekable();
+    let mut right = b.chars().flat_map(char::to_lowercase).peekable();
+    loop {
+        match (left.peek().copied(), right.peek().copied()) {
+            (None, None) => return Ordering::Equal,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut left);
+                let y = take_number(&mut right);
+                // Compare digit strings by length first so huge numbers cannot overflow.
+                let ordering = x
+                    .trim_start_matches('0')
+                    .len()
+                    .cmp(&y.trim_start_matches('0').len())
+                    .then_with(|| x.trim_start_matches('0').cmp(y.trim_start_matches('0')));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                left.next();
+                right.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<impl Iterator<Item = char>>) -> String {
+    let mut digits = String::new();
+    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+        digits.push(c);
+    }
+    digits
+}
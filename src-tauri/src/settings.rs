@@ -0,0 +1,313 @@
+use crate::downloads::{
+    DownloadManager, DEFAULT_MAX_CONCURRENT_DOWNLOADS, MAX_CONCURRENT_DOWNLOADS,
+};
+use crate::error::CommandError;
+use crate::hook;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use tauri::{AppHandle, Manager, State};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Theme {
+    #[default]
+    Dark,
+    Light,
+    /// Follows the operating system.
+    System,
+}
+
+/// Preferences kept in `settings.json`. Every field falls back to its
+/// default when missing, so files written by older versions still load.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Settings {
+    /// Folder downloads go to when `queue_download` is given none.
+    pub default_download_dir: Option<String>,
+    /// Folders that take precedence over `default_download_dir` for games by
+    /// a repacker, keyed by repacker name, matched ignoring case and accents.
+    pub repacker_download_dirs: BTreeMap<String, String>,
+    /// Further folders games are installed in, from which `uninstall_game`
+    /// may delete install folders.
+    pub game_folders: Vec<String>,
+    /// How many downloads run at once.
+    pub download_concurrency: usize,
+    /// Cap on the combined speed of all downloads; `None` for no cap.
+    pub download_speed_limit: Option<u64>,
+    /// Whether downloads that do not choose accept invalid TLS certificates.
+    pub allow_invalid_certs: bool,
+    /// Command run on every completed download, with `{file}` standing for
+    /// the file; a non-zero exit flags the download.
+    pub post_download_hook: Option<String>,
+    /// Whether finished and failed downloads raise a desktop notification.
+    pub download_notifications: bool,
+    /// Whether closing the window while downloads run hides it to the tray.
+    pub close_to_tray: bool,
+    /// Whether a play session stays open while processes from the game's
+    /// install folder run after the launched executable has exited.
+    pub follow_game_processes: bool,
+    pub theme: Theme,
+    /// Lets `open_path` open any existing path, not only those the library
+    /// knows.
+    pub allow_arbitrary_paths: bool,
+    /// Command `open_terminal` runs instead of the platform's terminal, with
+    /// `{path}` standing for the folder.
+    pub terminal_command: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            default_download_dir: None,
+            repacker_download_dirs: BTreeMap::new(),
+            game_folders: Vec::new(),
+            download_concurrency: DEFAULT_MAX_CONCURRENT_DOWNLOADS,
+            download_speed_limit: None,
+            allow_invalid_certs: false,
+            post_download_hook: None,
+            download_notifications: true,
+            close_to_tray: true,
+            follow_game_processes: true,
+            theme: Theme::Dark,
+            allow_arbitrary_paths: false,
+            terminal_command: None,
+        }
+    }
+}
+
+/// Changes for `update_settings`. Fields left out keep their value; for
+/// optional settings, `null` clears the value.
+#[derive(Debug, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SettingsPatch {
+    #[serde(default, deserialize_with = "present")]
+    pub default_download_dir: Option<Option<String>>,
+    /// Replaces all repacker folders at once.
+    pub repacker_download_dirs: Option<BTreeMap<String, String>>,
+    pub game_folders: Option<Vec<String>>,
+    pub download_concurrency: Option<usize>,
+    #[serde(default, deserialize_with = "present")]
+    pub download_speed_limit: Option<Option<u64>>,
+    pub allow_invalid_certs: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub post_download_hook: Option<Option<String>>,
+    pub download_notifications: Option<bool>,
+    pub close_to_tray: Option<bool>,
+    pub follow_game_processes: Option<bool>,
+    pub theme: Option<Theme>,
+    pub allow_arbitrary_paths: Option<bool>,
+    #[serde(default, deserialize_with = "present")]
+    pub terminal_command: Option<Option<String>>,
+}
+
+/// Tells a field set to `null` apart from one left out: it becomes
+/// `Some(None)` rather than `None`.
+pub fn present<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    Option::deserialize(deserializer).map(Some)
+}
+
+impl SettingsPatch {
+    fn apply(self, settings: &mut Settings) -> Result<(), CommandError> {
+        if let Some(dir) = self.default_download_dir {
+            let dir = dir.and_then(crate::non_empty);
+            if let Some(dir) = &dir {
+                check_absolute(dir)?;
+            }
+            settings.default_download_dir = dir;
+        }
+        if let Some(dirs) = self.repacker_download_dirs {
+            let mut kept = BTreeMap::new();
+            for (repacker, dir) in dirs {
+                let (Some(repacker), Some(dir)) =
+                    (crate::non_empty(repacker), crate::non_empty(dir))
+                else {
+                    continue;
+                };
+                check_absolute(&dir)?;
+                kept.insert(repacker, dir);
+            }
+            settings.repacker_download_dirs = kept;
+        }
+        if let Some(dirs) = self.game_folders {
+            let dirs: Vec<String> = dirs.into_iter().filter_map(crate::non_empty).collect();
+            for dir in &dirs {
+                check_absolute(dir)?;
+            }
+            settings.game_folders = dirs;
+        }
+        if let Some(limit) = self.download_concurrency {
+            if !(1..=MAX_CONCURRENT_DOWNLOADS).contains(&limit) {
+                return Err(CommandError::invalid_input(format!(
+                    "Concurrency limit must be between 1 and {MAX_CONCURRENT_DOWNLOADS}, got {limit}"
+                )));
+            }
+            settings.download_concurrency = limit;
+        }
+        if let Some(limit) = self.download_speed_limit {
+            if limit == Some(0) {
+                return Err(CommandError::invalid_input(
+                    "Speed limit must be greater than zero",
+                ));
+            }
+            settings.download_speed_limit = limit;
+        }
+        if let Some(allow) = self.allow_invalid_certs {
+            settings.allow_invalid_certs = allow;
+        }
+        if let Some(command) = self.post_download_hook {
+            let command = command.filter(|command| !command.trim().is_empty());
+            if let Some(command) = &command {
+                hook::parse_template(command)
+                    .map_err(|error| CommandError::invalid_input(error.to_string()))?;
+            }
+            settings.post_download_hook = command;
+        }
+        if let Some(enabled) = self.download_notifications {
+            settings.download_notifications = enabled;
+        }
+        if let Some(enabled) = self.close_to_tray {
+            settings.close_to_tray = enabled;
+        }
+        if let Some(enabled) = self.follow_game_processes {
+            settings.follow_game_processes = enabled;
+        }
+        if let Some(theme) = self.theme {
+            settings.theme = theme;
+        }
+        if let Some(allow) = self.allow_arbitrary_paths {
+            settings.allow_arbitrary_paths = allow;
+        }
+        if let Some(command) = self.terminal_command {
+            let command = command.filter(|command| !command.trim().is_empty());
+            if let Some(command) = &command {
+                hook::parse_template(command)
+                    .map_err(|error| CommandError::invalid_input(error.to_string()))?;
+            }
+            settings.terminal_command = command;
+        }
+        Ok(())
+    }
+}
+
+/// The settings in effect, shared by every part of the app that depends on
+/// them so changes apply without a restart.
+#[derive(Debug, Default)]
+pub struct SettingsStore {
+    current: Mutex<Settings>,
+}
+
+impl SettingsStore {
+    fn lock(&self) -> MutexGuard<'_, Settings> {
+        self.current.lock().expect("settings poisoned")
+    }
+
+    pub fn get(&self) -> Settings {
+        self.lock().clone()
+    }
+
+    /// Reads `settings.json`. A missing or unreadable file leaves the
+    /// defaults in place; it is replaced on the next change.
+    pub fn load(&self, app: &AppHandle) {
+        let loaded = settings_path(app)
+            .and_then(|path| fs::read_to_string(&path).map_err(Into::into))
+            .ok()
+            .and_then(|text| serde_json::from_str::<Settings>(&text).ok());
+        if let Some(settings) = loaded {
+            *self.lock() = settings;
+        }
+    }
+
+    /// Applies `patch`, saves the result and hands it to the download
+    /// manager. Nothing changes when a value is invalid or the file cannot
+    /// be written.
+    pub fn update(&self, app: &AppHandle, patch: SettingsPatch) -> Result<Settings, CommandError> {
+        let mut current = self.lock();
+        let mut settings = current.clone();
+        patch.apply(&mut settings)?;
+        save(app, &settings).context("Failed to save settings")?;
+        *current = settings.clone();
+        drop(current);
+
+        app.state::<DownloadManager>()
+            .apply_settings(app, &settings);
+        let _ = app.emit_all("settings-changed", &settings);
+        Ok(settings)
+    }
+}
+
+#[tauri::command]
+pub fn get_settings(settings: State<'_, SettingsStore>) -> Settings {
+    settings.get()
+}
+
+/// Changes the settings given in `partial` and returns all of them.
+#[tauri::command]
+pub fn update_settings(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    partial: SettingsPatch,
+) -> Result<Settings, CommandError> {
+    settings.update(&app, partial)
+}
+
+fn check_absolute(dir: &str) -> Result<(), CommandError> {
+    if Path::new(dir).is_absolute() {
+        Ok(())
+    } else {
+        Err(CommandError::invalid_input(format!(
+            "Folders must be absolute paths: {dir}"
+        )))
+    }
+}
+
+fn settings_path(app: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::resolve_data_dir(app)?.join(SETTINGS_FILE))
+}
+
+fn save(app: &AppHandle, settings: &Settings) -> Result<()> {
+    let json = serde_json::to_vec_pretty(settings).context("Failed to serialize settings")?;
+    crate::write_atomic(&settings_path(app)?, &json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    fn concurrency(limit: usize) -> Result<usize, CommandError> {
+        let mut settings = Settings::default();
+        let patch = SettingsPatch {
+            download_concurrency: Some(limit),
+            ..SettingsPatch::default()
+        };
+        patch.apply(&mut settings)?;
+        Ok(settings.download_concurrency)
+    }
+
+    #[test]
+    fn accepts_concurrency_in_range() {
+        assert_eq!(concurrency(1).unwrap(), 1);
+        assert_eq!(
+            concurrency(MAX_CONCURRENT_DOWNLOADS).unwrap(),
+            MAX_CONCURRENT_DOWNLOADS
+        );
+    }
+
+    #[test]
+    fn refuses_concurrency_out_of_range() {
+        for limit in [0, MAX_CONCURRENT_DOWNLOADS + 1, usize::MAX] {
+            let error = concurrency(limit).unwrap_err();
+            assert!(matches!(error.code, ErrorCode::InvalidInput), "{limit}");
+        }
+    }
+}
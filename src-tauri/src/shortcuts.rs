@@ -0,0 +1,280 @@
+use crate::error::CommandError;
+use crate::{GameEntry, Library};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Where `create_shortcut` puts a shortcut.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ShortcutLocation {
+    Desktop,
+    /// The Start menu on Windows; the applications menu elsewhere.
+    StartMenu,
+}
+
+/// Creates a shortcut to a game's executable on the desktop or in the Start
+/// menu, starting in the game's working folder with its launch arguments and
+/// showing its extracted icon when it has one. A `.lnk` file on
+/// Windows and a `.desktop` file on Linux; an existing shortcut of the same
+/// name is replaced. Environment variables, wrapper commands and elevation
+/// are not carried over. Returns the shortcut's path.
+#[tauri::command]
+pub fn create_shortcut(
+    app: AppHandle,
+    library: State<'_, Library>,
+    game_id: String,
+    location: ShortcutLocation,
+) -> Result<String, CommandError> {
+    let game = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .find(|game| game.id == game_id)
+        .ok_or_else(|| CommandError::game_not_found(&game_id))?;
+    create(&game, location)
+        .map(|path| path.to_string_lossy().into_owned())
+        .map_err(CommandError::from)
+}
+
+fn create(game: &GameEntry, location: ShortcutLocation) -> Result<PathBuf> {
+    let executable = game
+        .executable_path
+        .as_deref()
+        .map(PathBuf::from)
+        .ok_or_else(|| CommandError::not_found(format!("{} has no executable set", game.title)))?;
+    if !executable.is_file() {
+        return Err(CommandError::not_found(format!(
+            "Executable not found: {}",
+            executable.display()
+        ))
+        .into());
+    }
+    let working_dir = game
+        .working_dir
+        .as_deref()
+        .map(PathBuf::from)
+        .or_else(|| executable.parent().map(Path::to_path_buf));
+    let args = match game.launch_args.as_deref() {
+        Some(args) => crate::hook::parse_template(args)
+            .with_context(|| format!("Invalid launch arguments for {}", game.title))?,
+        None => Vec::new(),
+    };
+    let icon = game
+        .icon_path
+        .as_deref()
+        .map(PathBuf::from)
+        .filter(|path| path.is_file());
+
+    let folder = location_folder(location)?;
+    std::fs::create_dir_all(&folder)
+        .with_context(|| format!("Failed to create {}", folder.display()))?;
+    let shortcut = Shortcut {
+        name: file_name(&game.title),
+        title: &game.title,
+        executable: &executable,
+        working_dir: working_dir.as_deref(),
+        args: &args,
+        icon: icon.as_deref(),
+    };
+    write(&shortcut, &folder)
+}
+
+struct Shortcut<'a> {
+    /// `title` made safe for a file name.
+    name: String,
+    title: &'a str,
+    executable: &'a Path,
+    working_dir: Option<&'a Path>,
+    args: &'a [String],
+    icon: Option<&'a Path>,
+}
+
+/// `title` without the characters Windows refuses in file names.
+fn file_name(title: &str) -> String {
+    let name: String = title
+        .chars()
+        .filter(|c| {
+            !c.is_control() && !matches!(c, '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*')
+        })
+        .collect();
+    let name = name.trim().trim_end_matches('.');
+    if name.is_empty() {
+        "Game".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+#[cfg(windows)]
+fn location_folder(location: ShortcutLocation) -> Result<PathBuf> {
+    match location {
+        ShortcutLocation::Desktop => tauri::api::path::desktop_dir(),
+        ShortcutLocation::StartMenu => tauri::api::path::data_dir()
+            .map(|roaming| roaming.join(r"Microsoft\Windows\Start Menu\Programs")),
+    }
+    .ok_or_else(|| anyhow!("The shortcut folder could not be found"))
+}
+
+#[cfg(windows)]
+fn write(shortcut: &Shortcut<'_>, folder: &Path) -> Result<PathBuf> {
+    let path = folder.join(format!("{}.lnk", shortcut.name));
+    let mut link = mslnk::ShellLink::new(shortcut.executable)
+        .map_err(|error| anyhow!("Failed to create the shortcut: {error:?}"))?;
+    link.set_name(Some(shortcut.title.to_string()));
+    link.set_working_dir(
+        shortcut
+            .working_dir
+            .map(|dir| dir.to_string_lossy().into_owned()),
+    );
+    if !shortcut.args.is_empty() {
+        link.set_arguments(Some(windows_command_line(shortcut.args)));
+    }
+    // Shortcuts only show .ico files, so the stored PNG is converted once.
+    let icon = shortcut
+        .icon
+        .and_then(|png| ico_beside(png).ok())
+        .unwrap_or_else(|| shortcut.executable.to_path_buf());
+    link.set_icon_location(Some(icon.to_string_lossy().into_owned()));
+    link.create_lnk(&path)
+        .map_err(|error| anyhow!("Failed to write {}: {error:?}", path.display()))?;
+    Ok(path)
+}
+
+/// An `.ico` copy of a PNG icon, next to it.
+#[cfg(windows)]
+fn ico_beside(png: &Path) -> Result<PathBuf> {
+    let ico = png.with_extension("ico");
+    if !ico.is_file() {
+        image::open(png)?.save_with_format(&ico, image::ImageFormat::Ico)?;
+    }
+    Ok(ico)
+}
+
+/// Arguments quoted the way programs split their command line on Windows.
+#[cfg(windows)]
+fn windows_command_line(args: &[String]) -> String {
+    args.iter()
+        .map(|arg| {
+            if !arg.is_empty() && !arg.contains([' ', '\t', '"']) {
+                return arg.clone();
+            }
+            // Backslashes only escape when they precede a quote.
+            let mut quoted = String::from('"');
+            let mut backslashes = 0;
+            for c in arg.chars() {
+                if c == '\\' {
+                    backslashes += 1;
+                    continue;
+                }
+                let escapes = if c == '"' {
+                    backslashes * 2 + 1
+                } else {
+                    backslashes
+                };
+                quoted.push_str(&"\\".repeat(escapes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+            quoted.push_str(&"\\".repeat(backslashes * 2));
+            quoted.push('"');
+            quoted
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(not(windows))]
+fn location_folder(location: ShortcutLocation) -> Result<PathBuf> {
+    if cfg!(target_os = "macos") {
+        return Err(anyhow!("Shortcuts are not supported on macOS"));
+    }
+    match location {
+        ShortcutLocation::Desktop => tauri::api::path::desktop_dir(),
+        ShortcutLocation::StartMenu => {
+            tauri::api::path::data_dir().map(|data| data.join("applications"))
+        }
+    }
+    .ok_or_else(|| anyhow!("The shortcut folder could not be found"))
+}
+
+#[cfg(not(windows))]
+fn write(shortcut: &Shortcut<'_>, folder: &Path) -> Result<PathBuf> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let path = folder.join(format!("{}.desktop", shortcut.name));
+    let exec: Vec<String> = std::iter::once(shortcut.executable.to_string_lossy().into_owned())
+        .chain(shortcut.args.iter().cloned())
+        .map(|arg| desktop_exec_arg(&arg))
+        .collect();
+    let mut entry = format!(
+        "[Desktop Entry]\nType=Application\nName={}\nExec={}\nTerminal=false\nCategories=Game;\n",
+        desktop_value(shortcut.title),
+        desktop_value(&exec.join(" "))
+    );
+    if let Some(dir) = shortcut.working_dir {
+        entry.push_str(&format!("Path={}\n", desktop_value(&dir.to_string_lossy())));
+    }
+    if let Some(icon) = shortcut.icon {
+        entry.push_str(&format!(
+            "Icon={}\n",
+            desktop_value(&icon.to_string_lossy())
+        ));
+    }
+    crate::write_atomic(&path, entry.as_bytes())?;
+    // Desktop environments only start launchers marked executable.
+    std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))
+        .with_context(|| format!("Failed to mark {} executable", path.display()))?;
+    Ok(path)
+}
+
+/// An `Exec=` argument: quoted when it holds reserved characters, with `%`
+/// doubled so it is not read as a field code.
+#[cfg(not(windows))]
+fn desktop_exec_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%");
+    let reserved = |c: char| {
+        c.is_whitespace()
+            || matches!(
+                c,
+                '"' | '\''
+                    | '\\'
+                    | '>'
+                    | '<'
+                    | '~'
+                    | '|'
+                    | '&'
+                    | ';'
+                    | '$'
+                    | '*'
+                    | '?'
+                    | '#'
+                    | '('
+                    | ')'
+                    | '`'
+            )
+    };
+    if !arg.is_empty() && !arg.contains(reserved) {
+        return arg;
+    }
+    let mut quoted = String::from('"');
+    for c in arg.chars() {
+        if matches!(c, '"' | '`' | '$' | '\\') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// A value for a `.desktop` key, with the escapes the format requires.
+#[cfg(not(windows))]
+fn desktop_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+        .replace('\r', "\\r")
+}
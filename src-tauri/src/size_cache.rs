@@ -0,0 +1,112 @@
+use crate::error::CommandError;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, State};
+
+const SIZE_CACHE_FILE: &str = "size-cache.json";
+
+/// A folder's size as last measured, with what its top level looked like
+/// at the time.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CachedSize {
+    size_bytes: u64,
+    computed_at: DateTime<Utc>,
+    modified_at: DateTime<Utc>,
+    entry_count: usize,
+}
+
+/// Managed state holding `size-cache.json`, loaded on first use.
+#[derive(Debug, Default)]
+pub struct SizeCache {
+    entries: Mutex<Option<HashMap<String, CachedSize>>>,
+}
+
+impl SizeCache {
+    fn with_entries<T>(
+        &self,
+        app: &AppHandle,
+        action: impl FnOnce(&mut HashMap<String, CachedSize>) -> T,
+    ) -> T {
+        let mut entries = self.entries.lock().expect("size cache poisoned");
+        // A cache that cannot be read is only a reason to measure again.
+        let entries = entries.get_or_insert_with(|| read_cache(app).unwrap_or_default());
+        action(entries)
+    }
+}
+
+/// Size of `path` for showing in the library, without following links.
+/// Folders are only walked again when `force` is set or their modification
+/// time or number of entries changed since the last walk, so changes deeper
+/// inside can go unnoticed. Use [`crate::sizes::path_size`] wherever the
+/// size has to be right, such as when checking for free space.
+pub fn cached_size(app: &AppHandle, path: &Path, force: bool) -> Result<u64> {
+    if !path.is_dir() {
+        return crate::compute_path_size(path);
+    }
+    let key = path.to_string_lossy().into_owned();
+    let (modified_at, entry_count) = stamp(path)?;
+    let cache = app.state::<SizeCache>();
+    let cached = cache.with_entries(app, |entries| entries.get(&key).cloned());
+    if let Some(cached) = cached.filter(|cached| {
+        !force && cached.modified_at == modified_at && cached.entry_count == entry_count
+    }) {
+        return Ok(cached.size_bytes);
+    }
+
+    let size_bytes = crate::compute_path_size(path)?;
+    let entry = CachedSize {
+        size_bytes,
+        computed_at: Utc::now(),
+        modified_at,
+        entry_count,
+    };
+    cache.with_entries(app, |entries| {
+        entries.insert(key, entry);
+        // Losing the cache only costs a walk next time.
+        let _ = write_cache(app, entries);
+    });
+    Ok(size_bytes)
+}
+
+/// Forgets every measured size, so the next measurement walks each folder.
+#[tauri::command]
+pub fn clear_size_cache(app: AppHandle, cache: State<'_, SizeCache>) -> Result<(), CommandError> {
+    cache.with_entries(&app, |entries| entries.clear());
+    let path = cache_path(&app)?;
+    if path.exists() {
+        fs::remove_file(&path).context("Failed to delete the size cache")?;
+    }
+    Ok(())
+}
+
+/// The modification time and number of entries of the folder `path`.
+fn stamp(path: &Path) -> Result<(DateTime<Utc>, usize)> {
+    let modified = fs::metadata(path)?.modified()?;
+    let entry_count = fs::read_dir(path)?.count();
+    Ok((modified.into(), entry_count))
+}
+
+fn read_cache(app: &AppHandle) -> Result<HashMap<String, CachedSize>> {
+    let path = cache_path(app)?;
+    if !path.exists() {
+        return Ok(HashMap::new());
+    }
+    let content =
+        fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(serde_json::from_str(&content)?)
+}
+
+fn write_cache(app: &AppHandle, entries: &HashMap<String, CachedSize>) -> Result<()> {
+    let payload = serde_json::to_string(entries)?;
+    crate::write_atomic(&cache_path(app)?, payload.as_bytes())
+}
+
+fn cache_path(app: &AppHandle) -> Result<PathBuf> {
+    Ok(crate::resolve_data_dir(app)?.join(SIZE_CACHE_FILE))
+}
@@ -0,0 +1,719 @@
+use crate::error::CommandError;
+use crate::size_cache;
+use crate::{GameEntry, Library};
+use anyhow::{anyhow, Context};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use tauri::{AppHandle, Manager, State};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+/// Entries `start_size_scan` walks between two `scan-progress` events.
+const SCAN_PROGRESS_INTERVAL: u64 = 2000;
+
+/// Paths `scan_paths_size` measures at the same time.
+const SCAN_WORKERS: usize = 4;
+
+/// Sent once a game's size has been measured in the background.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GameSizeUpdatedEvent {
+    id: String,
+    size_bytes: u64,
+}
+
+/// Sent while `start_size_scan` walks its path.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ScanProgress {
+    job_id: String,
+    files_seen: u64,
+    bytes: u64,
+    current_dir: String,
+}
+
+/// What a scan counted under one path.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanTally {
+    /// Bytes in the files that could be read.
+    total: u64,
+    files_seen: u64,
+    /// Entries skipped because reading them was not permitted.
+    denied: u64,
+    /// Entries skipped for any other error, such as a link loop.
+    unreadable: u64,
+    cancelled: bool,
+}
+
+/// Sent once a scan from `start_size_scan` ends, also when it was cancelled.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ScanComplete {
+    job_id: String,
+    #[serde(flatten)]
+    tally: ScanTally,
+}
+
+/// A path's result in `paths-scan-complete`: its size, or why it has none.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathSize {
+    size_bytes: Option<u64>,
+    error: Option<String>,
+    /// Another path of the batch this one lies in, whose size includes it.
+    within: Option<String>,
+}
+
+/// Sent once a scan from `scan_paths_size` ends, also when it was cancelled.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathsScanComplete {
+    job_id: String,
+    /// Keyed by the paths as they were given.
+    sizes: HashMap<String, PathSize>,
+    /// Combined size of the paths not within another, so nothing counts twice.
+    total_bytes: u64,
+    cancelled: bool,
+}
+
+/// Games whose size is being measured. Each game has at most one job; a
+/// request arriving while it runs only marks it to measure once more.
+#[derive(Debug, Default)]
+pub struct SizeJobs {
+    /// Whether each running job has been asked to measure again.
+    running: Mutex<HashMap<String, bool>>,
+    /// Set to stop the running `refresh_sizes`, if any.
+    refresh: Mutex<Option<Arc<AtomicBool>>>,
+    /// Running scan jobs, each with its flag to stop it.
+    scans: Mutex<HashMap<String, Arc<AtomicBool>>>,
+}
+
+impl SizeJobs {
+    /// Registers a scan job for `cancel_scan`, returning its id and the flag
+    /// that tells it to stop.
+    pub fn start_scan(&self) -> (String, Arc<AtomicBool>) {
+        let job_id = Uuid::new_v4().to_string();
+        let cancelled = Arc::new(AtomicBool::new(false));
+        self.scans
+            .lock()
+            .expect("size jobs poisoned")
+            .insert(job_id.clone(), cancelled.clone());
+        (job_id, cancelled)
+    }
+
+    pub fn finish_scan(&self, job_id: &str) {
+        self.scans
+            .lock()
+            .expect("size jobs poisoned")
+            .remove(job_id);
+    }
+}
+
+/// Sends `scan-progress` for a scan job every `SCAN_PROGRESS_INTERVAL`
+/// entries.
+pub struct ScanReporter<'a> {
+    app: &'a AppHandle,
+    job_id: &'a str,
+    entries: u64,
+}
+
+impl<'a> ScanReporter<'a> {
+    pub fn new(app: &'a AppHandle, job_id: &'a str) -> Self {
+        Self {
+            app,
+            job_id,
+            entries: 0,
+        }
+    }
+
+    /// Counts one more entry, reporting the totals so far when it is time.
+    pub fn entry(&mut self, files_seen: u64, bytes: u64, current_dir: &Path) {
+        self.entries += 1;
+        if self.entries.is_multiple_of(SCAN_PROGRESS_INTERVAL) {
+            let progress = ScanProgress {
+                job_id: self.job_id.to_string(),
+                files_seen,
+                bytes,
+                current_dir: current_dir.to_string_lossy().into_owned(),
+            };
+            let _ = self.app.emit_all("scan-progress", progress);
+        }
+    }
+}
+
+/// Whether a scan skipped an entry for lack of permission, rather than for
+/// another reason.
+pub fn is_denied(error: &walkdir::Error) -> bool {
+    error.io_error().map(io::Error::kind) == Some(io::ErrorKind::PermissionDenied)
+}
+
+#[derive(Debug, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SizeRefreshSummary {
+    /// Games whose recorded size changed.
+    updated: usize,
+    /// Combined size of every game measured.
+    total_bytes: u64,
+    /// Games whose archive or install folder does not exist.
+    missing: Vec<String>,
+    /// Stopped by `cancel_refresh` before every game was measured.
+    cancelled: bool,
+}
+
+/// The path a game's size is taken from: its archive, or else its install
+/// folder.
+fn measured_path(game: &GameEntry) -> Option<&str> {
+    game.archive_path
+        .as_deref()
+        .or(game.install_path.as_deref())
+}
+
+/// Measures `game` off the calling thread, so commands return without
+/// walking its folder. Does nothing for games without a path.
+pub fn measure(app: &AppHandle, game: &GameEntry) {
+    if measured_path(game).is_none() {
+        return;
+    }
+    {
+        let jobs = app.state::<SizeJobs>();
+        let mut running = jobs.running.lock().expect("size jobs poisoned");
+        if let Some(again) = running.get_mut(&game.id) {
+            *again = true;
+            return;
+        }
+        running.insert(game.id.clone(), false);
+    }
+
+    let app = app.clone();
+    let id = game.id.clone();
+    tauri::async_runtime::spawn_blocking(move || loop {
+        measure_now(&app, &id);
+        let jobs = app.state::<SizeJobs>();
+        let mut running = jobs.running.lock().expect("size jobs poisoned");
+        match running.get_mut(&id) {
+            Some(again) if *again => *again = false,
+            _ => {
+                running.remove(&id);
+                break;
+            }
+        }
+    });
+}
+
+/// Records the size of `id` as it is on disk now.
+fn measure_now(app: &AppHandle, id: &str) {
+    let path = app.state::<Library>().games(app).ok().and_then(|games| {
+        games
+            .iter()
+            .find(|game| game.id == id)
+            .and_then(measured_path)
+            .map(str::to_string)
+    });
+    if let Some(path) = path {
+        record_size(app, id, &path, false);
+    }
+}
+
+/// Measures `path` and records it as the size of `id`, sending
+/// `game-size-updated` when that changed it. Skips games that were removed,
+/// or whose path changed, while the folder was walked. Returns the size and
+/// whether it was recorded, or `None` when `path` could not be measured.
+/// Sizes come from the size cache unless `force` is set.
+fn record_size(app: &AppHandle, id: &str, path: &str, force: bool) -> Option<(u64, bool)> {
+    let size_bytes = size_cache::cached_size(app, Path::new(path), force).ok()?;
+    // Not an edit, so `updated_at` stays and pending edits do not conflict.
+    let recorded = app.state::<Library>().update(app, |games| {
+        let game = games
+            .iter_mut()
+            .find(|game| game.id == id && measured_path(game) == Some(path));
+        Ok(match game {
+            Some(game) if game.size_bytes != Some(size_bytes) => {
+                game.size_bytes = Some(size_bytes);
+                true
+            }
+            _ => false,
+        })
+    });
+    let recorded = matches!(recorded, Ok(true));
+    if recorded {
+        let event = GameSizeUpdatedEvent {
+            id: id.to_string(),
+            size_bytes,
+        };
+        let _ = app.emit_all("game-size-updated", event);
+    }
+    Some((size_bytes, recorded))
+}
+
+/// Measures the games in `ids`, or every game with an archive or install
+/// folder, and records their sizes. Runs in the background and sends
+/// `game-size-updated` for each size that changed; the library is only
+/// locked to write each result. Folders that look unchanged keep their
+/// cached size unless `force` is set. Only one refresh runs at a time, and
+/// `cancel_refresh` stops it after the game being measured.
+#[tauri::command]
+pub async fn refresh_sizes(
+    app: AppHandle,
+    library: State<'_, Library>,
+    jobs: State<'_, SizeJobs>,
+    ids: Option<Vec<String>>,
+    force: Option<bool>,
+) -> Result<SizeRefreshSummary, CommandError> {
+    let force = force.unwrap_or(false);
+    let targets: Vec<(String, String)> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .iter()
+        .filter(|game| ids.as_ref().is_none_or(|ids| ids.contains(&game.id)))
+        .filter_map(|game| Some((game.id.clone(), measured_path(game)?.to_string())))
+        .collect();
+
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut refresh = jobs.refresh.lock().expect("size jobs poisoned");
+        if refresh.is_some() {
+            return Err(CommandError::conflict("Sizes are already being refreshed"));
+        }
+        *refresh = Some(cancelled.clone());
+    }
+
+    let scan_app = app.clone();
+    let summary = tauri::async_runtime::spawn_blocking(move || {
+        let mut summary = SizeRefreshSummary::default();
+        for (id, path) in targets {
+            if cancelled.load(Ordering::Relaxed) {
+                summary.cancelled = true;
+                break;
+            }
+            if !Path::new(&path).exists() {
+                summary.missing.push(id);
+                continue;
+            }
+            if let Some((size_bytes, recorded)) = record_size(&scan_app, &id, &path, force) {
+                summary.total_bytes += size_bytes;
+                summary.updated += usize::from(recorded);
+            }
+        }
+        summary
+    })
+    .await;
+    *jobs.refresh.lock().expect("size jobs poisoned") = None;
+    Ok(summary?)
+}
+
+/// Stops the running `refresh_sizes`. Returns whether one was running.
+#[tauri::command]
+pub fn cancel_refresh(jobs: State<'_, SizeJobs>) -> Result<bool, CommandError> {
+    let refresh = jobs.refresh.lock().expect("size jobs poisoned");
+    if let Some(cancelled) = refresh.as_ref() {
+        cancelled.store(true, Ordering::Relaxed);
+    }
+    Ok(refresh.is_some())
+}
+
+/// Starts measuring `path` in the background and returns the job's id at
+/// once. Sends `scan-progress` every few thousand entries and
+/// `scan-complete` with the total at the end. Entries that cannot be read
+/// are counted and skipped rather than failing the scan. Links inside a
+/// folder are only followed with `follow_symlinks`. `scan_path_size` does
+/// the same in one call, for paths known to be small.
+#[tauri::command]
+pub fn start_size_scan(
+    app: AppHandle,
+    jobs: State<'_, SizeJobs>,
+    path: String,
+    follow_symlinks: Option<bool>,
+) -> Result<String, CommandError> {
+    let root = Path::new(&path).to_path_buf();
+    if !root.exists() {
+        return Err(CommandError::not_found(format!(
+            "Path does not exist: {path}"
+        )));
+    }
+    let (job_id, cancelled) = jobs.start_scan();
+
+    let id = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let follow_symlinks = follow_symlinks.unwrap_or(false);
+        let complete = walk(&app, &id, &root, follow_symlinks, &cancelled);
+        app.state::<SizeJobs>().finish_scan(&id);
+        let _ = app.emit_all("scan-complete", complete);
+    });
+    Ok(job_id)
+}
+
+/// Stops the scan `job_id`, from `start_size_scan` or `analyze_path`, which
+/// still sends its final event.
+#[tauri::command]
+pub fn cancel_scan(jobs: State<'_, SizeJobs>, job_id: String) -> Result<(), CommandError> {
+    let scans = jobs.scans.lock().expect("size jobs poisoned");
+    let cancelled = scans
+        .get(&job_id)
+        .ok_or_else(|| CommandError::not_found(format!("Scan {job_id} not found")))?;
+    cancelled.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+fn walk(
+    app: &AppHandle,
+    job_id: &str,
+    root: &Path,
+    follow_symlinks: bool,
+    cancelled: &AtomicBool,
+) -> ScanComplete {
+    let mut reporter = ScanReporter::new(app, job_id);
+    let tally = tally(root, follow_symlinks, cancelled, |tally, dir| {
+        reporter.entry(tally.files_seen, tally.total, dir);
+    });
+    ScanComplete {
+        job_id: job_id.to_string(),
+        tally,
+    }
+}
+
+/// Counts the files under `root`, skipping entries that cannot be read.
+/// `on_entry` gets the counts so far and the folder being walked before
+/// each entry.
+fn tally(
+    root: &Path,
+    follow_symlinks: bool,
+    cancelled: &AtomicBool,
+    mut on_entry: impl FnMut(&ScanTally, &Path),
+) -> ScanTally {
+    let mut tally = ScanTally::default();
+    let mut current_dir = root.to_path_buf();
+    for visit in SizeWalk::new(root, follow_symlinks) {
+        if cancelled.load(Ordering::Relaxed) {
+            tally.cancelled = true;
+            break;
+        }
+        on_entry(&tally, &current_dir);
+
+        match visit {
+            Ok(Visit::File { len, .. }) => {
+                tally.files_seen += 1;
+                tally.total += len;
+            }
+            Ok(Visit::Dir(dir)) => current_dir = dir,
+            Ok(Visit::Link(_) | Visit::Other) => {}
+            Err(error) if is_denied(&error) => tally.denied += 1,
+            Err(_) => tally.unreadable += 1,
+        }
+    }
+    tally
+}
+
+/// Starts measuring several paths at once in the background, a few at a
+/// time, and returns one job id for the batch. Sends `scan-progress` with
+/// the batch's totals and `paths-scan-complete` with a result per path,
+/// so a missing path fails alone. A path given twice is measured once, and
+/// a path inside another one is marked as such and left out of the total.
+/// `cancel_scan` stops the batch.
+#[tauri::command]
+pub fn scan_paths_size(
+    app: AppHandle,
+    jobs: State<'_, SizeJobs>,
+    paths: Vec<String>,
+    follow_symlinks: Option<bool>,
+) -> Result<String, CommandError> {
+    let (job_id, cancelled) = jobs.start_scan();
+
+    let id = job_id.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let follow_symlinks = follow_symlinks.unwrap_or(false);
+        let complete = scan_batch(&app, &id, paths, follow_symlinks, &cancelled);
+        app.state::<SizeJobs>().finish_scan(&id);
+        let _ = app.emit_all("paths-scan-complete", complete);
+    });
+    Ok(job_id)
+}
+
+fn scan_batch(
+    app: &AppHandle,
+    job_id: &str,
+    paths: Vec<String>,
+    follow_symlinks: bool,
+    cancelled: &AtomicBool,
+) -> PathsScanComplete {
+    // Resolved, so that differently spelled and linked paths compare equal.
+    let resolved: Vec<(String, Option<PathBuf>)> = paths
+        .into_iter()
+        .map(|path| {
+            let target = fs::canonicalize(path.trim()).ok();
+            (path, target)
+        })
+        .collect();
+    let mut unique: Vec<PathBuf> = resolved
+        .iter()
+        .filter_map(|(_, target)| target.clone())
+        .collect();
+    unique.sort();
+    unique.dedup();
+
+    let next = AtomicUsize::new(0);
+    let (files_seen, bytes) = (AtomicU64::new(0), AtomicU64::new(0));
+    let measured: Mutex<HashMap<PathBuf, ScanTally>> = Mutex::new(HashMap::new());
+    thread::scope(|scope| {
+        for _ in 0..SCAN_WORKERS.min(unique.len()) {
+            scope.spawn(|| {
+                let mut reporter = ScanReporter::new(app, job_id);
+                while let Some(path) = unique.get(next.fetch_add(1, Ordering::Relaxed)) {
+                    let mut counted = ScanTally::default();
+                    let result = tally(path, follow_symlinks, cancelled, |tally, dir| {
+                        files_seen
+                            .fetch_add(tally.files_seen - counted.files_seen, Ordering::Relaxed);
+                        bytes.fetch_add(tally.total - counted.total, Ordering::Relaxed);
+                        counted = *tally;
+                        reporter.entry(
+                            files_seen.load(Ordering::Relaxed),
+                            bytes.load(Ordering::Relaxed),
+                            dir,
+                        );
+                    });
+                    measured
+                        .lock()
+                        .expect("size jobs poisoned")
+                        .insert(path.clone(), result);
+                }
+            });
+        }
+    });
+    let measured = measured.into_inner().expect("size jobs poisoned");
+    // Paths are reported as given rather than resolved.
+    let given: HashMap<&PathBuf, &String> = resolved
+        .iter()
+        .rev()
+        .filter_map(|(path, target)| Some((target.as_ref()?, path)))
+        .collect();
+
+    let within = |path: &PathBuf| {
+        unique
+            .iter()
+            .find(|other| *other != path && path.starts_with(other))
+    };
+    let total_bytes = measured
+        .iter()
+        .filter(|(path, tally)| within(path).is_none() && !tally.cancelled)
+        .map(|(_, tally)| tally.total)
+        .sum();
+    let sizes = resolved
+        .iter()
+        .map(|(path, target)| {
+            let tally = target.as_ref().and_then(|target| measured.get(target));
+            let error = match (target, tally) {
+                (None, _) => Some(format!("Path does not exist: {path}")),
+                (Some(_), Some(tally)) if !tally.cancelled => None,
+                (Some(_), _) => Some("The scan was cancelled".to_string()),
+            };
+            let size = PathSize {
+                size_bytes: tally.filter(|_| error.is_none()).map(|tally| tally.total),
+                error,
+                within: target
+                    .as_ref()
+                    .and_then(within)
+                    .and_then(|outer| given.get(outer))
+                    .map(|outer| outer.to_string()),
+            };
+            (path.clone(), size)
+        })
+        .collect();
+
+    PathsScanComplete {
+        job_id: job_id.to_string(),
+        sizes,
+        total_bytes,
+        cancelled: cancelled.load(Ordering::Relaxed),
+    }
+}
+
+/// Size of the file or folder at `path`. Within a folder, links are only
+/// followed with `follow_symlinks`, and a folder reached twice through them
+/// is counted once, as is a file with several hard links.
+pub fn path_size(path: &Path, follow_symlinks: bool) -> anyhow::Result<u64> {
+    if path.is_file() {
+        return Ok(fs::metadata(path)?.len());
+    }
+    if !path.is_dir() {
+        return Err(anyhow!("Unsupported path type"));
+    }
+    let mut total = 0;
+    for visit in SizeWalk::new(path, follow_symlinks) {
+        match visit {
+            Ok(Visit::File { len, .. }) => total += len,
+            Ok(_) => {}
+            // A link back up the tree holds nothing not already counted.
+            Err(error) if error.loop_ancestor().is_some() => {}
+            Err(error) => return Err(error.into()),
+        }
+    }
+    Ok(total)
+}
+
+/// What [`SizeWalk`] came across.
+pub enum Visit {
+    /// A file not counted before, with its size.
+    File {
+        path: PathBuf,
+        len: u64,
+    },
+    Dir(PathBuf),
+    /// A link that is not followed.
+    Link(PathBuf),
+    /// Anything else, which takes no space of its own.
+    Other,
+}
+
+/// Walks a folder for its size without counting anything twice: folders
+/// already walked are skipped, as are further hard links to files already
+/// seen. `walkdir` reports links pointing back up the tree as errors.
+pub struct SizeWalk {
+    entries: walkdir::IntoIter,
+    follow_symlinks: bool,
+    dirs: HashSet<FileId>,
+    files: HashSet<FileId>,
+}
+
+impl SizeWalk {
+    pub fn new(root: &Path, follow_symlinks: bool) -> Self {
+        Self {
+            entries: WalkDir::new(root).follow_links(follow_symlinks).into_iter(),
+            follow_symlinks,
+            dirs: HashSet::new(),
+            files: HashSet::new(),
+        }
+    }
+}
+
+impl Iterator for SizeWalk {
+    type Item = Result<Visit, walkdir::Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(error) => return Some(Err(error)),
+            };
+            let file_type = entry.file_type();
+            if file_type.is_dir() {
+                // Without following links, every folder is reached only once.
+                if self.follow_symlinks {
+                    if let Some(id) = dir_id(&entry) {
+                        if !self.dirs.insert(id) {
+                            self.entries.skip_current_dir();
+                            continue;
+                        }
+                    }
+                }
+                return Some(Ok(Visit::Dir(entry.into_path())));
+            }
+            if file_type.is_symlink() {
+                return Some(Ok(Visit::Link(entry.into_path())));
+            }
+            if !file_type.is_file() {
+                return Some(Ok(Visit::Other));
+            }
+            let metadata = match entry.metadata() {
+                Ok(metadata) => metadata,
+                Err(error) => return Some(Err(error)),
+            };
+            if first_link(&mut self.files, &metadata) {
+                let len = metadata.len();
+                return Some(Ok(Visit::File {
+                    path: entry.into_path(),
+                    len,
+                }));
+            }
+        }
+    }
+}
+
+#[cfg(unix)]
+type FileId = (u64, u64);
+
+/// Windows has no stable file id in `std`, so folders are told apart by
+/// their resolved path, which sees through links and junctions alike.
+#[cfg(not(unix))]
+type FileId = PathBuf;
+
+#[cfg(unix)]
+fn dir_id(entry: &walkdir::DirEntry) -> Option<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let metadata = entry.metadata().ok()?;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_id(entry: &walkdir::DirEntry) -> Option<FileId> {
+    fs::canonicalize(entry.path()).ok()
+}
+
+/// Whether `metadata` is the first link to its file in `seen`.
+#[cfg(unix)]
+fn first_link(seen: &mut HashSet<FileId>, metadata: &fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    metadata.nlink() <= 1 || seen.insert((metadata.dev(), metadata.ino()))
+}
+
+/// Hard links go uncounted here; they are rare on Windows.
+#[cfg(not(unix))]
+fn first_link(_seen: &mut HashSet<FileId>, _metadata: &fs::Metadata) -> bool {
+    true
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// A folder holding `game.bin` of 100 bytes and `data/pak.bin` of 50.
+    fn game_folder() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("game.bin"), [0; 100]).unwrap();
+        fs::create_dir(dir.path().join("data")).unwrap();
+        fs::write(dir.path().join("data/pak.bin"), [0; 50]).unwrap();
+        dir
+    }
+
+    #[test]
+    fn a_link_loop_ends_the_walk() {
+        let dir = game_folder();
+        symlink(dir.path(), dir.path().join("data/loop")).unwrap();
+
+        assert_eq!(path_size(dir.path(), true).unwrap(), 150);
+        assert_eq!(path_size(dir.path(), false).unwrap(), 150);
+        let tally = tally(dir.path(), true, &AtomicBool::new(false), |_, _| {});
+        assert_eq!(tally.total, 150);
+        assert_eq!(tally.files_seen, 2);
+    }
+
+    #[test]
+    fn a_folder_linked_twice_is_counted_once() {
+        let dir = game_folder();
+        symlink(dir.path().join("data"), dir.path().join("data-a")).unwrap();
+        symlink(dir.path().join("data"), dir.path().join("data-b")).unwrap();
+
+        assert_eq!(path_size(dir.path(), true).unwrap(), 150);
+    }
+
+    #[test]
+    fn a_hard_linked_file_is_counted_once() {
+        let dir = game_folder();
+        fs::hard_link(
+            dir.path().join("game.bin"),
+            dir.path().join("data/game.bin"),
+        )
+        .unwrap();
+
+        assert_eq!(path_size(dir.path(), false).unwrap(), 150);
+        assert_eq!(path_size(dir.path(), true).unwrap(), 150);
+    }
+}
@@ -0,0 +1,113 @@
+use crate::error::CommandError;
+use crate::repackers::StatusCounts;
+use crate::{InstallStatus, Library};
+use anyhow::Context;
+use chrono::{Datelike, Months, NaiveDate, Utc};
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+use tauri::{AppHandle, State};
+
+/// Games listed in `LibraryStats::largest`.
+const LARGEST_COUNT: usize = 10;
+/// Months covered by `LibraryStats::added_per_month`, this one included.
+const MONTHS_SHOWN: u32 = 12;
+
+/// Everything the stats page shows, aggregated over the library.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LibraryStats {
+    total_games: usize,
+    by_status: StatusCounts,
+    installed_size_bytes: u64,
+    archived_size_bytes: u64,
+    /// Games whose size was never measured, left out of the sums above.
+    unknown_size_count: usize,
+    largest: Vec<GameSize>,
+    untagged_count: usize,
+    /// Oldest month first, including months without additions.
+    added_per_month: Vec<MonthCount>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameSize {
+    id: String,
+    title: String,
+    size_bytes: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MonthCount {
+    /// `YYYY-MM`, in UTC.
+    month: String,
+    count: usize,
+}
+
+#[tauri::command]
+pub fn library_stats(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<LibraryStats, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+
+    let mut by_status = StatusCounts::default();
+    let (mut installed_size_bytes, mut archived_size_bytes) = (0, 0);
+    for game in &games {
+        by_status.add(&game.status);
+        match (&game.status, game.size_bytes) {
+            (InstallStatus::Installed, Some(size)) => installed_size_bytes += size,
+            (InstallStatus::Archived, Some(size)) => archived_size_bytes += size,
+            _ => {}
+        }
+    }
+
+    let mut largest: Vec<GameSize> = games
+        .iter()
+        .filter_map(|game| {
+            Some(GameSize {
+                id: game.id.clone(),
+                title: game.title.clone(),
+                size_bytes: game.size_bytes?,
+            })
+        })
+        .collect();
+    largest.sort_by_key(|game| Reverse(game.size_bytes));
+    largest.truncate(LARGEST_COUNT);
+
+    let this_month = Utc::now()
+        .date_naive()
+        .with_day(1)
+        .expect("every month has a first day");
+    let mut added: BTreeMap<NaiveDate, usize> = (0..MONTHS_SHOWN)
+        .filter_map(|back| this_month.checked_sub_months(Months::new(back)))
+        .map(|month| (month, 0))
+        .collect();
+    for game in &games {
+        let month = game.added_at.date_naive().with_day(1);
+        if let Some(count) = month.and_then(|month| added.get_mut(&month)) {
+            *count += 1;
+        }
+    }
+
+    Ok(LibraryStats {
+        total_games: games.len(),
+        by_status,
+        installed_size_bytes,
+        archived_size_bytes,
+        unknown_size_count: games
+            .iter()
+            .filter(|game| game.size_bytes.is_none())
+            .count(),
+        largest,
+        untagged_count: games.iter().filter(|game| game.tags.is_empty()).count(),
+        added_per_month: added
+            .into_iter()
+            .map(|(month, count)| MonthCount {
+                month: month.format("%Y-%m").to_string(),
+                count,
+            })
+            .collect(),
+    })
+}
@@ -0,0 +1,398 @@
+use crate::error::CommandError;
+use crate::{search, GameEntry, GamePayload, InstallStatus, Library};
+use anyhow::{anyhow, Context, Result};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+
+/// Custom field holding the Steam app id of games imported from Steam.
+/// Games carrying it are launched through Steam.
+pub const APP_ID_FIELD: &str = "Steam App ID";
+/// Set in an app manifest's `StateFlags` once the app is fully installed.
+const FULLY_INSTALLED: u32 = 4;
+/// Apps Steam installs alongside games rather than games themselves:
+/// Steamworks Common Redistributables and the Proton and runtime tools.
+const IGNORED_APP_IDS: &[&str] = &["228980", "1070560", "1391110", "1628350"];
+const IGNORED_NAME_PREFIXES: &[&str] = &["Proton ", "Steam Linux Runtime", "Steamworks"];
+
+/// Proposes a game for every app installed in the local Steam libraries,
+/// with its install folder, size and app id (as the `Steam App ID` custom
+/// field). Apps whose install folder or app id the library already has,
+/// also in the trash, are left out. Nothing is added; send the accepted
+/// proposals to `add_games`.
+#[tauri::command]
+pub async fn import_from_steam(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<Vec<GamePayload>, CommandError> {
+    let known: Vec<GameEntry> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .chain(library.trash(&app).context("Failed to load library")?)
+        .collect();
+    let known_paths: HashSet<PathBuf> = known
+        .iter()
+        .filter_map(|game| game.install_path.as_deref().map(PathBuf::from))
+        .collect();
+    let known_app_ids: HashSet<String> = known
+        .iter()
+        .filter_map(|game| app_id(game).map(str::to_string))
+        .collect();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let root = steam_root().ok_or_else(|| anyhow!("Steam does not seem to be installed"))?;
+        let mut proposals: Vec<GamePayload> = installed_apps(&root)?
+            .into_iter()
+            .filter(|proposal| {
+                proposal
+                    .install_path
+                    .as_deref()
+                    .is_some_and(|path| !known_paths.contains(Path::new(path)))
+                    && proposal
+                        .custom_fields
+                        .get(APP_ID_FIELD)
+                        .is_some_and(|id| !known_app_ids.contains(id))
+            })
+            .collect();
+        proposals.sort_by_key(|proposal| search::normalize(&proposal.title));
+        anyhow::Ok(proposals)
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+/// The Steam app id of a game imported from Steam.
+pub fn app_id(game: &GameEntry) -> Option<&str> {
+    game.custom_fields
+        .get(APP_ID_FIELD)
+        .map(|id| id.trim())
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_digit()))
+}
+
+/// Asks Steam to start the app. Steam runs the game itself, so there is no
+/// process for the launcher to follow.
+pub fn run(app: &AppHandle, app_id: &str) -> Result<()> {
+    tauri::api::shell::open(
+        &app.shell_scope(),
+        format!("steam://rungameid/{app_id}"),
+        None,
+    )
+    .map_err(|error| anyhow!("Failed to start Steam: {error}"))
+}
+
+/// Apps in every Steam library, one payload each. Apps still downloading
+/// or whose folder is gone are skipped.
+fn installed_apps(root: &Path) -> Result<Vec<GamePayload>> {
+    let mut payloads = Vec::new();
+    for library in library_folders(root)? {
+        let steamapps = library.join("steamapps");
+        let Ok(entries) = fs::read_dir(&steamapps) else {
+            continue;
+        };
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !(name.starts_with("appmanifest_") && name.ends_with(".acf")) {
+                continue;
+            }
+            // A damaged manifest only loses its own app.
+            if let Ok(Some(payload)) = read_manifest(&entry.path(), &steamapps) {
+                payloads.push(payload);
+            }
+        }
+    }
+    Ok(payloads)
+}
+
+/// The library folders listed in `libraryfolders.vdf`, the Steam folder
+/// itself first. Both the current layout, with a `path` per library, and
+/// the older one mapping numbers to paths are understood.
+fn library_folders(root: &Path) -> Result<Vec<PathBuf>> {
+    let mut folders = vec![root.to_path_buf()];
+    let file = root.join("steamapps").join("libraryfolders.vdf");
+    if !file.is_file() {
+        return Ok(folders);
+    }
+    let text =
+        fs::read_to_string(&file).with_context(|| format!("Failed to read {}", file.display()))?;
+    let document = parse(&text).with_context(|| format!("Failed to parse {}", file.display()))?;
+    let Some(libraries) = document.get("libraryfolders").and_then(Value::entries) else {
+        return Ok(folders);
+    };
+    for (key, library) in libraries {
+        let path = match library {
+            Value::Object(_) => library.get("path").and_then(Value::as_str),
+            Value::String(path) if key.chars().all(|c| c.is_ascii_digit()) => Some(path.as_str()),
+            Value::String(_) => None,
+        };
+        if let Some(path) = path.map(PathBuf::from) {
+            if path.is_dir() && !folders.contains(&path) {
+                folders.push(path);
+            }
+        }
+    }
+    Ok(folders)
+}
+
+fn read_manifest(manifest: &Path, steamapps: &Path) -> Result<Option<GamePayload>> {
+    let document = parse(&fs::read_to_string(manifest)?)?;
+    let state = document
+        .get("AppState")
+        .ok_or_else(|| anyhow!("No AppState"))?;
+    let field = |key: &str| state.get(key).and_then(Value::as_str).map(str::trim);
+    let (Some(app_id), Some(name), Some(install_dir)) =
+        (field("appid"), field("name"), field("installdir"))
+    else {
+        return Ok(None);
+    };
+    let flags: u32 = field("StateFlags")
+        .and_then(|flags| flags.parse().ok())
+        .unwrap_or(0);
+    if flags & FULLY_INSTALLED == 0
+        || IGNORED_APP_IDS.contains(&app_id)
+        || IGNORED_NAME_PREFIXES
+            .iter()
+            .any(|prefix| name.starts_with(prefix))
+    {
+        return Ok(None);
+    }
+    let install_path = steamapps.join("common").join(install_dir);
+    if !install_path.is_dir() {
+        return Ok(None);
+    }
+
+    Ok(Some(GamePayload {
+        title: name.to_string(),
+        install_path: Some(install_path.to_string_lossy().into_owned()),
+        size_override: field("SizeOnDisk")
+            .and_then(|size| size.parse().ok())
+            .map(Some),
+        status: InstallStatus::Installed,
+        custom_fields: BTreeMap::from([(APP_ID_FIELD.to_string(), app_id.to_string())]),
+        ..GamePayload::default()
+    }))
+}
+
+/// Where Steam is installed: from the registry on Windows, otherwise the
+/// usual folders, including the Flatpak one.
+fn steam_root() -> Option<PathBuf> {
+    candidate_roots()
+        .into_iter()
+        .find(|root| root.join("steamapps").is_dir())
+}
+
+#[cfg(windows)]
+fn candidate_roots() -> Vec<PathBuf> {
+    let mut roots: Vec<PathBuf> = windows::registry_root().into_iter().collect();
+    roots.push(PathBuf::from(r"C:\Program Files (x86)\Steam"));
+    roots
+}
+
+#[cfg(not(windows))]
+fn candidate_roots() -> Vec<PathBuf> {
+    let Some(home) = tauri::api::path::home_dir() else {
+        return Vec::new();
+    };
+    [
+        ".steam/steam",
+        ".local/share/Steam",
+        ".var/app/com.valvesoftware.Steam/.local/share/Steam",
+        "Library/Application Support/Steam",
+    ]
+    .iter()
+    .map(|path| home.join(path))
+    .collect()
+}
+
+/// A node of Valve's KeyValues text format, used by Steam's `.vdf` and
+/// `.acf` files.
+#[derive(Debug)]
+enum Value {
+    String(String),
+    Object(Vec<(String, Value)>),
+}
+
+impl Value {
+    /// The first child named `key`, ignoring case as Steam does.
+    fn get(&self, key: &str) -> Option<&Value> {
+        self.entries()?
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(key))
+            .map(|(_, value)| value)
+    }
+
+    fn entries(&self) -> Option<&[(String, Value)]> {
+        match self {
+            Self::Object(entries) => Some(entries),
+            Self::String(_) => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Self::String(value) => Some(value),
+            Self::Object(_) => None,
+        }
+    }
+}
+
+/// Parses a KeyValues document into an object holding its top-level keys.
+fn parse(text: &str) -> Result<Value> {
+    let mut tokens = Tokens {
+        chars: text.chars().peekable(),
+    };
+    let entries = parse_entries(&mut tokens, false)?;
+    Ok(Value::Object(entries))
+}
+
+fn parse_entries(tokens: &mut Tokens<'_>, nested: bool) -> Result<Vec<(String, Value)>> {
+    let mut entries = Vec::new();
+    loop {
+        let key = match tokens.next_token()? {
+            None if nested => return Err(anyhow!("Unclosed brace")),
+            None => return Ok(entries),
+            Some(Token::Close) if nested => return Ok(entries),
+            Some(Token::Close) => return Err(anyhow!("Unexpected closing brace")),
+            Some(Token::Open) => return Err(anyhow!("Expected a key")),
+            Some(Token::Text(key)) => key,
+        };
+        let value = match tokens.next_token()? {
+            Some(Token::Text(value)) => Value::String(value),
+            Some(Token::Open) => Value::Object(parse_entries(tokens, true)?),
+            _ => return Err(anyhow!("No value for {key}")),
+        };
+        entries.push((key, value));
+    }
+}
+
+enum Token {
+    Text(String),
+    Open,
+    Close,
+}
+
+struct Tokens<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+}
+
+impl Tokens<'_> {
+    fn next_token(&mut self) -> Result<Option<Token>> {
+        loop {
+            match self.chars.peek() {
+                None => return Ok(None),
+                Some(c) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('/') => {
+                    // Comments run to the end of the line.
+                    while self.chars.next_if(|c| *c != '\n').is_some() {}
+                }
+                // Conditions such as [$WIN32] are ignored.
+                Some('[') => while self.chars.next().is_some_and(|c| c != ']') {},
+                Some('{') => {
+                    self.chars.next();
+                    return Ok(Some(Token::Open));
+                }
+                Some('}') => {
+                    self.chars.next();
+                    return Ok(Some(Token::Close));
+                }
+                Some('"') => {
+                    self.chars.next();
+                    return self.quoted().map(|text| Some(Token::Text(text)));
+                }
+                Some(_) => {
+                    let mut text = String::new();
+                    while let Some(c) = self
+                        .chars
+                        .next_if(|c| !c.is_whitespace() && !matches!(c, '"' | '{' | '}'))
+                    {
+                        text.push(c);
+                    }
+                    return Ok(Some(Token::Text(text)));
+                }
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String> {
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err(anyhow!("Unclosed quote")),
+                Some('"') => return Ok(text),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(c) => text.push(c),
+                    None => return Err(anyhow!("Unclosed quote")),
+                },
+                Some(c) => text.push(c),
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::iter;
+    use std::path::PathBuf;
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, HKEY, HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE, RRF_RT_REG_SZ,
+    };
+
+    /// Steam's folder as recorded by its installer: per user first, then
+    /// machine-wide.
+    pub fn registry_root() -> Option<PathBuf> {
+        read_string(HKEY_CURRENT_USER, r"Software\Valve\Steam", "SteamPath")
+            .or_else(|| {
+                read_string(
+                    HKEY_LOCAL_MACHINE,
+                    r"SOFTWARE\WOW6432Node\Valve\Steam",
+                    "InstallPath",
+                )
+            })
+            .map(PathBuf::from)
+    }
+
+    fn read_string(root: HKEY, key: &str, value: &str) -> Option<String> {
+        let wide = |text: &str| -> Vec<u16> { text.encode_utf16().chain(iter::once(0)).collect() };
+        let (key, value) = (wide(key), wide(value));
+        let mut size = 0u32;
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                key.as_ptr(),
+                value.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                std::ptr::null_mut(),
+                &mut size,
+            )
+        };
+        if status != 0 || size == 0 {
+            return None;
+        }
+        let mut buffer = vec![0u16; size as usize / 2];
+        let status = unsafe {
+            RegGetValueW(
+                root,
+                key.as_ptr(),
+                value.as_ptr(),
+                RRF_RT_REG_SZ,
+                std::ptr::null_mut(),
+                buffer.as_mut_ptr().cast(),
+                &mut size,
+            )
+        };
+        if status != 0 {
+            return None;
+        }
+        let len = buffer
+            .iter()
+            .position(|unit| *unit == 0)
+            .unwrap_or(buffer.len());
+        Some(String::from_utf16_lossy(&buffer[..len]))
+    }
+}
@@ -0,0 +1,323 @@
+use crate::error::CommandError;
+use crate::{search, GamePayload, InstallStatus, Library};
+use anyhow::{Context, Result};
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use tauri::{AppHandle, State};
+
+/// Custom field holding the GOG product id of games imported from GOG Galaxy.
+pub const GOG_ID_FIELD: &str = "GOG Product ID";
+/// Custom field holding the Epic app name of games imported from Epic.
+pub const EPIC_ID_FIELD: &str = "Epic App Name";
+
+/// Games found in a store's records, ready for `add_games`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StoreImport {
+    proposals: Vec<GamePayload>,
+    /// Why nothing could be read, such as the store not being installed.
+    note: Option<String>,
+}
+
+impl StoreImport {
+    fn note(note: impl Into<String>) -> Self {
+        Self {
+            proposals: Vec::new(),
+            note: Some(note.into()),
+        }
+    }
+}
+
+/// The parts of a `goggame-<id>.info` file that are used.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GogInfo {
+    name: Option<String>,
+    #[serde(default)]
+    play_tasks: Vec<GogPlayTask>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GogPlayTask {
+    #[serde(default)]
+    is_primary: bool,
+    #[serde(rename = "type")]
+    kind: Option<String>,
+    path: Option<String>,
+    working_dir: Option<String>,
+    arguments: Option<String>,
+}
+
+/// The parts of an Epic `.item` manifest that are used.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct EpicManifest {
+    display_name: Option<String>,
+    install_location: Option<String>,
+    launch_executable: Option<String>,
+    launch_command: Option<String>,
+    app_name: Option<String>,
+    main_game_app_name: Option<String>,
+    #[serde(default, rename = "bIsIncompleteInstall")]
+    is_incomplete_install: bool,
+    install_size: Option<u64>,
+}
+
+/// Proposes a game for every product GOG Galaxy has installed, read from
+/// Galaxy's database and the `goggame-<id>.info` file in each install
+/// folder, which names the executable. Without Galaxy the list is empty and
+/// `note` says why. Folders the library already uses as an install path,
+/// also in the trash, are left out.
+#[tauri::command]
+pub async fn import_from_gog(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<StoreImport, CommandError> {
+    let known = known_install_paths(&app, &library)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(database) = gog_database().filter(|path| path.is_file()) else {
+            return Ok(StoreImport::note(
+                "GOG Galaxy does not seem to be installed",
+            ));
+        };
+        let proposals = gog_installed(&database)?;
+        anyhow::Ok(finish(proposals, &known))
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+/// Proposes a game for every app the Epic Games Launcher has installed, read
+/// from its `.item` manifests. Add-ons and unfinished installs are skipped.
+/// Without the launcher the list is empty and `note` says why. Folders the
+/// library already uses as an install path, also in the trash, are left out.
+#[tauri::command]
+pub async fn import_from_epic(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<StoreImport, CommandError> {
+    let known = known_install_paths(&app, &library)?;
+    tauri::async_runtime::spawn_blocking(move || {
+        let Some(manifests) = epic_manifests_dir().filter(|path| path.is_dir()) else {
+            return Ok(StoreImport::note(
+                "The Epic Games Launcher does not seem to be installed",
+            ));
+        };
+        let proposals = epic_installed(&manifests)?;
+        anyhow::Ok(finish(proposals, &known))
+    })
+    .await?
+    .map_err(CommandError::from)
+}
+
+fn known_install_paths(
+    app: &AppHandle,
+    library: &Library,
+) -> Result<HashSet<PathBuf>, CommandError> {
+    Ok(library
+        .games(app)
+        .context("Failed to load library")?
+        .into_iter()
+        .chain(library.trash(app).context("Failed to load library")?)
+        .filter_map(|game| game.install_path.map(PathBuf::from))
+        .collect())
+}
+
+/// Drops proposals for known install folders and sorts the rest by title.
+fn finish(proposals: Vec<GamePayload>, known: &HashSet<PathBuf>) -> StoreImport {
+    let mut proposals: Vec<GamePayload> = proposals
+        .into_iter()
+        .filter(|proposal| {
+            proposal
+                .install_path
+                .as_deref()
+                .is_some_and(|path| !known.contains(Path::new(path)))
+        })
+        .collect();
+    proposals.sort_by_key(|proposal| search::normalize(&proposal.title));
+    StoreImport {
+        proposals,
+        note: None,
+    }
+}
+
+fn gog_installed(database: &Path) -> Result<Vec<GamePayload>> {
+    // Galaxy may be running, so the database is only ever read.
+    let conn = Connection::open_with_flags(database, OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .with_context(|| format!("Failed to open {}", database.display()))?;
+    let mut statement = conn.prepare(
+        "SELECT installed.productId, installed.installationPath, details.title
+         FROM InstalledBaseProducts AS installed
+         LEFT JOIN LimitedDetails AS details ON details.productId = installed.productId",
+    )?;
+    let rows = statement
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, Option<String>>(2)?,
+            ))
+        })?
+        .collect::<rusqlite::Result<Vec<_>>>()
+        .with_context(|| format!("Failed to read {}", database.display()))?;
+
+    let mut proposals = Vec::new();
+    let mut seen = HashSet::new();
+    for (product_id, install_path, title) in rows {
+        let folder = PathBuf::from(install_path);
+        if !folder.is_dir() || !seen.insert(product_id) {
+            continue;
+        }
+        let info = read_gog_info(&folder, product_id);
+        let task = info.as_ref().and_then(|info| {
+            let files = || {
+                info.play_tasks
+                    .iter()
+                    .filter(|task| task.kind.as_deref() == Some("FileTask"))
+            };
+            files()
+                .find(|task| task.is_primary)
+                .or_else(|| files().next())
+        });
+        let executable = task
+            .and_then(|task| task.path.as_deref())
+            .map(|path| folder.join(path))
+            .filter(|path| path.is_file());
+        let title = info
+            .as_ref()
+            .and_then(|info| crate::non_empty(info.name.clone()?))
+            .or_else(|| crate::non_empty(title?))
+            .unwrap_or_else(|| folder_name(&folder));
+
+        proposals.push(GamePayload {
+            title,
+            install_path: Some(folder.to_string_lossy().into_owned()),
+            executable_path: executable.map(|path| path.to_string_lossy().into_owned()),
+            working_dir: task
+                .and_then(|task| task.working_dir.as_deref())
+                .filter(|dir| !dir.is_empty())
+                .map(|dir| folder.join(dir).to_string_lossy().into_owned()),
+            launch_args: task.and_then(|task| crate::non_empty(task.arguments.clone()?)),
+            status: InstallStatus::Installed,
+            custom_fields: BTreeMap::from([(GOG_ID_FIELD.to_string(), product_id.to_string())]),
+            ..GamePayload::default()
+        });
+    }
+    Ok(proposals)
+}
+
+fn read_gog_info(folder: &Path, product_id: i64) -> Option<GogInfo> {
+    let text = fs::read_to_string(folder.join(format!("goggame-{product_id}.info"))).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn epic_installed(manifests: &Path) -> Result<Vec<GamePayload>> {
+    let mut proposals = Vec::new();
+    for entry in fs::read_dir(manifests)
+        .with_context(|| format!("Failed to read {}", manifests.display()))?
+    {
+        let path = entry?.path();
+        if path.extension().is_none_or(|extension| extension != "item") {
+            continue;
+        }
+        // A damaged manifest only loses its own game.
+        let Some(manifest) = fs::read_to_string(&path)
+            .ok()
+            .and_then(|text| serde_json::from_str::<EpicManifest>(&text).ok())
+        else {
+            continue;
+        };
+        if let Some(proposal) = epic_proposal(manifest) {
+            proposals.push(proposal);
+        }
+    }
+    Ok(proposals)
+}
+
+fn epic_proposal(manifest: EpicManifest) -> Option<GamePayload> {
+    let app_name = crate::non_empty(manifest.app_name?)?;
+    let is_add_on = manifest
+        .main_game_app_name
+        .as_deref()
+        .is_some_and(|main| main != app_name);
+    if manifest.is_incomplete_install || is_add_on {
+        return None;
+    }
+    let folder = PathBuf::from(manifest.install_location?);
+    if !folder.is_dir() {
+        return None;
+    }
+    let executable = manifest
+        .launch_executable
+        .as_deref()
+        .filter(|path| !path.is_empty())
+        .map(|path| folder.join(path))
+        .filter(|path| path.is_file());
+
+    Some(GamePayload {
+        title: manifest
+            .display_name
+            .and_then(crate::non_empty)
+            .unwrap_or_else(|| folder_name(&folder)),
+        install_path: Some(folder.to_string_lossy().into_owned()),
+        executable_path: executable.map(|path| path.to_string_lossy().into_owned()),
+        launch_args: manifest.launch_command.and_then(crate::non_empty),
+        size_override: manifest.install_size.filter(|size| *size > 0).map(Some),
+        status: InstallStatus::Installed,
+        custom_fields: BTreeMap::from([(EPIC_ID_FIELD.to_string(), app_name)]),
+        ..GamePayload::default()
+    })
+}
+
+fn folder_name(folder: &Path) -> String {
+    folder
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| folder.to_string_lossy().into_owned())
+}
+
+#[cfg(windows)]
+fn program_data() -> PathBuf {
+    std::env::var_os("ProgramData")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from(r"C:\ProgramData"))
+}
+
+#[cfg(windows)]
+fn gog_database() -> Option<PathBuf> {
+    Some(program_data().join(r"GOG.com\Galaxy\storage\galaxy-2.0.db"))
+}
+
+#[cfg(target_os = "macos")]
+fn gog_database() -> Option<PathBuf> {
+    Some(PathBuf::from(
+        "/Users/Shared/GOG.com/Galaxy/Storage/galaxy-2.0.db",
+    ))
+}
+
+/// GOG Galaxy only exists for Windows and macOS.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn gog_database() -> Option<PathBuf> {
+    None
+}
+
+#[cfg(windows)]
+fn epic_manifests_dir() -> Option<PathBuf> {
+    Some(program_data().join(r"Epic\EpicGamesLauncher\Data\Manifests"))
+}
+
+#[cfg(target_os = "macos")]
+fn epic_manifests_dir() -> Option<PathBuf> {
+    tauri::api::path::home_dir()
+        .map(|home| home.join("Library/Application Support/Epic/EpicGamesLauncher/Data/Manifests"))
+}
+
+/// The Epic Games Launcher only exists for Windows and macOS.
+#[cfg(not(any(windows, target_os = "macos")))]
+fn epic_manifests_dir() -> Option<PathBuf> {
+    None
+}
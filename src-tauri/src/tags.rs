@@ -0,0 +1,285 @@
+use crate::error::CommandError;
+use crate::{GameEntry, Library};
+use anyhow::Context;
+use caseless::Caseless;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use tauri::{AppHandle, State};
+use unicode_normalization::UnicodeNormalization;
+
+/// Key of the library setting holding the `TagPolicy`.
+pub const TAG_POLICY_SETTING: &str = "tag-policy";
+
+/// How tags that differ only in case are treated when games are saved.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum TagPolicy {
+    /// Tags are kept as typed; only exact duplicates are dropped.
+    #[default]
+    Preserve,
+    Lowercase,
+    /// Every spelling of a tag becomes the one the library saw first.
+    FirstSeenCasing,
+}
+
+impl TagPolicy {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Preserve => "preserve",
+            Self::Lowercase => "lowercase",
+            Self::FirstSeenCasing => "first-seen-casing",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [Self::Preserve, Self::Lowercase, Self::FirstSeenCasing]
+            .into_iter()
+            .find(|policy| policy.name() == name)
+    }
+}
+
+/// Normalizes tag lists under a `TagPolicy`, remembering the spelling each
+/// tag was first seen with, starting from the library's own.
+#[derive(Debug, Clone)]
+pub struct TagRules {
+    policy: TagPolicy,
+    spellings: HashMap<String, String>,
+}
+
+impl TagRules {
+    pub fn new(policy: TagPolicy, games: &[GameEntry]) -> Self {
+        let mut rules = Self {
+            policy,
+            spellings: HashMap::new(),
+        };
+        for tag in games.iter().flat_map(|game| &game.tags) {
+            rules
+                .spellings
+                .entry(tag_key(tag))
+                .or_insert_with(|| tag.clone());
+        }
+        rules
+    }
+
+    /// Makes `tag` the spelling its variants collapse to from now on.
+    pub fn prefer(&mut self, tag: &str) {
+        self.spellings.insert(tag_key(tag), tag.to_string());
+    }
+
+    /// Splits comma-separated entries, trims them, applies the policy and
+    /// drops duplicates. The result is sorted.
+    pub fn normalize(&mut self, tags: Vec<String>) -> Vec<String> {
+        let mut seen = HashSet::new();
+        let mut normalized = Vec::new();
+        for tag in tags
+            .iter()
+            .flat_map(|tag| tag.split(','))
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+        {
+            let (tag, key) = match self.policy {
+                TagPolicy::Preserve => (tag.to_string(), tag.to_string()),
+                TagPolicy::Lowercase => (tag.to_lowercase(), tag_key(tag)),
+                TagPolicy::FirstSeenCasing => {
+                    let key = tag_key(tag);
+                    let spelling = self
+                        .spellings
+                        .entry(key.clone())
+                        .or_insert_with(|| tag.to_string());
+                    (spelling.clone(), key)
+                }
+            };
+            if seen.insert(key) {
+                normalized.push(tag);
+            }
+        }
+        normalized.sort();
+        normalized
+    }
+}
+
+/// A tag folded for caseless comparison: Unicode case folding between
+/// canonical decompositions, so "Überraschung", "ÜBERRASCHUNG" and a
+/// decomposed "Ü" all compare equal, as do "Straße" and "STRASSE".
+pub fn tag_key(tag: &str) -> String {
+    tag.chars().nfd().default_case_fold().nfd().collect()
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagUsage {
+    tag: String,
+    count: usize,
+}
+
+/// Every tag in the library with the number of games carrying it, ordered
+/// by name. Spellings that differ only in case are listed separately, so
+/// they can be found and merged with `rename_tag`.
+#[tauri::command]
+pub fn list_tags(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<Vec<TagUsage>, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    let mut counts: BTreeMap<(String, String), usize> = BTreeMap::new();
+    for tag in games.iter().flat_map(|game| &game.tags) {
+        *counts.entry((tag.to_lowercase(), tag.clone())).or_default() += 1;
+    }
+    Ok(counts
+        .into_iter()
+        .map(|((_, tag), count)| TagUsage { tag, count })
+        .collect())
+}
+
+/// Renames a tag on every game in one write. `from` matches in any case and
+/// so does `to`, so "coop", "Coop" and "CO-OP" all become exactly `to` and
+/// games carrying several of them end up with one. Returns how many games
+/// changed.
+#[tauri::command]
+pub fn rename_tag(
+    app: AppHandle,
+    library: State<'_, Library>,
+    from: String,
+    to: String,
+) -> Result<usize, CommandError> {
+    let (from, to) = (from.trim(), to.trim());
+    if from.is_empty() || to.is_empty() {
+        return Err(CommandError::invalid_input("Tag names cannot be empty"));
+    }
+    if to.contains(',') {
+        return Err(CommandError::invalid_input(
+            "Tag names cannot contain commas",
+        ));
+    }
+    let mut rules = library.tag_rules(&app)?;
+    rules.prefer(to);
+    retag(&app, &library, rules, |tag| {
+        if same_tag(tag, from) || same_tag(tag, to) {
+            Some(to.to_string())
+        } else {
+            Some(tag.to_string())
+        }
+    })
+}
+
+/// Removes a tag, matched in any case, from every game in one write.
+/// Returns how many games changed.
+#[tauri::command]
+pub fn delete_tag(
+    app: AppHandle,
+    library: State<'_, Library>,
+    tag: String,
+) -> Result<usize, CommandError> {
+    let tag = tag.trim();
+    if tag.is_empty() {
+        return Err(CommandError::invalid_input("Tag name cannot be empty"));
+    }
+    let rules = library.tag_rules(&app)?;
+    retag(&app, &library, rules, |existing| {
+        (!same_tag(existing, tag)).then(|| existing.to_string())
+    })
+}
+
+/// Maps every tag of every game through `map`, dropping those it returns
+/// `None` for. Games where that changed anything get their tags normalized
+/// and `updated_at` bumped; the rest are left alone.
+fn retag(
+    app: &AppHandle,
+    library: &Library,
+    mut rules: TagRules,
+    map: impl Fn(&str) -> Option<String>,
+) -> Result<usize, CommandError> {
+    let changed = library.update(app, |games| {
+        let now = Utc::now();
+        let mut changed = Vec::new();
+        for game in games.iter_mut() {
+            let mapped: Vec<String> = game.tags.iter().filter_map(|tag| map(tag)).collect();
+            if mapped != game.tags {
+                game.tags = rules.normalize(mapped);
+                game.updated_at = now;
+                changed.push(game.id.clone());
+            }
+        }
+        Ok(changed)
+    })?;
+
+    let count = changed.len();
+    if count > 0 {
+        crate::emit_library_updated(app, changed);
+    }
+    Ok(count)
+}
+
+/// Result of `apply_tag_policy`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TagMigration {
+    /// Games whose tags were rewritten.
+    games_changed: usize,
+    /// Tags dropped because they duplicated another spelling on the same game.
+    tags_collapsed: usize,
+}
+
+#[tauri::command]
+pub fn get_tag_policy(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<TagPolicy, CommandError> {
+    library
+        .tag_policy(&app)
+        .context("Failed to load library")
+        .map_err(CommandError::from)
+}
+
+/// Sets the policy applied to tags from now on. Existing tags are only
+/// rewritten by `apply_tag_policy`.
+#[tauri::command]
+pub fn set_tag_policy(
+    app: AppHandle,
+    library: State<'_, Library>,
+    policy: TagPolicy,
+) -> Result<(), CommandError> {
+    library
+        .set_tag_policy(&app, policy)
+        .map_err(CommandError::from)
+}
+
+/// Rewrites the tags of every game under the current policy in one write.
+/// Under `first-seen-casing` each tag takes the spelling it first appears
+/// with in the library.
+#[tauri::command]
+pub fn apply_tag_policy(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<TagMigration, CommandError> {
+    let mut rules = library.tag_rules(&app)?;
+    let (changed, tags_collapsed) = library.update(&app, |games| {
+        let now = Utc::now();
+        let mut changed = Vec::new();
+        let mut collapsed = 0;
+        for game in games.iter_mut() {
+            let tags = rules.normalize(game.tags.clone());
+            if tags != game.tags {
+                collapsed += game.tags.len().saturating_sub(tags.len());
+                game.tags = tags;
+                game.updated_at = now;
+                changed.push(game.id.clone());
+            }
+        }
+        Ok((changed, collapsed))
+    })?;
+
+    let games_changed = changed.len();
+    if games_changed > 0 {
+        crate::emit_library_updated(&app, changed);
+    }
+    Ok(TagMigration {
+        games_changed,
+        tags_collapsed,
+    })
+}
+
+fn same_tag(a: &str, b: &str) -> bool {
+    tag_key(a) == tag_key(b)
+}
@@ -0,0 +1,103 @@
+use crate::downloads::QueueProgress;
+use tauri::AppHandle;
+
+/// Mirrors the download queue on the window's taskbar button: the combined
+/// progress while downloads run, in red once one of them failed, and no bar
+/// once the queue is idle. Only Windows shows such a bar.
+#[cfg(windows)]
+pub fn show(app: &AppHandle, progress: &QueueProgress) {
+    use tauri::Manager;
+
+    let Some(window) = app.get_window("main") else {
+        return;
+    };
+    let Ok(hwnd) = window.hwnd() else {
+        return;
+    };
+    let (hwnd, state) = (hwnd.0, windows::TaskbarState::of(progress));
+    // The taskbar is driven from the thread that owns the window.
+    let _ = window.run_on_main_thread(move || {
+        let _ = windows::apply(hwnd, state);
+    });
+}
+
+#[cfg(not(windows))]
+pub fn show(_app: &AppHandle, _progress: &QueueProgress) {}
+
+#[cfg(windows)]
+mod windows {
+    use crate::downloads::QueueProgress;
+    use std::cell::RefCell;
+    use windows::core::Result;
+    use windows::Win32::Foundation::HWND;
+    use windows::Win32::System::Com::{CoCreateInstance, CLSCTX_INPROC_SERVER};
+    use windows::Win32::UI::Shell::{
+        ITaskbarList3, TaskbarList, TBPF_ERROR, TBPF_INDETERMINATE, TBPF_NOPROGRESS, TBPF_NORMAL,
+    };
+
+    thread_local! {
+        static TASKBAR: RefCell<Option<ITaskbarList3>> = const { RefCell::new(None) };
+    }
+
+    #[derive(Debug, Clone, Copy)]
+    pub enum TaskbarState {
+        Hidden,
+        /// Downloads run but none knows its size yet.
+        Indeterminate,
+        Normal {
+            completed: u64,
+            total: u64,
+        },
+        Error {
+            completed: u64,
+            total: u64,
+        },
+    }
+
+    impl TaskbarState {
+        pub fn of(progress: &QueueProgress) -> Self {
+            let (completed, total) = (progress.processed_bytes, progress.total_bytes);
+            if progress.active + progress.queued == 0 {
+                Self::Hidden
+            } else if progress.failed > 0 {
+                Self::Error { completed, total }
+            } else if total == 0 {
+                Self::Indeterminate
+            } else {
+                Self::Normal { completed, total }
+            }
+        }
+    }
+
+    /// Sets the bar of `hwnd`. Must run on the thread owning the window,
+    /// where COM is already initialized.
+    pub fn apply(hwnd: isize, state: TaskbarState) -> Result<()> {
+        TASKBAR.with(|taskbar| {
+            let mut taskbar = taskbar.borrow_mut();
+            let list = match taskbar.as_ref() {
+                Some(list) => list,
+                None => {
+                    let list: ITaskbarList3 =
+                        unsafe { CoCreateInstance(&TaskbarList, None, CLSCTX_INPROC_SERVER)? };
+                    unsafe { list.HrInit()? };
+                    taskbar.insert(list)
+                }
+            };
+            let hwnd = HWND(hwnd);
+            unsafe {
+                match state {
+                    TaskbarState::Hidden => list.SetProgressState(hwnd, TBPF_NOPROGRESS),
+                    TaskbarState::Indeterminate => list.SetProgressState(hwnd, TBPF_INDETERMINATE),
+                    TaskbarState::Normal { completed, total } => {
+                        list.SetProgressState(hwnd, TBPF_NORMAL)?;
+                        list.SetProgressValue(hwnd, completed, total)
+                    }
+                    TaskbarState::Error { completed, total } => {
+                        list.SetProgressState(hwnd, TBPF_ERROR)?;
+                        list.SetProgressValue(hwnd, completed, total.max(1))
+                    }
+                }
+            }
+        })
+    }
+}
@@ -0,0 +1,110 @@
+use crate::error::CommandError;
+use crate::hook;
+use crate::path_access;
+use crate::settings::SettingsStore;
+use anyhow::Context;
+use std::path::Path;
+use std::process::Command;
+use tauri::{AppHandle, Manager};
+
+/// Placeholder replaced by the folder in a terminal command template.
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Opens a terminal in the folder `path`. The `terminalCommand` setting, such
+/// as `wt -d {path}`, is used when set, starting in the folder and with
+/// `{path}` replaced by it. Otherwise the platform's terminal is picked:
+/// Windows Terminal or `cmd` on Windows, `$TERMINAL` or the first known
+/// emulator found on Linux, Terminal.app on macOS. Only folders `open_path`
+/// would open are accepted.
+#[tauri::command]
+pub fn open_terminal(app: AppHandle, path: String) -> Result<(), CommandError> {
+    path_access::check(&app, &path)?;
+    let dir = Path::new(&path);
+    if !dir.is_dir() {
+        return Err(CommandError::not_found(format!("Not a folder: {path}")));
+    }
+    let mut command = match app.state::<SettingsStore>().get().terminal_command {
+        Some(template) => from_template(&template, dir)?,
+        None => default_terminal(dir)?,
+    };
+    command
+        .current_dir(dir)
+        .spawn()
+        .map(drop)
+        .context("Failed to open a terminal")
+        .map_err(CommandError::from)
+}
+
+fn from_template(template: &str, dir: &Path) -> Result<Command, CommandError> {
+    let path = dir.to_string_lossy();
+    let mut args = hook::parse_template(template)
+        .map_err(CommandError::invalid)?
+        .into_iter()
+        .map(|arg| arg.replace(PATH_PLACEHOLDER, &path));
+    let program = args
+        .next()
+        .ok_or_else(|| CommandError::invalid_input("Terminal command cannot be empty"))?;
+    let mut command = Command::new(program);
+    command.args(args);
+    Ok(command)
+}
+
+#[cfg(windows)]
+fn default_terminal(dir: &Path) -> Result<Command, CommandError> {
+    use std::os::windows::process::CommandExt;
+    const CREATE_NEW_CONSOLE: u32 = 0x0000_0010;
+
+    if on_path("wt.exe") {
+        let mut command = Command::new("wt");
+        command.arg("-d").arg(dir);
+        return Ok(command);
+    }
+    let mut command = Command::new("cmd");
+    command.creation_flags(CREATE_NEW_CONSOLE);
+    Ok(command)
+}
+
+#[cfg(target_os = "macos")]
+fn default_terminal(dir: &Path) -> Result<Command, CommandError> {
+    let mut command = Command::new("open");
+    command.args(["-a", "Terminal"]).arg(dir);
+    Ok(command)
+}
+
+/// Emulators tried in order when `$TERMINAL` is not set; all of them start
+/// in the folder they are started from.
+#[cfg(not(any(windows, target_os = "macos")))]
+const LINUX_TERMINALS: &[&str] = &[
+    "x-terminal-emulator",
+    "gnome-terminal",
+    "konsole",
+    "xfce4-terminal",
+    "kitty",
+    "alacritty",
+    "wezterm",
+    "foot",
+    "xterm",
+];
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn default_terminal(_dir: &Path) -> Result<Command, CommandError> {
+    if let Some(terminal) = std::env::var("TERMINAL").ok().and_then(crate::non_empty) {
+        return Ok(Command::new(terminal));
+    }
+    LINUX_TERMINALS
+        .iter()
+        .find(|name| on_path(name))
+        .map(Command::new)
+        .ok_or_else(|| {
+            CommandError::new(
+                crate::error::ErrorCode::NotConfigured,
+                "No terminal found; set a terminal command in the settings",
+            )
+        })
+}
+
+#[cfg(not(target_os = "macos"))]
+fn on_path(program: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+}
@@ -0,0 +1,82 @@
+use crate::error::CommandError;
+use crate::{artwork, collections, GameEntry, Library};
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, Utc};
+use tauri::{AppHandle, State};
+
+/// Days a removed game stays in the trash before the purge on start drops it.
+pub const TRASH_RETENTION_DAYS: u32 = 30;
+
+/// Games removed from the library, most recently removed first.
+#[tauri::command]
+pub fn list_trash(
+    app: AppHandle,
+    library: State<'_, Library>,
+) -> Result<Vec<GameEntry>, CommandError> {
+    let mut trash = library.trash(&app).context("Failed to load library")?;
+    trash.sort_by_key(|game| std::cmp::Reverse(game.deleted_at));
+    Ok(trash)
+}
+
+/// Moves a game from the trash back into the library with its id and
+/// timestamps intact, apart from `updated_at`.
+#[tauri::command]
+pub fn restore_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    id: String,
+) -> Result<GameEntry, CommandError> {
+    library
+        .update_with_trash(&app, |games, trash| {
+            let index = trash
+                .iter()
+                .position(|game| game.id == id)
+                .ok_or_else(|| anyhow!("Game {id} is not in the trash"))?;
+            let mut game = trash.remove(index);
+            game.deleted_at = None;
+            game.updated_at = Utc::now();
+            games.push(game.clone());
+            Ok(game)
+        })
+        .map_err(CommandError::from)
+}
+
+/// Deletes games from the trash for good: those removed more than
+/// `older_than_days` ago, or all of them, together with their artwork and
+/// their place in collections.
+/// Returns how many were deleted.
+#[tauri::command]
+pub fn purge_trash(
+    app: AppHandle,
+    library: State<'_, Library>,
+    older_than_days: Option<u32>,
+) -> Result<usize, CommandError> {
+    purge(&app, &library, older_than_days).map_err(CommandError::from)
+}
+
+/// Drops games that have been in the trash longer than the retention period.
+pub fn purge_expired(app: &AppHandle, library: &Library) -> Result<usize> {
+    purge(app, library, Some(TRASH_RETENTION_DAYS))
+}
+
+fn purge(app: &AppHandle, library: &Library, older_than_days: Option<u32>) -> Result<usize> {
+    let cutoff = older_than_days.map(|days| Utc::now() - Duration::days(days.into()));
+    let purged = library.update_with_trash(app, |_, trash| {
+        let (purged, kept) = trash
+            .drain(..)
+            .partition(|game| match (cutoff, game.deleted_at) {
+                (Some(cutoff), Some(deleted_at)) => deleted_at <= cutoff,
+                _ => true,
+            });
+        *trash = kept;
+        Ok(purged)
+    })?;
+    for game in &purged {
+        artwork::remove_game_artwork(app, &game.id);
+    }
+    let ids: Vec<String> = purged.iter().map(|game| game.id.clone()).collect();
+    if !ids.is_empty() {
+        collections::forget_games(app, &ids)?;
+    }
+    Ok(purged.len())
+}
@@ -0,0 +1,285 @@
+use crate::downloads::{self, DownloadManager, DownloadSnapshot, DownloadStatus};
+use crate::error::CommandError;
+use crate::settings::{SettingsPatch, SettingsStore};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tauri::{
+    AppHandle, CustomMenuItem, GlobalWindowEvent, Icon, Manager, State, SystemTray,
+    SystemTrayEvent, SystemTrayMenu, SystemTrayMenuItem, WindowEvent,
+};
+
+const TRAY_ID: &str = "main";
+const REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// Downloads listed in the menu; the rest are summed up in one line.
+const MENU_DOWNLOADS: usize = 8;
+const ICON_SIZE: u32 = 32;
+
+/// State of the tray icon, shared by the menu, the main window and the
+/// loop that keeps both up to date.
+#[derive(Debug)]
+pub struct Tray {
+    /// Whether the platform gave the app a tray icon.
+    available: AtomicBool,
+    /// Set when a close request hid the window, so the app exits once the
+    /// queue is idle.
+    exit_when_idle: AtomicBool,
+    /// The downloads, with their status, the current menu was built for.
+    listed: Mutex<Vec<(String, DownloadStatus)>>,
+}
+
+impl Default for Tray {
+    fn default() -> Self {
+        Self {
+            available: AtomicBool::new(false),
+            exit_when_idle: AtomicBool::new(false),
+            listed: Mutex::new(Vec::new()),
+        }
+    }
+}
+
+/// Whether closing the main window while downloads are queued or running
+/// hides it to the tray instead of quitting. On by default; without a tray
+/// the window always closes.
+#[tauri::command]
+pub fn set_close_to_tray(
+    app: AppHandle,
+    settings: State<'_, SettingsStore>,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    let patch = SettingsPatch {
+        close_to_tray: Some(enabled),
+        ..SettingsPatch::default()
+    };
+    settings.update(&app, patch).map(drop)
+}
+
+/// Adds the tray icon and starts keeping its menu and tooltip in step with
+/// the download queue. Where the platform has no tray, nothing changes and
+/// the window closes as before.
+pub fn setup(app: &AppHandle) {
+    let tray = SystemTray::new()
+        .with_id(TRAY_ID)
+        .with_icon(icon())
+        .with_menu(menu(&[]))
+        .with_tooltip("RoseLauncher")
+        .build(app);
+    if tray.is_err() {
+        return;
+    }
+    app.state::<Tray>().available.store(true, Ordering::SeqCst);
+
+    let app = app.clone();
+    thread::spawn(move || loop {
+        refresh(&app);
+        thread::sleep(REFRESH_INTERVAL);
+    });
+}
+
+/// Brings the main window back, from the tray or minimized.
+pub fn show_window(app: &AppHandle) {
+    app.state::<Tray>()
+        .exit_when_idle
+        .store(false, Ordering::SeqCst);
+    if let Some(window) = app.get_window("main") {
+        let _ = window.show();
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+}
+
+/// Hides the main window to the tray at startup, or minimizes it where the
+/// platform has no tray. The tray menu brings it back.
+pub fn start_minimized(app: &AppHandle) {
+    let Some(window) = app.get_window("main") else {
+        return;
+    };
+    if app.state::<Tray>().available.load(Ordering::SeqCst) {
+        let _ = window.hide();
+    } else {
+        let _ = window.minimize();
+    }
+}
+
+/// Hides the window instead of closing it while downloads are queued or
+/// running, so they can finish; the app then exits on its own.
+pub fn on_window_event(event: GlobalWindowEvent) {
+    let WindowEvent::CloseRequested { api, .. } = event.event() else {
+        return;
+    };
+    let window = event.window();
+    let app = window.app_handle();
+    let tray = app.state::<Tray>();
+    if !tray.available.load(Ordering::SeqCst)
+        || !app.state::<SettingsStore>().get().close_to_tray
+        || !is_busy(&app.state::<DownloadManager>().snapshot())
+    {
+        return;
+    }
+    api.prevent_close();
+    if window.hide().is_ok() {
+        tray.exit_when_idle.store(true, Ordering::SeqCst);
+    }
+}
+
+pub fn on_tray_event(app: &AppHandle, event: SystemTrayEvent) {
+    match event {
+        SystemTrayEvent::LeftClick { .. } | SystemTrayEvent::DoubleClick { .. } => show_window(app),
+        SystemTrayEvent::MenuItemClick { id, .. } => match id.as_str() {
+            "show" => show_window(app),
+            "pause-all" => pause_all(app),
+            "quit" => app.exit(0),
+            _ => {}
+        },
+        _ => {}
+    }
+}
+
+fn pause_all(app: &AppHandle) {
+    let snapshot = app.state::<DownloadManager>().snapshot();
+    for download in snapshot.into_iter().filter(|d| is_running(d.status)) {
+        // A job that finished in the meantime has nothing left to pause.
+        let _ = downloads::pause_download(app.clone(), app.state(), download.id);
+    }
+    refresh(app);
+}
+
+/// Updates the tooltip and menu from the same state `list_downloads`
+/// reports, and exits once a hidden window was only waiting on the queue.
+fn refresh(app: &AppHandle) {
+    let snapshot = app.state::<DownloadManager>().snapshot();
+    let tray = app.state::<Tray>();
+    if tray.exit_when_idle.load(Ordering::SeqCst) && !is_busy(&snapshot) {
+        app.exit(0);
+        return;
+    }
+    let Some(handle) = app.tray_handle_by_id(TRAY_ID) else {
+        return;
+    };
+    let pending: Vec<&DownloadSnapshot> = snapshot
+        .iter()
+        .filter(|download| !download.status.is_finished())
+        .collect();
+    let _ = handle.set_tooltip(&tooltip(&pending));
+
+    let listed: Vec<(String, DownloadStatus)> = pending
+        .iter()
+        .map(|download| (download.id.clone(), download.status))
+        .collect();
+    let mut current = tray.listed.lock().unwrap_or_else(|e| e.into_inner());
+    if *current != listed {
+        // Items cannot be added or removed in place, so a changed list
+        // means a new menu.
+        let _ = handle.set_menu(menu(&pending));
+        *current = listed;
+    } else {
+        for download in pending.iter().take(MENU_DOWNLOADS) {
+            let _ = handle
+                .get_item(&item_id(&download.id))
+                .set_title(item_title(download));
+        }
+    }
+}
+
+fn menu(pending: &[&DownloadSnapshot]) -> SystemTrayMenu {
+    let mut menu = SystemTrayMenu::new()
+        .add_item(CustomMenuItem::new("show", "Show library"))
+        .add_native_item(SystemTrayMenuItem::Separator);
+    if pending.is_empty() {
+        menu = menu.add_item(CustomMenuItem::new("no-downloads", "No downloads").disabled());
+    }
+    for download in pending.iter().take(MENU_DOWNLOADS) {
+        menu = menu
+            .add_item(CustomMenuItem::new(item_id(&download.id), item_title(download)).disabled());
+    }
+    if pending.len() > MENU_DOWNLOADS {
+        let more = format!("and {} more", pending.len() - MENU_DOWNLOADS);
+        menu = menu.add_item(CustomMenuItem::new("more-downloads", more).disabled());
+    }
+    let mut pause_all = CustomMenuItem::new("pause-all", "Pause all downloads");
+    if !pending.iter().any(|download| is_running(download.status)) {
+        pause_all = pause_all.disabled();
+    }
+    menu.add_item(pause_all)
+        .add_native_item(SystemTrayMenuItem::Separator)
+        .add_item(CustomMenuItem::new("quit", "Quit"))
+}
+
+fn item_id(download_id: &str) -> String {
+    format!("download-{download_id}")
+}
+
+/// `game.zip — 42% (active)`, without the percentage while the size is
+/// unknown.
+fn item_title(download: &DownloadSnapshot) -> String {
+    let status = match download.status {
+        DownloadStatus::Queued => "queued",
+        DownloadStatus::Active => "active",
+        DownloadStatus::Paused => "paused",
+        DownloadStatus::Interrupted => "interrupted",
+        DownloadStatus::Done => "done",
+        DownloadStatus::Flagged => "flagged",
+        DownloadStatus::Failed => "failed",
+        DownloadStatus::Cancelled => "cancelled",
+    };
+    match percent(download.processed, download.total) {
+        Some(percent) => format!("{} — {percent}% ({status})", download.file_name),
+        None => format!("{} ({status})", download.file_name),
+    }
+}
+
+/// `RoseLauncher — 2 downloads, 42%`: progress over every unfinished
+/// download whose size is known.
+fn tooltip(pending: &[&DownloadSnapshot]) -> String {
+    if pending.is_empty() {
+        return "RoseLauncher".to_string();
+    }
+    let count = match pending.len() {
+        1 => "1 download".to_string(),
+        count => format!("{count} downloads"),
+    };
+    let (processed, total) = pending
+        .iter()
+        .filter_map(|download| Some((download.processed, download.total?)))
+        .fold((0, 0), |(processed, total), (p, t)| {
+            (processed + p, total + t)
+        });
+    match percent(processed, Some(total)) {
+        Some(percent) => format!("RoseLauncher — {count}, {percent}%"),
+        None => format!("RoseLauncher — {count}"),
+    }
+}
+
+fn percent(processed: u64, total: Option<u64>) -> Option<u64> {
+    let total = total.filter(|total| *total > 0)?;
+    Some((processed.min(total) as u128 * 100 / total as u128) as u64)
+}
+
+fn is_running(status: DownloadStatus) -> bool {
+    matches!(status, DownloadStatus::Active | DownloadStatus::Queued)
+}
+
+fn is_busy(snapshot: &[DownloadSnapshot]) -> bool {
+    snapshot.iter().any(|download| is_running(download.status))
+}
+
+/// A rose-coloured dot, drawn here since the bundle ships no icon files.
+fn icon() -> Icon {
+    let center = (ICON_SIZE as f32 - 1.0) / 2.0;
+    let radius = ICON_SIZE as f32 / 2.0 - 2.0;
+    let mut rgba = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let distance = (x as f32 - center).hypot(y as f32 - center);
+            // One pixel of fade keeps the edge smooth.
+            let alpha = (radius + 0.5 - distance).clamp(0.0, 1.0);
+            rgba.extend_from_slice(&[245, 194, 231, (alpha * 255.0) as u8]);
+        }
+    }
+    Icon::Rgba {
+        rgba,
+        width: ICON_SIZE,
+        height: ICON_SIZE,
+    }
+}
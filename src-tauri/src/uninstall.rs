@@ -0,0 +1,282 @@
+use crate::error::CommandError;
+use crate::launcher::Launcher;
+use crate::settings::SettingsStore;
+use crate::{GameEntry, InstallStatus, Library};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use tauri::{AppHandle, Manager, State};
+use walkdir::WalkDir;
+
+/// Install folders with fewer named parts than this, such as `D:\Game`, are
+/// too close to a drive root to delete.
+const MIN_INSTALL_DEPTH: usize = 2;
+/// Files between two progress events while deleting permanently.
+const PROGRESS_EVERY: usize = 200;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct UninstallProgress {
+    game_id: String,
+    removed_files: usize,
+    total_files: usize,
+}
+
+/// Marks a game as no longer installed: clears its install and executable
+/// paths, takes its size from the archive that is left, if any, and sets its
+/// status to `Archived` or `NotInstalled` accordingly. With `delete_files`
+/// the install folder is removed first, to the recycle bin where the system
+/// has one and for good otherwise, reporting `uninstall-progress` while it
+/// goes. The folder is only deleted when it lies inside a configured games
+/// folder (a download folder or one of the `gameFolders` setting), is not a
+/// drive root or close to one, and holds no other entry's files.
+#[tauri::command]
+pub async fn uninstall_game(
+    app: AppHandle,
+    library: State<'_, Library>,
+    launcher: State<'_, Launcher>,
+    id: String,
+    delete_files: bool,
+) -> Result<GameEntry, CommandError> {
+    let games = library.games(&app).context("Failed to load library")?;
+    let game = games
+        .iter()
+        .find(|game| game.id == id)
+        .ok_or_else(|| CommandError::game_not_found(&id))?;
+    if launcher.is_running(&id) {
+        return Err(CommandError::conflict(format!("{} is running", game.title)));
+    }
+
+    if delete_files {
+        let install = game
+            .install_path
+            .as_deref()
+            .map(PathBuf::from)
+            .ok_or_else(|| {
+                CommandError::not_found(format!("{} has no install folder", game.title))
+            })?;
+        check_deletable(&app, &install, &games, &id)?;
+        let worker_app = app.clone();
+        let game_id = id.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            delete_folder(&worker_app, &game_id, &install)
+        })
+        .await??;
+    }
+
+    let updated = library
+        .update(&app, |games| {
+            let game = games
+                .iter_mut()
+                .find(|game| game.id == id)
+                .ok_or_else(|| CommandError::game_not_found(&id))?;
+            mark_uninstalled(game);
+            Ok(game.clone())
+        })
+        .context("Failed to save library")?;
+    crate::emit_library_updated(&app, vec![id]);
+    Ok(updated)
+}
+
+fn mark_uninstalled(game: &mut GameEntry) {
+    let install = game.install_path.take().map(PathBuf::from);
+    game.executable_path = None;
+    if let (Some(install), Some(dir)) = (&install, &game.working_dir) {
+        if Path::new(dir).starts_with(install) {
+            game.working_dir = None;
+        }
+    }
+    let archive = game
+        .archive_path
+        .as_deref()
+        .map(Path::new)
+        .filter(|path| path.exists());
+    game.size_bytes = archive.and_then(|path| crate::compute_path_size(path).ok());
+    game.status = match archive {
+        Some(_) => InstallStatus::Archived,
+        None => InstallStatus::NotInstalled,
+    };
+}
+
+/// Refuses folders that are missing, near a drive root, not strictly inside
+/// a configured games folder, or holding the files of another entry, trashed
+/// ones included.
+fn check_deletable(app: &AppHandle, install: &Path, games: &[GameEntry], id: &str) -> Result<()> {
+    let settings = app.state::<SettingsStore>().get();
+    let roots: Vec<PathBuf> = settings
+        .default_download_dir
+        .into_iter()
+        .chain(settings.repacker_download_dirs.into_values())
+        .chain(settings.game_folders)
+        .map(PathBuf::from)
+        .collect();
+    let trash = app
+        .state::<Library>()
+        .trash(app)
+        .context("Failed to load library")?;
+    let others = games.iter().chain(&trash).filter(|game| game.id != id);
+    check_deletable_within(install, &roots, others)
+}
+
+fn check_deletable_within<'a>(
+    install: &Path,
+    roots: &[PathBuf],
+    others: impl IntoIterator<Item = &'a GameEntry>,
+) -> Result<()> {
+    let install = install
+        .canonicalize()
+        .with_context(|| format!("Install folder not found: {}", install.display()))?;
+    if !install.is_dir() {
+        return Err(CommandError::not_found(format!("Not a folder: {}", install.display())).into());
+    }
+    let depth = install
+        .components()
+        .filter(|component| matches!(component, Component::Normal(_)))
+        .count();
+    if depth < MIN_INSTALL_DEPTH {
+        return Err(anyhow!(
+            "Refusing to delete {}: it is too close to the drive root",
+            install.display()
+        ));
+    }
+
+    let known = roots
+        .iter()
+        .filter_map(|dir| dir.canonicalize().ok())
+        .any(|dir| install.starts_with(&dir) && install != dir);
+    if !known {
+        return Err(anyhow!(
+            "Refusing to delete {}: it is not inside a known games folder",
+            install.display()
+        ));
+    }
+
+    // Paths that no longer exist hold nothing to lose.
+    for game in others {
+        let inside = [
+            &game.install_path,
+            &game.archive_path,
+            &game.executable_path,
+        ]
+        .into_iter()
+        .flatten()
+        .filter_map(|path| Path::new(path).canonicalize().ok())
+        .any(|path| path.starts_with(&install));
+        if inside {
+            return Err(CommandError::conflict(format!(
+                "Refusing to delete {}: it holds files of {}",
+                install.display(),
+                game.title
+            ))
+            .into());
+        }
+    }
+    Ok(())
+}
+
+/// Sends `dir` to the recycle bin, or deletes it file by file when that is
+/// not possible.
+fn delete_folder(app: &AppHandle, game_id: &str, dir: &Path) -> Result<()> {
+    let files: Vec<PathBuf> = WalkDir::new(dir)
+        .contents_first(true)
+        .into_iter()
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.into_path())
+        .collect();
+    let total_files = files.len();
+    let progress = |removed_files| {
+        let _ = app.emit_all(
+            "uninstall-progress",
+            UninstallProgress {
+                game_id: game_id.to_string(),
+                removed_files,
+                total_files,
+            },
+        );
+    };
+
+    progress(0);
+    if trash::delete(dir).is_ok() {
+        progress(total_files);
+        return Ok(());
+    }
+    // Children come before their folder, so each folder is empty when reached.
+    for (index, path) in files.iter().enumerate() {
+        let removed = if path.is_dir() && !path.is_symlink() {
+            fs::remove_dir(path)
+        } else {
+            fs::remove_file(path)
+        };
+        removed.with_context(|| format!("Failed to delete {}", path.display()))?;
+        if (index + 1) % PROGRESS_EVERY == 0 {
+            progress(index + 1);
+        }
+    }
+    progress(total_files);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tags::{TagPolicy, TagRules};
+    use crate::GamePayload;
+
+    fn installed(title: &str, install: &Path) -> GameEntry {
+        let payload = GamePayload {
+            title: title.into(),
+            install_path: Some(install.to_string_lossy().into_owned()),
+            ..GamePayload::default()
+        };
+        let mut rules = TagRules::new(TagPolicy::default(), &[]);
+        crate::game_from_payload(payload, None, &mut rules)
+    }
+
+    /// A games folder `library` holding `first` and `second`.
+    fn library() -> (tempfile::TempDir, PathBuf) {
+        let dir = tempfile::tempdir().unwrap();
+        let library = dir.path().join("library");
+        fs::create_dir_all(library.join("first")).unwrap();
+        fs::create_dir_all(library.join("second")).unwrap();
+        (dir, library)
+    }
+
+    #[test]
+    fn allows_a_folder_inside_a_games_folder() {
+        let (_dir, library) = library();
+        let second = installed("Second", &library.join("second"));
+        let roots = [library.clone()];
+        assert!(check_deletable_within(&library.join("first"), &roots, [&second]).is_ok());
+    }
+
+    #[test]
+    fn refuses_a_folder_holding_another_game() {
+        let (_dir, library) = library();
+        let roots = [library.clone()];
+        fs::create_dir(library.join("first").join("dlc")).unwrap();
+        let nested = installed("Nested", &library.join("first").join("dlc"));
+        assert!(check_deletable_within(&library.join("first"), &roots, [&nested]).is_err());
+
+        let mut archived = installed("Archived", &library.join("second"));
+        let archive = library.join("first").join("archived.rar");
+        fs::write(&archive, "").unwrap();
+        archived.archive_path = Some(archive.to_string_lossy().into_owned());
+        assert!(check_deletable_within(&library.join("first"), &roots, [&archived]).is_err());
+    }
+
+    #[test]
+    fn refuses_a_shared_library_folder() {
+        let (dir, library) = library();
+        let second = installed("Second", &library.join("second"));
+        let roots = [dir.path().to_path_buf()];
+        assert!(check_deletable_within(&library, &roots, [&second]).is_err());
+    }
+
+    #[test]
+    fn does_not_trust_the_parent_of_another_install() {
+        let (_dir, library) = library();
+        let second = installed("Second", &library.join("second"));
+        assert!(check_deletable_within(&library.join("first"), &[], [&second]).is_err());
+    }
+}
@@ -0,0 +1,207 @@
+use crate::error::CommandError;
+use crate::{GameEntry, InstallStatus, Library};
+use anyhow::Context;
+use chrono::Utc;
+use serde::Serialize;
+use std::path::Path;
+use tauri::{AppHandle, Manager, State};
+
+/// A recorded size counts as stale when a re-scan differs from it by more
+/// than this share, in percent.
+const SIZE_TOLERANCE_PERCENT: u64 = 5;
+
+/// Sent after each game `validate_library` has checked.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ValidationProgress {
+    game_id: String,
+    checked: usize,
+    total: usize,
+}
+
+/// What is wrong with one game. Only games with at least one problem are
+/// reported.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GameProblems {
+    game_id: String,
+    title: String,
+    /// The path as checked, for paths that no longer exist.
+    missing_archive_path: Option<String>,
+    missing_install_path: Option<String>,
+    missing_executable_path: Option<String>,
+    /// `executable_path` exists but is a folder or something else.
+    executable_not_a_file: bool,
+    stale_size: Option<StaleSize>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaleSize {
+    recorded: u64,
+    measured: u64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidationReport {
+    checked: usize,
+    problems: Vec<GameProblems>,
+    /// Games changed by `auto_fix`.
+    fixed: Vec<String>,
+}
+
+/// Checks every game's archive, install folder and executable still exist
+/// and, unless `check_sizes` is false, re-measures sizes to find stale ones.
+/// Runs in the background and sends `library-validation-progress` after each
+/// game, since missing network drives can take long to time out.
+///
+/// With `auto_fix`, dead paths are cleared, stale sizes replaced, and games
+/// marked installed whose install folder is gone become not installed.
+/// Games with a running download are skipped, as their archive is expected
+/// to be missing.
+#[tauri::command]
+pub async fn validate_library(
+    app: AppHandle,
+    library: State<'_, Library>,
+    check_sizes: Option<bool>,
+    auto_fix: Option<bool>,
+) -> Result<ValidationReport, CommandError> {
+    let games: Vec<GameEntry> = library
+        .games(&app)
+        .context("Failed to load library")?
+        .into_iter()
+        .filter(|game| game.download_id.is_none())
+        .collect();
+    let check_sizes = check_sizes.unwrap_or(true);
+
+    let scan_app = app.clone();
+    let problems = tauri::async_runtime::spawn_blocking(move || {
+        let total = games.len();
+        let mut problems = Vec::new();
+        for (index, game) in games.iter().enumerate() {
+            if let Some(found) = check_game(game, check_sizes) {
+                problems.push(found);
+            }
+            let _ = scan_app.emit_all(
+                "library-validation-progress",
+                ValidationProgress {
+                    game_id: game.id.clone(),
+                    checked: index + 1,
+                    total,
+                },
+            );
+        }
+        (total, problems)
+    })
+    .await?;
+    let (checked, problems) = problems;
+
+    let fixed = if auto_fix == Some(true) && !problems.is_empty() {
+        let fixed = library.update(&app, |games| {
+            let now = Utc::now();
+            let mut fixed = Vec::new();
+            for found in &problems {
+                let Some(game) = games.iter_mut().find(|game| game.id == found.game_id) else {
+                    continue;
+                };
+                if fix_game(game, found) {
+                    game.updated_at = now;
+                    fixed.push(game.id.clone());
+                }
+            }
+            Ok(fixed)
+        })?;
+        if !fixed.is_empty() {
+            crate::emit_library_updated(&app, fixed.clone());
+        }
+        fixed
+    } else {
+        Vec::new()
+    };
+
+    Ok(ValidationReport {
+        checked,
+        problems,
+        fixed,
+    })
+}
+
+fn check_game(game: &GameEntry, check_sizes: bool) -> Option<GameProblems> {
+    let missing = |path: &Option<String>| path.clone().filter(|path| !Path::new(path).exists());
+    let missing_archive_path = missing(&game.archive_path);
+    let missing_install_path = missing(&game.install_path);
+    let missing_executable_path = missing(&game.executable_path);
+    let executable_not_a_file = game
+        .executable_path
+        .as_ref()
+        .is_some_and(|path| Path::new(path).exists() && !Path::new(path).is_file());
+
+    // Sizes are measured the way they were recorded: archive first.
+    let stale_size = game
+        .size_bytes
+        .filter(|_| check_sizes)
+        .and_then(|recorded| {
+            let path = game
+                .archive_path
+                .as_ref()
+                .filter(|_| missing_archive_path.is_none())
+                .or(game
+                    .install_path
+                    .as_ref()
+                    .filter(|_| missing_install_path.is_none()))?;
+            let measured = crate::compute_path_size(Path::new(path)).ok()?;
+            let tolerance = recorded / 100 * SIZE_TOLERANCE_PERCENT;
+            (recorded.abs_diff(measured) > tolerance).then_some(StaleSize { recorded, measured })
+        });
+
+    let problems = GameProblems {
+        game_id: game.id.clone(),
+        title: game.title.clone(),
+        missing_archive_path,
+        missing_install_path,
+        missing_executable_path,
+        executable_not_a_file,
+        stale_size,
+    };
+    let found = problems.missing_archive_path.is_some()
+        || problems.missing_install_path.is_some()
+        || problems.missing_executable_path.is_some()
+        || problems.executable_not_a_file
+        || problems.stale_size.is_some();
+    found.then_some(problems)
+}
+
+/// Applies the fixes for `problems` to `game`. Fields the user changed while
+/// the scan ran are left alone. Returns whether anything changed.
+fn fix_game(game: &mut GameEntry, problems: &GameProblems) -> bool {
+    let mut changed = false;
+    let mut clear = |field: &mut Option<String>, dead: &Option<String>| {
+        if dead.is_some() && field == dead {
+            *field = None;
+            changed = true;
+        }
+    };
+    clear(&mut game.archive_path, &problems.missing_archive_path);
+    clear(&mut game.install_path, &problems.missing_install_path);
+    clear(&mut game.executable_path, &problems.missing_executable_path);
+    if problems.executable_not_a_file {
+        let not_a_file = game.executable_path.clone();
+        clear(&mut game.executable_path, &not_a_file);
+    }
+
+    if problems.missing_install_path.is_some()
+        && game.install_path.is_none()
+        && game.status == InstallStatus::Installed
+    {
+        game.status = InstallStatus::NotInstalled;
+        changed = true;
+    }
+    if let Some(stale) = problems.stale_size {
+        if game.size_bytes == Some(stale.recorded) {
+            game.size_bytes = Some(stale.measured);
+            changed = true;
+        }
+    }
+    changed
+}
@@ -1,21 +1,34 @@
 import { useEffect, useMemo, useState } from 'react';
 import { invoke } from '@tauri-apps/api/tauri';
+import { open as openDialog } from '@tauri-apps/api/dialog';
 import { listen } from '@tauri-apps/api/event';
 import { AddGameModal } from './components/AddGameModal';
 import { GameCard } from './components/GameCard';
 import { DownloadManager } from './components/DownloadManager';
 import {
+  AddGameResult,
+  ArtworkKind,
+  CommandError,
+  DeepLinkAddPayload,
+  DeepLinkErrorPayload,
   DownloadCompletePayload,
   DownloadErrorPayload,
   DownloadProgressPayload,
   DownloadTask,
   GameEntry,
   GamePayload,
+  GameProcessPayload,
+  GameSessionEndedPayload,
+  GameSizeUpdatedPayload,
+  IconError,
   InstallStatus,
+  MetadataCandidate,
+  QueueProgress,
   formatBytes,
   formatDate,
   statusPreset,
 } from './lib/types';
+import { scanPathSize } from './lib/sizeScan';
 import './App.css';
 
 interface DownloadQueuedPayload {
@@ -28,28 +41,48 @@ const statusOrder: InstallStatus[] = ['downloading', 'installed', 'not-installed
 
 const toPayload = (game: GameEntry): GamePayload => ({
   title: game.title,
+  sortTitle: game.sortTitle,
+  alternateTitles: game.alternateTitles,
   version: game.version,
+  developer: game.developer,
+  publisher: game.publisher,
+  releaseYear: game.releaseYear,
+  description: game.description,
   archivePath: game.archivePath,
   installPath: game.installPath,
   executablePath: game.executablePath,
+  launchArgs: game.launchArgs,
+  workingDir: game.workingDir,
+  env: game.env,
+  wrapperCommand: game.wrapperCommand,
+  runElevated: game.runElevated,
   repacker: game.repacker,
   tags: game.tags ?? [],
   status: game.status,
+  rating: game.rating,
+  completion: game.completion,
   notes: game.notes,
+  customFields: game.customFields,
   checksum: game.checksum,
   color: game.color,
   sizeOverride: game.sizeBytes,
+  favorite: game.favorite,
 });
 
 export default function App() {
   const [games, setGames] = useState<GameEntry[]>([]);
+  const [runningGameIds, setRunningGameIds] = useState<Set<string>>(new Set());
   const [selectedGameId, setSelectedGameId] = useState<string | null>(null);
   const [searchTerm, setSearchTerm] = useState('');
   const [loading, setLoading] = useState(true);
   const [modalOpen, setModalOpen] = useState(false);
   const [modalGame, setModalGame] = useState<GameEntry | null>(null);
   const [downloadsOpen, setDownloadsOpen] = useState(false);
+  const [deepLinkAdd, setDeepLinkAdd] = useState<DeepLinkAddPayload | null>(null);
   const [downloads, setDownloads] = useState<DownloadTask[]>([]);
+  const [queueProgress, setQueueProgress] = useState<QueueProgress | null>(null);
+  const [metadataAvailable, setMetadataAvailable] = useState(false);
+  const [metadataCandidates, setMetadataCandidates] = useState<MetadataCandidate[]>([]);
 
   const selectedGame = useMemo(() => games.find((game) => game.id === selectedGameId) ?? null, [games, selectedGameId]);
 
@@ -67,8 +100,13 @@ export default function App() {
         setLoading(false);
       }
     })();
+    invoke<boolean>('metadata_available').then(setMetadataAvailable).catch(console.error);
   }, []);
 
+  useEffect(() => {
+    setMetadataCandidates([]);
+  }, [selectedGameId]);
+
   useEffect(() => {
     let unsubscribers: Array<() => void> = [];
 
@@ -136,7 +174,69 @@ export default function App() {
         );
       });
 
-      unsubscribers = [unlistenProgress, unlistenComplete, unlistenError];
+      const unlistenSession = await listen<GameSessionEndedPayload>('game-session-ended', ({ payload }) => {
+        setGames((prev) =>
+          prev.map((game) =>
+            game.id === payload.gameId ? { ...game, totalPlaytimeSeconds: payload.totalPlaytimeSeconds } : game
+          )
+        );
+      });
+
+      const unlistenSize = await listen<GameSizeUpdatedPayload>('game-size-updated', ({ payload }) => {
+        setGames((prev) =>
+          prev.map((game) => (game.id === payload.id ? { ...game, sizeBytes: payload.sizeBytes } : game))
+        );
+      });
+
+      const unlistenStarted = await listen<GameProcessPayload>('game-started', ({ payload }) => {
+        setRunningGameIds((prev) => new Set(prev).add(payload.gameId));
+      });
+
+      const unlistenStopped = await listen<GameProcessPayload>('game-stopped', ({ payload }) => {
+        setRunningGameIds((prev) => {
+          const next = new Set(prev);
+          next.delete(payload.gameId);
+          return next;
+        });
+      });
+
+      const unlistenDeepLinkAdd = await listen<DeepLinkAddPayload>('deep-link-add', ({ payload }) => {
+        setDeepLinkAdd(payload);
+        setModalGame(null);
+        setModalOpen(true);
+        setDownloadsOpen(true);
+      });
+
+      const unlistenDeepLinkUnknown = await listen<{ url: string }>('deep-link-unknown', ({ payload }) => {
+        window.alert(`RoseLauncher does not know what to do with ${payload.url}`);
+      });
+
+      const unlistenDeepLinkError = await listen<DeepLinkErrorPayload>('deep-link-error', ({ payload }) => {
+        window.alert(`Could not open ${payload.url}: ${payload.message}`);
+      });
+
+      const unlistenShowDownloads = await listen('show-downloads', () => {
+        setDownloadsOpen(true);
+      });
+
+      const unlistenQueue = await listen<QueueProgress>('queue-progress', ({ payload }) => {
+        setQueueProgress(payload);
+      });
+
+      unsubscribers = [
+        unlistenProgress,
+        unlistenComplete,
+        unlistenError,
+        unlistenSession,
+        unlistenSize,
+        unlistenStarted,
+        unlistenStopped,
+        unlistenDeepLinkAdd,
+        unlistenDeepLinkUnknown,
+        unlistenDeepLinkError,
+        unlistenShowDownloads,
+        unlistenQueue,
+      ];
     })();
 
     return () => {
@@ -146,7 +246,7 @@ export default function App() {
 
   const filteredGames = useMemo(() => {
     const query = searchTerm.trim().toLowerCase();
-    const sorted = [...games].sort((a, b) => statusOrder.indexOf(a.status) - statusOrder.indexOf(b.status) || a.title.localeCompare(b.title));
+    const sorted = [...games].sort((a, b) => statusOrder.indexOf(a.status) - statusOrder.indexOf(b.status) || (a.sortTitle ?? a.title).localeCompare(b.sortTitle ?? b.title));
 
     if (!query) {
       return sorted;
@@ -155,6 +255,7 @@ export default function App() {
     return sorted.filter((game) => {
       return (
         game.title.toLowerCase().includes(query) ||
+        game.alternateTitles?.some((title) => title.toLowerCase().includes(query)) ||
         game.tags?.some((tag) => tag.toLowerCase().includes(query)) ||
         game.repacker?.toLowerCase().includes(query)
       );
@@ -178,9 +279,22 @@ export default function App() {
       setGames((prev) => prev.map((game) => (game.id === updated.id ? updated : game)));
       setSelectedGameId(updated.id);
     } else {
-      const created = await invoke<GameEntry>('add_game', { payload });
-      setGames((prev) => [created, ...prev]);
-      setSelectedGameId(created.id);
+      let result = await invoke<AddGameResult>('add_game', { payload });
+      if (result.outcome === 'duplicate') {
+        const { existingId } = result;
+        const existing = games.find((game) => game.id === existingId);
+        const reason = result.field === 'title' ? 'has the same title' : `uses the same ${result.field.replace('-', ' ')}`;
+        if (!window.confirm(`"${existing?.title ?? payload.title}" ${reason}. Add it anyway?`)) {
+          setSelectedGameId(existingId);
+          return;
+        }
+        result = await invoke<AddGameResult>('add_game', { payload: { ...payload, allowDuplicate: true } });
+      }
+      if (result.outcome === 'added') {
+        const created = result.game;
+        setGames((prev) => [created, ...prev]);
+        setSelectedGameId(created.id);
+      }
     }
   };
 
@@ -195,7 +309,7 @@ export default function App() {
     });
   };
 
-  const handleStartDownload = async ({ url, destination, fileName }: { url: string; destination: string; fileName?: string }) => {
+  const handleStartDownload = async ({ url, destination, fileName }: { url: string; destination?: string; fileName?: string }) => {
     const payload = await invoke<DownloadQueuedPayload>('queue_download', {
       url,
       destination,
@@ -241,13 +355,110 @@ export default function App() {
     }
   };
 
+  const openTerminal = async (path?: string | null) => {
+    if (!path) return;
+    try {
+      await invoke('open_terminal', { path });
+    } catch (error) {
+      console.error(error);
+    }
+  };
+
+  const launchGame = async (game: GameEntry, path?: string | null) => {
+    if (!path) return;
+    try {
+      let updated: GameEntry;
+      if (path === game.executablePath) {
+        await invoke('launch_game', { id: game.id });
+        updated = await invoke<GameEntry>('get_game', { id: game.id });
+      } else {
+        await invoke('open_path', { path });
+        updated = await invoke<GameEntry>('record_launch', { id: game.id });
+      }
+      setGames((prev) => prev.map((entry) => (entry.id === updated.id ? updated : entry)));
+    } catch (error) {
+      console.error(error);
+    }
+  };
+
+  const handleSetArtwork = async (kind: ArtworkKind) => {
+    if (!selectedGame) return;
+    const source = await openDialog({
+      title: kind === 'cover' ? 'Select cover image' : 'Select background image',
+      multiple: false,
+      filters: [{ name: 'Images', extensions: ['png', 'jpg', 'jpeg', 'gif', 'webp', 'bmp'] }],
+    });
+    if (typeof source !== 'string') return;
+    try {
+      const updated = await invoke<GameEntry>('set_game_artwork', { id: selectedGame.id, kind, source });
+      setGames((prev) => prev.map((game) => (game.id === updated.id ? updated : game)));
+    } catch (error) {
+      console.error(error);
+    }
+  };
+
+  const handleExtractIcon = async () => {
+    if (!selectedGame) return;
+    try {
+      const iconPath = await invoke<string>('extract_icon', { gameId: selectedGame.id });
+      setGames((prev) => prev.map((game) => (game.id === selectedGame.id ? { ...game, iconPath } : game)));
+    } catch (error) {
+      const failure = (error as CommandError<IconError>).details;
+      if (failure?.kind === 'missing-executable') {
+        window.alert(`The executable ${failure.path} no longer exists.`);
+      } else if (failure?.kind === 'no-icon') {
+        window.alert(`${failure.path} has no icon.`);
+      } else {
+        console.error(error);
+      }
+    }
+  };
+
+  const handleCreateShortcut = async () => {
+    if (!selectedGame) return;
+    try {
+      const path = await invoke<string>('create_shortcut', { gameId: selectedGame.id, location: 'desktop' });
+      window.alert(`Shortcut created at ${path}`);
+    } catch (error) {
+      window.alert((error as CommandError).message);
+    }
+  };
+
+  const handleFindMetadata = async () => {
+    if (!selectedGame) return;
+    try {
+      setMetadataCandidates(await invoke<MetadataCandidate[]>('fetch_metadata', { query: selectedGame.title }));
+    } catch (error) {
+      const { code } = error as CommandError;
+      if (code === 'not-configured') {
+        setMetadataAvailable(false);
+      } else if (code === 'rate-limited') {
+        window.alert('The metadata service is busy, try again in a moment.');
+      } else {
+        console.error(error);
+      }
+    }
+  };
+
+  const handleApplyMetadata = async (candidate: MetadataCandidate) => {
+    if (!selectedGame) return;
+    try {
+      const updated = await invoke<GameEntry>('apply_metadata', { gameId: selectedGame.id, candidate });
+      setGames((prev) => prev.map((game) => (game.id === updated.id ? updated : game)));
+      setMetadataCandidates([]);
+    } catch (error) {
+      console.error(error);
+    }
+  };
+
   const handleScanSize = async () => {
     if (!selectedGame) return;
     const target = selectedGame.installPath ?? selectedGame.archivePath;
     if (!target) return;
     try {
-      const size = await invoke<number>('scan_path_size', { path: target });
-      const payload: GamePayload = { ...toPayload(selectedGame), sizeOverride: size };
+      const { total, cancelled } = await scanPathSize(target);
+      if (cancelled) return;
+      const payload: GamePayload = { ...toPayload(selectedGame), sizeOverride: total };
       const updated = await invoke<GameEntry>('update_game', { id: selectedGame.id, payload });
       setGames((prev) => prev.map((game) => (game.id === updated.id ? updated : game)));
     } catch (error) {
@@ -327,7 +538,9 @@ export default function App() {
                     game={game}
                     isSelected={selectedGameId === game.id}
                     onSelect={() => setSelectedGameId(game.id)}
-                    onPlay={() => openPath(game.executablePath ?? game.installPath ?? game.archivePath)}
+                    onPlay={() => launchGame(game, game.executablePath ?? game.installPath ?? game.archivePath)}
+                    onStop={() => invoke('stop_game', { id: game.id, force: false }).catch(console.error)}
+                    isRunning={runningGameIds.has(game.id)}
                   />
                 ))}
               </div>
@@ -397,6 +610,9 @@ export default function App() {
                         <button className="ghost-button" onClick={() => openPath(selectedGame.installPath)}>
                           Open folder
                         </button>
+                        <button className="ghost-button" onClick={() => openTerminal(selectedGame.installPath)}>
+                          Open terminal
+                        </button>
                       </li>
                     ) : null}
                     {selectedGame.archivePath ? (
@@ -410,7 +626,7 @@ export default function App() {
                     {selectedGame.executablePath ? (
                       <li>
                         <span>Executable</span>
-                        <button className="primary-button" onClick={() => openPath(selectedGame.executablePath)}>
+                        <button className="primary-button" onClick={() => launchGame(selectedGame, selectedGame.executablePath)}>
                           Launch
                         </button>
                       </li>
@@ -418,6 +634,30 @@ export default function App() {
                   </ul>
                 </div>
 
+                {selectedGame.description ? (
+                  <div className="details-section">
+                    <h3>About{selectedGame.releaseYear ? ` · ${selectedGame.releaseYear}` : ''}</h3>
+                    {selectedGame.developer || selectedGame.publisher ? (
+                      <p className="muted">
+                        {[selectedGame.developer, selectedGame.publisher].filter(Boolean).join(' / ')}
+                      </p>
+                    ) : null}
+                    <p className="notes-block">{selectedGame.description}</p>
+                  </div>
+                ) : null}
+
+                {metadataCandidates.length ? (
+                  <div className="details-section">
+                    <h3>Matches</h3>
+                    {metadataCandidates.map((candidate) => (
+                      <button key={candidate.sourceId} className="ghost-button" onClick={() => handleApplyMetadata(candidate)}>
+                        {candidate.title}
+                        {candidate.year ? ` (${candidate.year})` : ''}
+                      </button>
+                    ))}
+                  </div>
+                ) : null}
+
                 {selectedGame.notes ? (
                   <div className="details-section">
                     <h3>Notes</h3>
@@ -438,6 +678,27 @@ export default function App() {
                   <button className="ghost-button" onClick={handleScanSize}>
                     Rescan size
                   </button>
+                  <button className="ghost-button" onClick={() => handleSetArtwork('cover')}>
+                    Set cover
+                  </button>
+                  <button className="ghost-button" onClick={() => handleSetArtwork('background')}>
+                    Set background
+                  </button>
+                  {selectedGame.executablePath ? (
+                    <button className="ghost-button" onClick={handleExtractIcon}>
+                      Use executable icon
+                    </button>
+                  ) : null}
+                  {selectedGame.executablePath ? (
+                    <button className="ghost-button" onClick={handleCreateShortcut}>
+                      Desktop shortcut
+                    </button>
+                  ) : null}
+                  {metadataAvailable ? (
+                    <button className="ghost-button" onClick={handleFindMetadata}>
+                      Find metadata
+                    </button>
+                  ) : null}
                 </div>
               </div>
             ) : (
@@ -449,8 +710,24 @@ export default function App() {
         </section>
       </main>
 
-      <AddGameModal open={modalOpen} onClose={() => setModalOpen(false)} onSubmit={handleSubmitGame} initialData={modalGame ?? undefined} />
-      <DownloadManager open={downloadsOpen} onClose={() => setDownloadsOpen(false)} downloads={downloads} onStartDownload={handleStartDownload} />
+      <AddGameModal
+        open={modalOpen}
+        onClose={() => {
+          setModalOpen(false);
+          setDeepLinkAdd(null);
+        }}
+        onSubmit={handleSubmitGame}
+        initialData={modalGame ?? undefined}
+        initialTitle={deepLinkAdd?.title}
+      />
+      <DownloadManager
+        open={downloadsOpen}
+        onClose={() => setDownloadsOpen(false)}
+        downloads={downloads}
+        queueProgress={queueProgress}
+        onStartDownload={handleStartDownload}
+        initialUrl={deepLinkAdd?.url}
+      />
     </div>
   );
 }
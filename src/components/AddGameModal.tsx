@@ -1,25 +1,42 @@
 import { FormEvent, useEffect, useMemo, useState } from 'react';
 import { open as openDialog } from '@tauri-apps/api/dialog';
 import { invoke } from '@tauri-apps/api/tauri';
-import { GameEntry, GamePayload, InstallStatus, formatBytes } from '../lib/types';
+import { Completion, ExecutableCandidate, GameEntry, GamePayload, InstallStatus, formatBytes } from '../lib/types';
+import { scanPathSize } from '../lib/sizeScan';
 
 interface AddGameModalProps {
   open: boolean;
   onClose: () => void;
   onSubmit: (payload: GamePayload, id?: string) => Promise<void>;
   initialData?: GameEntry | null;
+  /** Title a new game starts with, such as one passed in a deep link. */
+  initialTitle?: string;
 }
 
 type FormState = {
   title: string;
+  sortTitle?: string;
+  alternateTitles: string;
   version?: string;
+  developer?: string;
+  publisher?: string;
+  releaseYear?: number;
+  description?: string;
   archivePath?: string;
   installPath?: string;
   executablePath?: string;
+  launchArgs?: string;
+  workingDir?: string;
+  env: string;
+  wrapperCommand?: string;
+  runElevated: boolean;
   repacker?: string;
   tags: string;
   status: InstallStatus;
+  rating?: number;
+  completion?: Completion;
   notes?: string;
+  customFields: string;
   checksum?: string;
   color?: string;
   sizeOverride?: number;
@@ -27,44 +44,86 @@ type FormState = {
 
 const defaultState: FormState = {
   title: '',
+  alternateTitles: '',
   status: 'not-installed',
   tags: '',
+  env: '',
+  runElevated: false,
+  customFields: '',
 };
 
-export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameModalProps) {
+const formatPairs = (pairs?: Record<string, string>) =>
+  Object.entries(pairs ?? {})
+    .map(([name, value]) => `${name}=${value}`)
+    .join('\n');
+
+const parsePairs = (text: string): Record<string, string> =>
+  Object.fromEntries(
+    text
+      .split('\n')
+      .map((line) => line.trim())
+      .filter(Boolean)
+      .map((line) => {
+        const separator = line.indexOf('=');
+        return separator < 0 ? [line, ''] : [line.slice(0, separator).trim(), line.slice(separator + 1)];
+      })
+  );
+
+export function AddGameModal({ open, onClose, onSubmit, initialData, initialTitle }: AddGameModalProps) {
   const [form, setForm] = useState<FormState>(defaultState);
   const [saving, setSaving] = useState(false);
   const [sizePreview, setSizePreview] = useState<number | undefined>(undefined);
+  const [repackers, setRepackers] = useState<string[]>([]);
+  const [executables, setExecutables] = useState<ExecutableCandidate[]>([]);
 
   useEffect(() => {
     if (!open) {
       setForm(defaultState);
       setSizePreview(undefined);
+      setExecutables([]);
       setSaving(false);
       return;
     }
 
+    invoke<string[]>('list_repackers')
+      .then(setRepackers)
+      .catch((error) => console.error('Failed to list repackers', error));
+
     if (initialData) {
       setForm({
         title: initialData.title,
+        sortTitle: initialData.sortTitle,
+        alternateTitles: initialData.alternateTitles?.join('\n') ?? '',
         version: initialData.version,
+        developer: initialData.developer,
+        publisher: initialData.publisher,
+        releaseYear: initialData.releaseYear,
+        description: initialData.description,
         archivePath: initialData.archivePath,
         installPath: initialData.installPath,
         executablePath: initialData.executablePath,
+        launchArgs: initialData.launchArgs,
+        workingDir: initialData.workingDir,
+        env: formatPairs(initialData.env),
+        wrapperCommand: initialData.wrapperCommand,
+        runElevated: initialData.runElevated ?? false,
         repacker: initialData.repacker,
         tags: initialData.tags?.join(', ') ?? '',
         status: initialData.status,
+        rating: initialData.rating,
+        completion: initialData.completion,
         notes: initialData.notes,
+        customFields: formatPairs(initialData.customFields),
         checksum: initialData.checksum,
         color: initialData.color,
         sizeOverride: initialData.sizeBytes,
       });
       setSizePreview(initialData.sizeBytes);
     } else {
-      setForm(defaultState);
+      setForm({ ...defaultState, title: initialTitle ?? '' });
       setSizePreview(undefined);
     }
-  }, [open, initialData]);
+  }, [open, initialData, initialTitle]);
 
   const parsedTags = useMemo(
     () =>
@@ -115,11 +174,28 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
     }
   };
 
+  const handleDetectExecutables = async () => {
+    if (!form.installPath) return;
+    try {
+      const found = await invoke<ExecutableCandidate[]>('list_executables', {
+        path: form.installPath,
+        title: form.title.trim() || undefined,
+      });
+      setExecutables(found);
+      if (found.length && !form.executablePath) {
+        updateField('executablePath', found[0].path);
+      }
+    } catch (error) {
+      console.error('Failed to list executables', error);
+    }
+  };
+
   const refreshSize = async (path: string) => {
     try {
-      const size = await invoke<number>('scan_path_size', { path });
-      setSizePreview(size);
-      updateField('sizeOverride', size);
+      const { total, cancelled } = await scanPathSize(path);
+      if (cancelled) return;
+      setSizePreview(total);
+      updateField('sizeOverride', total);
     } catch (error) {
       console.error('Failed to read size', error);
     }
@@ -133,17 +209,39 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
 
     const payload: GamePayload = {
       title: form.title.trim(),
+      sortTitle: form.sortTitle?.trim() || undefined,
+      alternateTitles: form.alternateTitles
+        .split('\n')
+        .map((title) => title.trim())
+        .filter(Boolean),
       version: form.version?.trim() || undefined,
+      developer: form.developer?.trim() || undefined,
+      publisher: form.publisher?.trim() || undefined,
+      releaseYear: form.releaseYear || undefined,
+      description: form.description?.trim() || undefined,
       archivePath: form.archivePath,
       installPath: form.installPath,
       executablePath: form.executablePath,
+      launchArgs: form.launchArgs?.trim() || undefined,
+      workingDir: form.workingDir?.trim() || undefined,
+      env: parsePairs(form.env),
+      wrapperCommand: form.wrapperCommand?.trim() || undefined,
+      runElevated: form.runElevated,
       repacker: form.repacker?.trim() || undefined,
       tags: parsedTags,
       status: form.status,
+      rating: form.rating,
+      completion: form.completion,
       notes: form.notes?.trim() || undefined,
+      // Fields are merged on update, so removed ones are sent empty.
+      customFields: {
+        ...Object.fromEntries(Object.keys(initialData?.customFields ?? {}).map((key) => [key, ''])),
+        ...parsePairs(form.customFields),
+      },
       checksum: form.checksum?.trim() || undefined,
       color: form.color?.trim() || undefined,
       sizeOverride: sizePreview,
+      favorite: initialData?.favorite ?? false,
     };
 
     try {
@@ -197,8 +295,67 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
                 placeholder="1.0.0, Update 2, etc"
               />
             </label>
+            <label>
+              <span>Release year</span>
+              <input
+                type="number"
+                value={form.releaseYear ?? ''}
+                onChange={(event) => updateField('releaseYear', event.target.value ? Number(event.target.value) : undefined)}
+                placeholder="2015"
+              />
+            </label>
           </div>
 
+          <div className="field-group">
+            <label>
+              <span>Developer</span>
+              <input
+                type="text"
+                value={form.developer ?? ''}
+                onChange={(event) => updateField('developer', event.target.value)}
+              />
+            </label>
+            <label>
+              <span>Publisher</span>
+              <input
+                type="text"
+                value={form.publisher ?? ''}
+                onChange={(event) => updateField('publisher', event.target.value)}
+              />
+            </label>
+          </div>
+
+          <div className="field-group">
+            <label>
+              <span>Sort as</span>
+              <input
+                type="text"
+                value={form.sortTitle ?? ''}
+                onChange={(event) => updateField('sortTitle', event.target.value)}
+                placeholder="Defaults to the title without The, A or An"
+              />
+            </label>
+            <label>
+              <span>Also known as</span>
+              <textarea
+                rows={2}
+                value={form.alternateTitles}
+                onChange={(event) => updateField('alternateTitles', event.target.value)}
+                placeholder="One title per line"
+              />
+            </label>
+          </div>
+
+          <label>
+            <span>Description</span>
+            <textarea
+              rows={3}
+              value={form.description ?? ''}
+              onChange={(event) => updateField('description', event.target.value)}
+              placeholder="What the game is about"
+            />
+          </label>
+
           <div className="field-group">
             <label>
               <span>Archive (FitGirl repack)</span>
@@ -228,7 +385,25 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
                 <button type="button" className="ghost-button" onClick={handlePickExecutable}>
                   Browse
                 </button>
+                {form.installPath && (
+                  <button type="button" className="ghost-button" onClick={handleDetectExecutables}>
+                    Detect
+                  </button>
+                )}
               </div>
+              {executables.length > 0 && (
+                <select
+                  value={form.executablePath ?? ''}
+                  onChange={(event) => updateField('executablePath', event.target.value)}
+                >
+                  {executables.map((candidate) => (
+                    <option key={candidate.path} value={candidate.path}>
+                      {candidate.relativePath} · {formatBytes(candidate.sizeBytes)}
+                      {candidate.productName ? ` · ${candidate.productName}` : ''}
+                    </option>
+                  ))}
+                </select>
+              )}
             </label>
             <label>
               <span>Repacker</span>
@@ -237,6 +412,63 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
                 value={form.repacker ?? ''}
                 onChange={(event) => updateField('repacker', event.target.value)}
                 placeholder="FitGirl, DODI, etc"
+                list="repacker-suggestions"
+              />
+              <datalist id="repacker-suggestions">
+                {repackers.map((repacker) => (
+                  <option key={repacker} value={repacker} />
+                ))}
+              </datalist>
+            </label>
+          </div>
+
+          <div className="field-group">
+            <label>
+              <span>Launch arguments</span>
+              <input
+                type="text"
+                value={form.launchArgs ?? ''}
+                onChange={(event) => updateField('launchArgs', event.target.value)}
+                placeholder="-skipintro -windowed"
+              />
+            </label>
+            <label>
+              <span>Working folder</span>
+              <input
+                type="text"
+                value={form.workingDir ?? ''}
+                onChange={(event) => updateField('workingDir', event.target.value)}
+                placeholder="Defaults to the executable's folder"
+              />
+            </label>
+          </div>
+
+          <label>
+            <span>Environment variables</span>
+            <textarea
+              rows={2}
+              value={form.env}
+              onChange={(event) => updateField('env', event.target.value)}
+              placeholder={'DXVK_HUD=fps\nWINEPREFIX=  (an empty value unsets it)'}
+            />
+          </label>
+
+          <div className="field-group">
+            <label>
+              <span>Wrapper command</span>
+              <input
+                type="text"
+                value={form.wrapperCommand ?? ''}
+                onChange={(event) => updateField('wrapperCommand', event.target.value)}
+                placeholder="gamemoderun %command%"
+              />
+            </label>
+            <label>
+              <span>Run as administrator</span>
+              <input
+                type="checkbox"
+                checked={form.runElevated}
+                onChange={(event) => updateField('runElevated', event.target.checked)}
               />
             </label>
           </div>
@@ -261,6 +493,34 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
             </select>
           </label>
 
+          <div className="field-group">
+            <label>
+              <span>Rating</span>
+              <input
+                type="number"
+                min={0}
+                max={10}
+                value={form.rating ?? ''}
+                onChange={(event) => updateField('rating', event.target.value ? Number(event.target.value) : undefined)}
+                placeholder="0 – 10"
+              />
+            </label>
+            <label>
+              <span>Completion</span>
+              <select
+                value={form.completion ?? ''}
+                onChange={(event) => updateField('completion', (event.target.value || undefined) as Completion | undefined)}
+              >
+                <option value="">Not tracked</option>
+                <option value="not-started">Not started</option>
+                <option value="playing">Playing</option>
+                <option value="on-hold">On hold</option>
+                <option value="completed">Completed</option>
+                <option value="abandoned">Abandoned</option>
+              </select>
+            </label>
+          </div>
+
           <div className="field-group">
             <label>
               <span>Accent color</span>
@@ -287,6 +547,16 @@ export function AddGameModal({ open, onClose, onSubmit, initialData }: AddGameMo
             />
           </label>
 
+          <label>
+            <span>Custom fields</span>
+            <textarea
+              rows={3}
+              value={form.customFields}
+              onChange={(event) => updateField('customFields', event.target.value)}
+              placeholder={'Save location=%APPDATA%\\Game\nForum thread=https://…'}
+            />
+          </label>
+
           <div className="size-preview">
             <span>Detected size:</span>
             <strong>{formatBytes(sizePreview)}</strong>
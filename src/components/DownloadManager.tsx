@@ -1,20 +1,37 @@
-import { FormEvent, useState } from 'react';
+import { FormEvent, useEffect, useState } from 'react';
 import { open as openDialog } from '@tauri-apps/api/dialog';
-import { DownloadTask, formatBytes } from '../lib/types';
+import { DownloadTask, QueueProgress, formatBytes } from '../lib/types';
 
 interface DownloadManagerProps {
   open: boolean;
   onClose: () => void;
   downloads: DownloadTask[];
-  onStartDownload: (payload: { url: string; destination: string; fileName?: string }) => Promise<void>;
+  /** Combined progress of the running downloads, as last reported. */
+  queueProgress?: QueueProgress | null;
+  onStartDownload: (payload: { url: string; destination?: string; fileName?: string }) => Promise<void>;
+  /** Pre-fills the url field, such as from a deep link. */
+  initialUrl?: string;
 }
 
-export function DownloadManager({ open, onClose, downloads, onStartDownload }: DownloadManagerProps) {
+export function DownloadManager({
+  open,
+  onClose,
+  downloads,
+  queueProgress,
+  onStartDownload,
+  initialUrl,
+}: DownloadManagerProps) {
   const [url, setUrl] = useState('');
   const [destination, setDestination] = useState('');
   const [fileName, setFileName] = useState('');
   const [busy, setBusy] = useState(false);
 
+  useEffect(() => {
+    if (initialUrl) {
+      setUrl(initialUrl);
+    }
+  }, [initialUrl]);
+
   const pickDestination = async () => {
     const result = await openDialog({ title: 'Select destination folder', directory: true });
     if (typeof result === 'string') {
@@ -24,11 +41,15 @@ export function DownloadManager({ open, onClose, downloads, onStartDownload }: D
 
   const handleSubmit = async (event: FormEvent<HTMLFormElement>) => {
     event.preventDefault();
-    if (!url.trim() || !destination.trim()) return;
+    if (!url.trim()) return;
 
     setBusy(true);
     try {
-      await onStartDownload({ url: url.trim(), destination: destination.trim(), fileName: fileName.trim() || undefined });
+      await onStartDownload({
+        url: url.trim(),
+        destination: destination.trim() || undefined,
+        fileName: fileName.trim() || undefined,
+      });
       setUrl('');
       setFileName('');
     } catch (error) {
@@ -43,7 +64,15 @@ export function DownloadManager({ open, onClose, downloads, onStartDownload }: D
       <header className="download-drawer__header">
         <div>
           <h2>Download Manager</h2>
-          <p className="muted">Queue and monitor repack downloads in one place.</p>
+          <p className="muted">
+            {queueProgress && queueProgress.active + queueProgress.queued > 0
+              ? `${queueProgress.active} active, ${queueProgress.queued} queued — ${
+                  queueProgress.totalBytes > 0
+                    ? Math.floor((queueProgress.processedBytes / queueProgress.totalBytes) * 100)
+                    : 0
+                }% at ${formatBytes(queueProgress.bytesPerSecond)}/s`
+              : 'Queue and monitor repack downloads in one place.'}
+          </p>
         </div>
         <button className="ghost-button" onClick={onClose} type="button">
           Close
@@ -65,7 +94,12 @@ export function DownloadManager({ open, onClose, downloads, onStartDownload }: D
           <label>
             <span>Destination folder</span>
             <div className="with-button">
-              <input type="text" value={destination} onChange={(event) => setDestination(event.target.value)} required />
+              <input
+                type="text"
+                value={destination}
+                placeholder="Default download folder"
+                onChange={(event) => setDestination(event.target.value)}
+              />
               <button type="button" className="ghost-button" onClick={pickDestination}>
                 Browse
               </button>
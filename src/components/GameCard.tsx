@@ -1,3 +1,4 @@
+import { convertFileSrc } from '@tauri-apps/api/tauri';
 import { clsx } from 'clsx';
 import { GameEntry, formatBytes, statusPreset } from '../lib/types';
 
@@ -6,11 +7,13 @@ interface GameCardProps {
   isSelected: boolean;
   onSelect: () => void;
   onPlay?: () => void;
+  onStop?: () => void;
+  isRunning?: boolean;
 }
 
 const fallbackGradient = 'linear-gradient(135deg, rgba(203, 166, 247, 0.25), rgba(148, 226, 213, 0.15))';
 
-export function GameCard({ game, isSelected, onSelect, onPlay }: GameCardProps) {
+export function GameCard({ game, isSelected, onSelect, onPlay, onStop, isRunning = false }: GameCardProps) {
   const status = statusPreset[game.status];
 
   return (
@@ -18,7 +21,9 @@ export function GameCard({ game, isSelected, onSelect, onPlay }: GameCardProps)
       className={clsx('game-card', { selected: isSelected })}
       onClick={onSelect}
       style={{
-        background: fallbackGradient,
+        background: game.coverPath
+          ? `linear-gradient(180deg, rgba(17, 17, 27, 0.35), rgba(17, 17, 27, 0.9)), center / cover no-repeat url("${convertFileSrc(game.coverPath)}")`
+          : fallbackGradient,
         borderColor: game.color ?? 'rgba(203, 166, 247, 0.25)',
       }}
     >
@@ -30,7 +35,10 @@ export function GameCard({ game, isSelected, onSelect, onPlay }: GameCardProps)
       </header>
 
       <div className="game-card__body">
-        <h3>{game.title}</h3>
+        <h3>
+          {game.iconPath ? <img className="game-card__icon" src={convertFileSrc(game.iconPath)} alt="" /> : null}
+          {game.title}
+        </h3>
         {game.repacker ? <p className="muted">Repacker · {game.repacker}</p> : null}
         {game.sizeBytes ? <p className="muted">Size · {formatBytes(game.sizeBytes)}</p> : null}
         {game.tags?.length ? (
@@ -47,10 +55,14 @@ export function GameCard({ game, isSelected, onSelect, onPlay }: GameCardProps)
           className="ghost-button"
           onClick={(event) => {
             event.stopPropagation();
-            onPlay?.();
+            if (isRunning) {
+              onStop?.();
+            } else {
+              onPlay?.();
+            }
           }}
         >
-          Launch
+          {isRunning ? 'Stop' : 'Launch'}
         </button>
       </footer>
     </article>
@@ -0,0 +1,35 @@
+import { invoke } from '@tauri-apps/api/tauri';
+import { listen } from '@tauri-apps/api/event';
+import { ScanComplete, ScanProgress } from './types';
+
+/** Measures `path` through `start_size_scan`, resolving once the scan ends. */
+export const scanPathSize = async (
+  path: string,
+  onProgress?: (progress: ScanProgress) => void,
+): Promise<ScanComplete> => {
+  let jobId: string | undefined;
+  const finished = new Map<string, ScanComplete>();
+  let resolve: (complete: ScanComplete) => void = () => {};
+  const done = new Promise<ScanComplete>((settle) => {
+    resolve = settle;
+  });
+
+  // Listening starts before the scan so that no event of a fast one is missed.
+  const unlistenProgress = await listen<ScanProgress>('scan-progress', ({ payload }) => {
+    if (payload.jobId === jobId) onProgress?.(payload);
+  });
+  const unlistenComplete = await listen<ScanComplete>('scan-complete', ({ payload }) => {
+    if (payload.jobId === jobId) resolve(payload);
+    else finished.set(payload.jobId, payload);
+  });
+
+  try {
+    jobId = await invoke<string>('start_size_scan', { path });
+    const early = finished.get(jobId);
+    if (early) resolve(early);
+    return await done;
+  } finally {
+    unlistenProgress();
+    unlistenComplete();
+  }
+};
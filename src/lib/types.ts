@@ -1,25 +1,286 @@
 export type InstallStatus = 'not-installed' | 'downloading' | 'installed' | 'archived';
 
+export type Completion = 'not-started' | 'playing' | 'completed' | 'abandoned' | 'on-hold';
+
 export interface GamePayload {
   title: string;
+  sortTitle?: string;
+  alternateTitles?: string[];
   version?: string;
+  developer?: string;
+  publisher?: string;
+  releaseYear?: number;
+  description?: string;
   archivePath?: string;
   installPath?: string;
   executablePath?: string;
+  launchArgs?: string;
+  workingDir?: string;
+  env?: Record<string, string>;
+  wrapperCommand?: string;
+  runElevated?: boolean;
   repacker?: string;
   tags: string[];
   status: InstallStatus;
+  rating?: number;
+  completion?: Completion;
   notes?: string;
+  customFields?: Record<string, string>;
   checksum?: string;
   color?: string;
-  sizeOverride?: number;
+  sizeOverride?: number | null;
+  favorite?: boolean;
+  allowDuplicate?: boolean;
 }
 
-export interface GameEntry extends Omit<GamePayload, 'sizeOverride'> {
+export type GamePatch = {
+  [K in Exclude<keyof GamePayload, 'allowDuplicate'>]?: GamePayload[K] | null;
+} & {
+  addTags?: string[];
+  removeTags?: string[];
+};
+
+export interface GameEntry extends Omit<GamePayload, 'sizeOverride' | 'favorite' | 'allowDuplicate'> {
   id: string;
+  favorite: boolean;
+  coverPath?: string;
+  backgroundPath?: string;
+  iconPath?: string;
   sizeBytes?: number;
+  downloadId?: string;
+  statusBeforeDownload?: InstallStatus;
   addedAt: string;
   updatedAt: string;
+  lastPlayedAt?: string;
+  playCount: number;
+  totalPlaytimeSeconds: number;
+  deletedAt?: string;
+}
+
+export interface Collection {
+  id: string;
+  name: string;
+  gameIds: string[];
+  color?: string;
+  icon?: string;
+  createdAt: string;
+}
+
+export type TagPolicy = 'preserve' | 'lowercase' | 'first-seen-casing';
+
+export interface TagMigration {
+  gamesChanged: number;
+  tagsCollapsed: number;
+}
+
+export interface TagUsage {
+  tag: string;
+  count: number;
+}
+
+export interface StatusCounts {
+  notInstalled: number;
+  downloading: number;
+  installed: number;
+  archived: number;
+}
+
+export interface RepackerStats {
+  repacker: string;
+  gameCount: number;
+  totalSizeBytes: number;
+  byStatus: StatusCounts;
+}
+
+export interface LibraryStats {
+  totalGames: number;
+  byStatus: StatusCounts;
+  installedSizeBytes: number;
+  archivedSizeBytes: number;
+  unknownSizeCount: number;
+  largest: { id: string; title: string; sizeBytes: number }[];
+  untaggedCount: number;
+  addedPerMonth: { month: string; count: number }[];
+}
+
+export interface GameProblems {
+  gameId: string;
+  title: string;
+  missingArchivePath?: string;
+  missingInstallPath?: string;
+  missingExecutablePath?: string;
+  executableNotAFile: boolean;
+  staleSize?: { recorded: number; measured: number };
+}
+
+export interface ValidationReport {
+  checked: number;
+  problems: GameProblems[];
+  fixed: string[];
+}
+
+export interface ValidationProgress {
+  gameId: string;
+  checked: number;
+  total: number;
+}
+
+export interface SizeRefreshSummary {
+  updated: number;
+  totalBytes: number;
+  missing: string[];
+  cancelled: boolean;
+}
+
+export interface ScanProgress {
+  jobId: string;
+  filesSeen: number;
+  bytes: number;
+  currentDir: string;
+}
+
+export interface ScanComplete {
+  jobId: string;
+  total: number;
+  filesSeen: number;
+  denied: number;
+  unreadable: number;
+  cancelled: boolean;
+}
+
+export interface PathSize {
+  sizeBytes?: number;
+  error?: string;
+  within?: string;
+}
+
+export interface PathsScanComplete {
+  jobId: string;
+  sizes: Record<string, PathSize>;
+  totalBytes: number;
+  cancelled: boolean;
+}
+
+export interface FolderUsage {
+  path: string;
+  sizeBytes: number;
+  fileCount: number;
+  children: FolderUsage[];
+}
+
+export interface PathAnalysis {
+  jobId: string;
+  root: FolderUsage;
+  largestFiles: { path: string; sizeBytes: number }[];
+  symlinks: string[];
+  denied: number;
+  unreadable: number;
+  cancelled: boolean;
+}
+
+export interface RelocationProposal {
+  gameId: string;
+  title: string;
+  oldInstallPath: string;
+  candidates: { installPath: string; executablePath?: string }[];
+}
+
+export interface RelocationProgress {
+  root: string;
+  foldersScanned: number;
+  candidatesFound: number;
+}
+
+export interface ExecutableCandidate {
+  path: string;
+  relativePath: string;
+  sizeBytes: number;
+  productName?: string;
+}
+
+export type ArtworkKind = 'cover' | 'background' | 'icon';
+
+export interface MetadataCandidate {
+  sourceId: number;
+  title: string;
+  year?: number;
+  developer?: string;
+  publisher?: string;
+  coverUrl?: string;
+  description?: string;
+}
+
+export type MetadataError =
+  | { kind: 'not-configured' }
+  | { kind: 'unauthorized' }
+  | { kind: 'rate-limited'; retryAfterSeconds?: number }
+  | { kind: 'api'; status: number; message: string }
+  | { kind: 'failed'; message: string };
+
+export interface DeepLinkAddPayload {
+  url: string;
+  title?: string;
+}
+
+export interface DeepLinkErrorPayload {
+  url: string;
+  message: string;
+}
+
+export type ErrorCode =
+  | 'not-found'
+  | 'already-exists'
+  | 'invalid-input'
+  | 'conflict'
+  | 'permission-denied'
+  | 'insufficient-space'
+  | 'io'
+  | 'network'
+  | 'checksum-mismatch'
+  | 'not-configured'
+  | 'rate-limited'
+  | 'unsupported'
+  | 'password-required'
+  | 'credentials-required'
+  | 'internal';
+
+export interface CommandError<Details = unknown> {
+  code: ErrorCode;
+  message: string;
+  details?: Details;
+}
+
+export type IconError =
+  | { kind: 'no-executable' }
+  | { kind: 'missing-executable'; path: string }
+  | { kind: 'no-icon'; path: string }
+  | { kind: 'failed'; message: string };
+
+export type DuplicateField = 'title' | 'install-path' | 'archive-path';
+
+export type AddGameResult =
+  | { outcome: 'added'; game: GameEntry }
+  | { outcome: 'duplicate'; existingId: string; field: DuplicateField };
+
+export interface PlaySession {
+  gameId: string;
+  startedAt: string;
+  endedAt: string;
+  durationSeconds: number;
+}
+
+export interface GameProcessPayload {
+  gameId: string;
+  pid?: number;
+}
+
+export interface GameSizeUpdatedPayload {
+  id: string;
+  sizeBytes: number;
+}
+
+export interface GameSessionEndedPayload extends PlaySession {
+  totalPlaytimeSeconds: number;
 }
 
 export interface DownloadTask {
@@ -34,6 +295,16 @@ export interface DownloadTask {
   error?: string;
 }
 
+export interface QueueProgress {
+  active: number;
+  queued: number;
+  completed: number;
+  failed: number;
+  totalBytes: number;
+  processedBytes: number;
+  bytesPerSecond: number;
+}
+
 export interface DownloadProgressPayload {
   id: string;
   processed: number;
@@ -51,6 +322,72 @@ export interface DownloadErrorPayload {
   id: string;
   fileName: string;
   message: string;
+  code: ErrorCode;
+}
+
+export interface ExtractProgress {
+  id: string;
+  gameId?: string;
+  processedBytes: number;
+  totalBytes: number;
+  currentFile: string;
+}
+
+export interface ExtractComplete {
+  id: string;
+  gameId?: string;
+  destination: string;
+}
+
+export interface ExtractError {
+  id: string;
+  gameId?: string;
+  message: string;
+  code: ErrorCode;
+  cancelled: boolean;
+}
+
+export interface ExtractionSupport {
+  formats: string[];
+  rarTool?: string;
+  rarHint?: string;
+}
+
+export interface Volume {
+  mountPoint: string;
+  label: string | null;
+  fileSystem: string;
+  totalBytes: number;
+  availableBytes: number;
+  removable: boolean;
+}
+
+export type Theme = 'dark' | 'light' | 'system';
+
+export interface Settings {
+  defaultDownloadDir: string | null;
+  repackerDownloadDirs: Record<string, string>;
+  gameFolders: string[];
+  downloadConcurrency: number;
+  downloadSpeedLimit: number | null;
+  allowInvalidCerts: boolean;
+  postDownloadHook: string | null;
+  downloadNotifications: boolean;
+  closeToTray: boolean;
+  followGameProcesses: boolean;
+  theme: Theme;
+  allowArbitraryPaths: boolean;
+  terminalCommand: string | null;
+}
+
+export type SettingsPatch = Partial<Settings>;
+
+export type ArchiveAction = { kind: 'delete' } | { kind: 'move'; destination: string };
+
+export interface MoveProgress {
+  gameId: string;
+  copiedBytes: number;
+  totalBytes: number;
 }
 
 export const statusPreset: Record<InstallStatus, { label: string; hue: string }>